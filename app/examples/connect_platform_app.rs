@@ -27,7 +27,7 @@ impl AppConnectDemo {
 
 impl APP for AppConnectDemo {
     fn get_msg(&self, sensor_name: String, value: SensorData) {
-        todo!()
+        println!("[{}]: {:?}", sensor_name, value);
     }
 
     fn config_app(&mut self) {
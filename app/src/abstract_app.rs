@@ -1,11 +1,13 @@
 use std::any::type_name;
 use std::sync::{Arc, RwLock};
 
+use log::info;
+
 use common::structs::sensor_data::SensorData;
 use common::SyncString;
 
-use crate::abstract_app::get_value_thread::RwLockOptionGetValueThread;
-use crate::app::APP;
+use crate::abstract_app::get_value_thread::{GetValueThread, RwLockOptionGetValueThread};
+use crate::app::{WeakAbstractApp, APP};
 
 pub mod get_value_thread;
 
@@ -51,16 +53,46 @@ impl APP for AbstractApp {
         self
     }
 
-    fn start_get_value_thread(&self, udp_port: u16) {
-        todo!()
+    /// start get value thread
+    /// a running thread is stopped first, so register back listens on the new port
+    fn start_get_value_thread(&self, app: WeakAbstractApp, udp_port: u16) {
+        self.stop_get_value_thread();
+        info!(
+            "[{}]: start get value thread on {}",
+            self.app_name, udp_port
+        );
+        *self
+            .get_value_thread
+            .write()
+            .expect("write get value thread fail") = Some(GetValueThread::new(app, udp_port));
     }
 
+    /// stop get value thread
+    /// nothing is done if thread is not started
     fn stop_get_value_thread(&self) {
-        todo!()
+        let get_value_thread = self
+            .get_value_thread
+            .write()
+            .expect("write get value thread fail")
+            .take();
+        if let Some(get_value_thread) = get_value_thread {
+            get_value_thread.stop_thread();
+            info!("[{}]: stop get value thread", self.app_name);
+        }
     }
 
+    /// wait for thread
+    /// block until get value thread is stopped, return at once if it is not started
     fn wait_for_thread(&self) {
-        todo!()
+        let handle = self
+            .get_value_thread
+            .read()
+            .expect("read get value thread fail")
+            .as_ref()
+            .and_then(|get_value_thread| get_value_thread.take_handle());
+        if let Some(handle) = handle {
+            handle.join().expect("join get value thread fail");
+        }
     }
 
     /// get_app_name
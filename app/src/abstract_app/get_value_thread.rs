@@ -1,7 +1,232 @@
-use std::sync::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+
+use log::{info, trace, warn};
+use serde_json::Value;
+
+use common::socket::udp;
+use common::structs::sensor_data::SensorData;
+
+use crate::app::WeakAbstractApp;
 
 pub type RwLockOptionGetValueThread = RwLock<Option<GetValueThread>>;
 
+pub type SyncStop = Arc<AtomicBool>;
+
+/// timeout of a udp recv in ms, the thread checks stop flag after every timeout
+pub const RECV_TIMEOUT_MS: u64 = 200;
+
+/// notion: get value thread is a thread that receives sensor data pushed by platform
+/// its mode can be seen as a consumer
+/// it listens on udp port given by register app,
+/// decodes {"sensor_name", "sensor_data"} and dispatches it to APP::get_msg
+/// struct type:
+/// {
+///   should_stop: flag checked after every recv timeout
+///   stopped: set when thread exits
+///   udp_port: port the thread listens on
+///   thread: Mutex<Option<thread::JoinHandle<()>>>, taken by whoever joins it
+/// }
+#[derive(Debug)]
 pub struct GetValueThread {
-    //todo
+    should_stop: SyncStop,
+    stopped: SyncStop,
+    udp_port: u16,
+    thread: Mutex<Option<thread::JoinHandle<()>>>,
+}
+
+impl GetValueThread {
+    /// new a get value thread
+    /// it also run the thread
+    /// thread holds a weak app, so app can be dropped while thread is running
+    pub fn new(app: WeakAbstractApp, udp_port: u16) -> Self {
+        let should_stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let join_handle = {
+            let should_stop = should_stop.clone();
+            let stopped = stopped.clone();
+            thread::Builder::new()
+                .name(format!("get value thread {}", udp_port))
+                .spawn(move || Self::run(should_stop, stopped, app, udp_port))
+                .expect("spawn get value thread fail")
+        };
+
+        Self {
+            should_stop,
+            stopped,
+            udp_port,
+            thread: Mutex::new(Some(join_handle)),
+        }
+    }
+
+    /// how thread run
+    /// recv with timeout so that stop is seen without a message from platform
+    /// it stops by itself when app is dropped
+    fn run(should_stop: SyncStop, stopped: SyncStop, app: WeakAbstractApp, udp_port: u16) {
+        info!("get value thread on udp port {} start", udp_port);
+        while !should_stop.load(Ordering::SeqCst) {
+            let msg = match udp::recv(udp_port, Some(RECV_TIMEOUT_MS)) {
+                Some(msg) => msg,
+                None => continue,
+            };
+            trace!("get value thread on udp port {} recv: {}", udp_port, msg);
+            let (sensor_name, sensor_data) = match Self::decode(&msg) {
+                Some(decoded) => decoded,
+                None => {
+                    warn!("get value thread recv an invalid msg: {}", msg);
+                    continue;
+                }
+            };
+            match app.upgrade() {
+                Some(app) => app.get_msg(sensor_name, sensor_data),
+                None => break,
+            }
+        }
+        udp::close(udp_port);
+        stopped.store(true, Ordering::SeqCst);
+        info!("get value thread on udp port {} stop", udp_port);
+    }
+
+    /// decode a msg pushed by platform
+    /// msg is {"sensor_name": name, "sensor_data": data},
+    /// data of type IncResult and InvReport keeps its type,
+    /// data that is not an object such as a failed request is put in default field
+    pub fn decode(msg: &str) -> Option<(String, SensorData)> {
+        let mut msg: Value = serde_json::from_str(msg).ok()?;
+        let sensor_name = msg.get("sensor_name")?.as_str()?.to_string();
+        let sensor_data = msg.get_mut("sensor_data")?.take();
+        let sensor_data = match serde_json::from_value(sensor_data.clone()) {
+            Ok(sensor_data) => sensor_data,
+            Err(_) => {
+                SensorData::new_with_one_field_with_default_type("default".to_string(), sensor_data)
+            }
+        };
+        Some((sensor_name, sensor_data))
+    }
+
+    //getter
+    pub fn get_udp_port(&self) -> u16 {
+        self.udp_port
+    }
+
+    /// is thread stopped
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+
+    /// take join handle
+    /// used by wait, so that the thread is joined without holding a lock
+    pub fn take_handle(&self) -> Option<thread::JoinHandle<()>> {
+        self.thread
+            .lock()
+            .expect("lock get value thread fail")
+            .take()
+    }
+
+    /// stop thread and wait for it
+    /// it is not joined if it is waited by others or stop is called in the thread itself,
+    /// e.g. in get_msg
+    pub fn stop_thread(&self) {
+        self.should_stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self
+            .take_handle()
+            .filter(|thread| thread.thread().id() != thread::current().id())
+        {
+            thread.join().expect("join get value thread fail");
+        }
+    }
+}
+
+impl Drop for GetValueThread {
+    fn drop(&mut self) {
+        self.stop_thread();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::sync::mpsc::{channel, Sender};
+    use std::time::{Duration, Instant};
+
+    use serde_json::json;
+
+    use common::structs::enumeration::sensor_data_type::SensorDataType;
+
+    use crate::abstract_app::AbstractApp;
+    use crate::app::{SyncAbstractApp, APP};
+
+    use super::*;
+
+    struct Catcher {
+        abstract_app: AbstractApp,
+        sender: Mutex<Sender<(String, SensorData)>>,
+    }
+
+    impl APP for Catcher {
+        fn get_msg(&self, sensor_name: String, value: SensorData) {
+            self.sender
+                .lock()
+                .unwrap()
+                .send((sensor_name, value))
+                .unwrap();
+        }
+
+        fn config_app(&mut self) {}
+
+        fn super_reference(&self) -> &AbstractApp {
+            &self.abstract_app
+        }
+    }
+
+    #[test]
+    fn test_decode() {
+        let msg = json!({"sensor_name": "YellowCar", "sensor_data": {"speed": 10}});
+        let (sensor_name, sensor_data) = GetValueThread::decode(&msg.to_string()).unwrap();
+        assert_eq!(sensor_name, "YellowCar");
+        assert_eq!(sensor_data.get_data("speed"), Some(&json!(10)));
+
+        let msg = json!({"sensor_name": "YellowCar",
+            "sensor_data": {"sensor_data_type": "IncResult", "rule_id": "rule_1", "link": []}});
+        let (_, sensor_data) = GetValueThread::decode(&msg.to_string()).unwrap();
+        assert_eq!(
+            sensor_data.get_sensor_data_type(),
+            SensorDataType::IncResult
+        );
+
+        let msg = json!({"sensor_name": "YellowCar", "sensor_data": "@#$%"});
+        let (_, sensor_data) = GetValueThread::decode(&msg.to_string()).unwrap();
+        println!("{:?}", sensor_data);
+        assert_eq!(sensor_data.get_default_data(), Some(&json!("@#$%")));
+
+        assert!(GetValueThread::decode(r#"{"sensor_data": {}}"#).is_none());
+    }
+
+    #[test]
+    fn test_get_value_thread() {
+        let udp_port = 34567;
+        let (sender, receiver) = channel();
+        let app: SyncAbstractApp = Arc::new(Catcher {
+            abstract_app: AbstractApp::new(),
+            sender: Mutex::new(sender),
+        });
+        let get_value_thread = GetValueThread::new(Arc::downgrade(&app), udp_port);
+        // make sure socket is bound before send
+        thread::sleep(Duration::from_millis(100));
+
+        let addr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let msg = json!({"sensor_name": "YellowCar", "sensor_data": {"speed": 10}});
+        udp::send_with_ip(addr, udp_port, &msg.to_string());
+        let (sensor_name, sensor_data) = receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(sensor_name, "YellowCar");
+        assert_eq!(sensor_data.get_data("speed"), Some(&json!(10)));
+        assert!(!get_value_thread.is_stopped());
+
+        let start = Instant::now();
+        get_value_thread.stop_thread();
+        assert!(get_value_thread.is_stopped());
+        assert!(start.elapsed() < Duration::from_millis(RECV_TIMEOUT_MS * 3));
+    }
 }
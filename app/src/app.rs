@@ -1,4 +1,4 @@
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, RwLock, Weak};
 
 use env_logger::Builder;
 
@@ -9,6 +9,7 @@ use crate::abstract_app::AbstractApp;
 
 pub type RwLockOptionSyncAbstractApp = RwLock<Option<SyncAbstractApp>>;
 pub type SyncAbstractApp = Arc<dyn APP>;
+pub type WeakAbstractApp = Weak<dyn APP>;
 
 pub trait APP: Send + Sync {
     /// get msg
@@ -34,8 +35,9 @@ pub trait APP: Send + Sync {
     }
 
     /// start get value thread
-    fn start_get_value_thread(&self, udp_port: u16) {
-        self.super_reference().start_get_value_thread(udp_port);
+    /// app is the trait object of self, sensor data is dispatched to its get_msg
+    fn start_get_value_thread(&self, app: WeakAbstractApp, udp_port: u16) {
+        self.super_reference().start_get_value_thread(app, udp_port);
     }
 
     /// stop get value thread
@@ -1,16 +1,23 @@
 use std::collections::HashMap;
-use std::error::Error;
 use std::net::TcpStream;
-use std::os::linux::raw::stat;
 use std::sync::{Arc, RwLock, RwLockReadGuard};
 
 use log::{info, trace};
 use once_cell::sync::Lazy;
-use serde_json::{json, Value};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
 use thiserror::Error;
 
+use common::socket::framing;
+use common::socket::framing::{Framing, FRAME_VERSION};
 use common::socket::tcp::TCP;
 use common::structs::actor_info::ActorInfo;
+use common::structs::api_request::{
+    ActorCmdRequest, ActorInfoRequest, ActorRequest, ApiRequest, AppRequest, ConnectRequest,
+    MsgThreadRequest, RegisterSensorRequest, SensorInfoRequest, SensorRequest, ServiceCallRequest,
+    ServiceRequest, StartServiceRequest,
+};
+use common::structs::api_response::{ApiResponse, ErrorCode};
 use common::structs::app_info::AppInfo;
 use common::structs::enumeration::cmd_type::CmdType;
 use common::structs::enumeration::sensor_mode::SensorMode;
@@ -27,6 +34,7 @@ use crate::app::{RwLockOptionSyncAbstractApp, SyncAbstractApp};
 use crate::app_remote_connector::app_remote_connector_tcp::{
     AppRemoteConnectorTCP, RwLockOptionAppRemoteConnectorTCP,
 };
+use crate::db_controller::DbController;
 
 pub mod app_remote_connector_tcp;
 
@@ -43,6 +51,24 @@ pub struct AppRemoteConnector {
 pub enum PlatformError {
     #[error("connect platform fail")]
     ConnectPlatformFail,
+    #[error("platform is not connected")]
+    NotConnectedError,
+    #[error("app is not registered")]
+    AppNotRegisteredError,
+    #[error("parse return of {0} err: {1}")]
+    ParseReturnError(String, String),
+    #[error("{0} is refused by platform with {1}: {2}")]
+    RefusedError(String, ErrorCode, String),
+}
+
+impl PlatformError {
+    /// error code replied by platform, none if the call is not refused by platform
+    pub fn code(&self) -> Option<ErrorCode> {
+        match self {
+            PlatformError::RefusedError(_, code, _) => Some(*code),
+            _ => None,
+        }
+    }
 }
 
 impl AppRemoteConnector {
@@ -85,14 +111,19 @@ impl AppRemoteConnector {
 /// app remote connector instance
 pub static APP_REMOTE_CONNECTOR: Lazy<AppRemoteConnector> = Lazy::new(|| AppRemoteConnector::new());
 
-fn check_return_string(ret: &str) -> Result<bool, Box<dyn Error>> {
-    let jo: Value = serde_json::from_str(ret)?;
-    let state = jo
-        .get("state")
-        .ok_or("no state")?
-        .as_bool()
-        .ok_or("state is not bool")?;
-    Ok(state)
+/// check return string of api
+/// return data of an ok reply, RefusedError with error code of reply if it is not ok
+pub(crate) fn check_return_string(api: &str, ret: &str) -> Result<Value, PlatformError> {
+    match ApiResponse::parse(ret).into_result() {
+        Ok(data) => Ok(data),
+        Err((ErrorCode::BadResponse, message)) => {
+            Err(PlatformError::ParseReturnError(api.to_string(), message))
+        }
+        Err((code, message)) => {
+            info!("[AppConnector]: {} fail with {}: {}", api, code, message);
+            Err(PlatformError::RefusedError(api.to_string(), code, message))
+        }
+    }
 }
 
 impl AppRemoteConnector {
@@ -105,7 +136,7 @@ impl AppRemoteConnector {
             .read()
             .expect("tcp read lock fail")
             .as_ref()
-            .expect("tcp is none")
+            .ok_or(PlatformError::NotConnectedError)?
             .send(jo)
         {
             true => Ok(()),
@@ -124,13 +155,59 @@ impl AppRemoteConnector {
             .read()
             .expect("tcp read lock fail")
             .as_ref()
-            .expect("tcp is none")
+            .ok_or(PlatformError::NotConnectedError)?
             .recv()
         {
             Some(s) => Ok(s),
             None => Err(PlatformError::ConnectPlatformFail),
         }
     }
+
+    /// call
+    /// send a request to platform and recv the reply
+    pub(crate) fn call(&self, request: &ApiRequest) -> Result<String, PlatformError> {
+        self.send(&request.to_string())?;
+        let recv = self.recv()?;
+        trace!("call: {} -> {}", request, recv);
+        Ok(recv)
+    }
+
+    /// app name of registered app
+    fn registered_app_name(&self) -> Result<SyncClientAppName, PlatformError> {
+        self.app
+            .read()
+            .expect("app read lock fail")
+            .as_ref()
+            .map(|app| app.get_app_name())
+            .ok_or(PlatformError::AppNotRegisteredError)
+    }
+
+    /// request of registered app
+    fn app_request(&self) -> Result<AppRequest, PlatformError> {
+        Ok(AppRequest {
+            app_name: self.registered_app_name()?,
+        })
+    }
+
+    /// request
+    /// call api and return data of the reply
+    fn request(&self, request: ApiRequest) -> Result<Value, PlatformError> {
+        let recv = self.call(&request)?;
+        check_return_string(request.api(), &recv)
+    }
+
+    /// request data
+    /// call api and return field key of data of the reply
+    fn request_data<T: DeserializeOwned>(
+        &self,
+        request: ApiRequest,
+        key: &str,
+    ) -> Result<T, PlatformError> {
+        let api = request.api();
+        let data = self.request(request)?;
+        serde_json::from_value(data[key].clone())
+            .map_err(|e| PlatformError::ParseReturnError(api.to_string(), e.to_string()))
+    }
 }
 
 /// below is platform related
@@ -139,7 +216,10 @@ impl AppRemoteConnector {
     /// use ip and port to connect platform
     /// return a bool to indicate whether connect success
     pub fn connect_platform(&self, ip: String, port: u16) -> Result<bool, PlatformError> {
-        let jo: Value = json!({"api": "connect"});
+        //request length prefixed framing, platform replies without it if it does not support
+        let request = ApiRequest::Connect(ConnectRequest {
+            framing: Some(FRAME_VERSION as u64),
+        });
         let mut state = false;
 
         match TcpStream::connect(format!("{}:{}", ip, port)) {
@@ -147,15 +227,11 @@ impl AppRemoteConnector {
                 let tcp = AppRemoteConnectorTCP::new(stream, true);
                 *self.tcp.write().expect("tcp write lock fail") = Some(tcp);
 
-                self.send(&jo.to_string())?;
-                let recv = self.recv()?;
-                match check_return_string(&recv) {
-                    Ok(s) => {
-                        state = s;
-                    }
-                    Err(e) => {
-                        trace!("connect platform fail: {}", e);
-                    }
+                let recv = self.call(&request)?;
+                state = check_return_string(request.api(), &recv).is_ok();
+                //reply of connect is the last line, frames start after it
+                if let Some(tcp) = self.tcp.read().expect("tcp read lock fail").as_ref() {
+                    tcp.set_framing(Framing::from_version(framing::version_of(&recv)));
                 }
             }
             Err(e) => {
@@ -170,26 +246,13 @@ impl AppRemoteConnector {
     }
 
     /// disconnect platform
-    /// return a bool to indicate whether disconnect success
+    /// return true if disconnect success, RefusedError if platform refuses it
     pub fn disconnect_platform(&self) -> Result<bool, PlatformError> {
-        let jo: Value = json!({"api": "disconnect"});
-        let mut state = false;
-
-        self.send(&jo.to_string())?;
-        let recv = self.recv()?;
-
-        match check_return_string(&recv) {
-            Ok(s) => {
-                state = s;
-            }
-            Err(e) => {
-                trace!("disconnect platform fail: {}", e);
-            }
-        }
+        self.request(ApiRequest::Disconnect)?;
 
-        info!("[AppConnector]: disconnect platform -> {}", state);
+        info!("[AppConnector]: disconnect platform -> ok");
 
-        if state && (self.app.read().expect("app read lock fail").is_some()) {
+        if self.app.read().expect("app read lock fail").is_some() {
             self.app
                 .read()
                 .expect("app read lock fail")
@@ -199,102 +262,67 @@ impl AppRemoteConnector {
             self.app.write().expect("app write lock fail").take();
         }
 
-        Ok(state)
+        Ok(true)
     }
 
     /// check whether connected
     /// used after connect platform
     /// return a bool to indicate whether connected
     pub fn check_connected(&self) -> Result<bool, PlatformError> {
-        let jo: Value = json!({"api": "is_connected"});
-        let mut state = false;
-
-        self.send(&jo.to_string())?;
-        let recv = self.recv()?;
-
-        match check_return_string(&recv) {
-            Ok(s) => {
-                state = s;
-            }
-            Err(e) => {
-                trace!("check connected fail: {}", e);
-            }
-        }
-
-        info!("[AppConnector]: check connected -> {}", state);
-        Ok(state)
+        let connected = self.request_data(ApiRequest::IsConnected, "connected")?;
+        info!("[AppConnector]: check connected -> {}", connected);
+        Ok(connected)
     }
 
-    //todo: get database instance
+    /// get database
+    /// return a controller of tables of the registered app
+    pub fn get_database(&self) -> DbController {
+        DbController::new(self.get_app_name_clone())
+    }
 
     /// register app
     /// give a trait object of app to app remote connector
     /// it will store app in app field
-    /// return a bool to indicate whether register success
+    /// return true if register success, RefusedError if platform refuses it
     pub fn register_app(&self, app: SyncAbstractApp) -> Result<bool, PlatformError> {
-        let mut jo: Value = json!({"api": "register_app"});
         let app_name = app.get_app_name();
-        jo["app_name"] = Value::String(app_name.to_string());
-
-        self.send(&jo.to_string())?;
-
-        let mut state = false;
+        let udp_port: u16 = self.request_data(
+            ApiRequest::RegisterApp(AppRequest {
+                app_name: app_name.clone(),
+            }),
+            "udp_port",
+        )?;
+        app.start_get_value_thread(Arc::downgrade(&app), udp_port);
+        *self.app.write().expect("app write lock fail") = Some(app);
+        *self.udp_port.write().expect("udp_port write lock fail") = Some(udp_port);
 
-        let recv = self.recv()?;
-
-        if let Ok(ret_json) = serde_json::from_str::<Value>(&recv) {
-            if let Some(ret_state) = ret_json.get("state").and_then(|s| s.as_bool()) {
-                state = ret_state;
-                if state {
-                    *self.app.write().expect("app write lock fail") = Some(app);
-                    *self.udp_port.write().expect("udp_port write lock fail") = Some(
-                        ret_json
-                            .get("udp_port")
-                            .and_then(|s| s.as_u64())
-                            .unwrap_or(0) as u16,
-                    );
-                }
-            }
-        }
-
-        info!("[AppConnector]: register app({}) -> {}", app_name, state);
-        Ok(state)
+        info!("[AppConnector]: register app({}) -> ok", app_name);
+        Ok(true)
     }
 
     /// unregister app
     /// it will remove app in app field
-    /// return a bool to indicate whether unregister success
+    /// return true if unregister success, RefusedError if platform refuses it
     pub fn unregister_app(&self, app: SyncAbstractApp) -> Result<bool, PlatformError> {
-        let app_name = app.get_app_name().to_string();
-        let jo: Value = json!({"api": "unregister_app", "app_name": app_name});
-
-        self.send(&jo.to_string())?;
-
-        let mut state = false;
-        let recv = self.recv()?;
+        let app_name = app.get_app_name();
+        self.request(ApiRequest::UnregisterApp(AppRequest {
+            app_name: app_name.clone(),
+        }))?;
 
-        if let Ok(ret_json) = serde_json::from_str::<Value>(&recv) {
-            state = ret_json
-                .get("state")
-                .and_then(Value::as_bool)
-                .unwrap_or(false);
-            if state {
-                self.app
-                    .read()
-                    .expect("app read lock fail")
-                    .as_ref()
-                    .expect("app is none")
-                    .stop_get_value_thread();
-                self.app.write().expect("app write lock fail").take();
-                self.udp_port
-                    .write()
-                    .expect("udp_port write lock fail")
-                    .take();
-            }
-        }
+        self.app
+            .read()
+            .expect("app read lock fail")
+            .as_ref()
+            .expect("app is none")
+            .stop_get_value_thread();
+        self.app.write().expect("app write lock fail").take();
+        self.udp_port
+            .write()
+            .expect("udp_port write lock fail")
+            .take();
 
-        info!("unregister app({}) -> {}", app_name, state);
-        Ok(state)
+        info!("unregister app({}) -> ok", app_name);
+        Ok(true)
     }
 }
 
@@ -303,67 +331,114 @@ impl AppRemoteConnector {
     /// get supported sensors
     /// return a map of supported sensors name and sensor info
     pub fn get_supported_sensors(&self) -> Result<HashMap<String, SensorInfo>, PlatformError> {
-        todo!()
+        self.request_data(ApiRequest::GetSupportedSensors, "sensors")
     }
 
     /// get registered sensors
     /// return a map of registered sensors name and sensor info
     pub fn get_registered_sensors(&self) -> Result<HashMap<String, SensorInfo>, PlatformError> {
-        todo!()
+        self.request_data(
+            ApiRequest::GetRegisteredSensors(self.app_request()?),
+            "sensors",
+        )
     }
 
     /// get registered sensors status
     /// return true if sensors status is on
     pub fn get_registered_sensors_status(&self) -> Result<bool, PlatformError> {
-        todo!()
+        self.request_data(
+            ApiRequest::GetRegisteredSensorsStatus(self.app_request()?),
+            "status",
+        )
     }
 
     /// register sensor
     /// use sensor name to register sensor
     /// mode is used to indicate whether sensor is Active or Passive
     /// frequency is used to indicate how often Passive sensor send data to app
-    /// return a bool to indicate whether register success
+    /// return true if register success, RefusedError if platform refuses it
     pub fn register_sensor(
         &self,
         sensor_name: String,
         mode: SensorMode,
         frequency: FrequencyType,
     ) -> Result<bool, PlatformError> {
-        todo!()
+        self.request(ApiRequest::RegisterSensor(RegisterSensorRequest {
+            app_name: self.registered_app_name()?,
+            sensor_name: Arc::new(sensor_name.clone()),
+            sensor_mode: mode,
+            freq: frequency,
+        }))?;
+        info!(
+            "[AppConnector]: register sensor({}, {}, {}) -> ok",
+            sensor_name, mode, frequency
+        );
+        Ok(true)
     }
 
     /// cancel sensor
     /// use sensor name to cancel sensor
-    /// return a bool to indicate whether cancel success
+    /// return true if cancel success, RefusedError if platform refuses it
     pub fn cancel_sensor(&self, sensor_name: String) -> Result<bool, PlatformError> {
-        todo!()
+        self.request(ApiRequest::CancelSensor(SensorRequest {
+            app_name: self.registered_app_name()?,
+            sensor_name: Arc::new(sensor_name.clone()),
+        }))?;
+        info!("[AppConnector]: cancel sensor({}) -> ok", sensor_name);
+        Ok(true)
     }
 
     /// cancel all sensors
-    /// return a bool to indicate whether cancel all success
+    /// return true if cancel all success, RefusedError if platform refuses it
     pub fn cancel_all_sensors(&self) -> Result<bool, PlatformError> {
-        todo!()
+        self.request(ApiRequest::CancelAllSensors(self.app_request()?))?;
+        info!("[AppConnector]: cancel all sensors -> ok");
+        Ok(true)
     }
 
     /// get sensor data
     /// use sensor name to get sensor data
     /// return sensor data
     pub fn get_sensor_data(&self, sensor_name: String) -> Result<SensorData, PlatformError> {
-        todo!()
+        self.request_data(
+            ApiRequest::GetSensorData(SensorRequest {
+                app_name: self.registered_app_name()?,
+                sensor_name: Arc::new(sensor_name),
+            }),
+            "sensor_data",
+        )
     }
 
     /// get all sensor data
     /// return a map of sensor name and sensor data
     /// if sensor data is none, it will be none
     pub fn get_all_sensor_data(&self) -> Result<HashMap<String, SensorData>, PlatformError> {
-        todo!()
+        let sensor_data: HashMap<String, Value> = self.request_data(
+            ApiRequest::GetAllSensorData(self.app_request()?),
+            "sensor_data",
+        )?;
+        Ok(sensor_data
+            .into_iter()
+            .filter_map(|(sensor_name, data)| match serde_json::from_value(data) {
+                Ok(data) => Some((sensor_name, data)),
+                Err(e) => {
+                    trace!("sensor data of {} is none: {}", sensor_name, e);
+                    None
+                }
+            })
+            .collect())
     }
 
     /// get msg thread
     /// use cmd to set msg thread
-    /// return a bool to indicate whether you get msg thread success
+    /// return true if you get msg thread success, RefusedError if platform refuses it
     pub fn get_msg_thread(&self, cmd: CmdType) -> Result<bool, PlatformError> {
-        todo!()
+        self.request(ApiRequest::GetMsgThread(MsgThreadRequest {
+            app_name: self.registered_app_name()?,
+            cmd,
+        }))?;
+        info!("[AppConnector]: get msg thread({}) -> ok", cmd);
+        Ok(true)
     }
 }
 
@@ -372,46 +447,73 @@ impl AppRemoteConnector {
     /// get supported actors
     /// return a map of supported actors name and actor info
     pub fn get_supported_actors(&self) -> Result<HashMap<String, ActorInfo>, PlatformError> {
-        todo!()
+        self.request_data(ApiRequest::GetSupportedActors, "actors")
     }
 
     /// get registered actors
     /// return a map of registered actors name and actor info
     pub fn get_registered_actors(&self) -> Result<HashMap<String, ActorInfo>, PlatformError> {
-        todo!()
+        self.request_data(
+            ApiRequest::GetRegisteredActors(self.app_request()?),
+            "actors",
+        )
     }
 
     /// get registered actors status
     /// return true if actors status is on
     pub fn get_registered_actors_status(&self) -> Result<bool, PlatformError> {
-        todo!()
+        self.request_data(
+            ApiRequest::GetRegisteredActorsStatus(self.app_request()?),
+            "status",
+        )
     }
 
     /// register actor
     /// use actor name to register actor
-    /// return a bool to indicate whether register success
+    /// return true if register success, RefusedError if platform refuses it
     pub fn register_actor(&self, actor_name: String) -> Result<bool, PlatformError> {
-        todo!()
+        self.request(ApiRequest::RegisterActor(ActorRequest {
+            app_name: self.registered_app_name()?,
+            actor_name: Arc::new(actor_name.clone()),
+        }))?;
+        info!("[AppConnector]: register actor({}) -> ok", actor_name);
+        Ok(true)
     }
 
     /// cancel actor
     /// use actor name to cancel actor
-    /// return a bool to indicate whether cancel success
+    /// return true if cancel success, RefusedError if platform refuses it
     pub fn cancel_actor(&self, actor_name: String) -> Result<bool, PlatformError> {
-        todo!()
+        self.request(ApiRequest::CancelActor(ActorRequest {
+            app_name: self.registered_app_name()?,
+            actor_name: Arc::new(actor_name.clone()),
+        }))?;
+        info!("[AppConnector]: cancel actor({}) -> ok", actor_name);
+        Ok(true)
     }
 
     /// cancel all actors
-    /// return a bool to indicate whether cancel all success
+    /// return true if cancel all success, RefusedError if platform refuses it
     pub fn cancel_all_actors(&self) -> Result<bool, PlatformError> {
-        todo!()
+        self.request(ApiRequest::CancelAllActors(self.app_request()?))?;
+        info!("[AppConnector]: cancel all actors -> ok");
+        Ok(true)
     }
 
     /// set actor cmd
     /// use actor name and cmd to set actor cmd
-    /// return a bool to indicate whether set actor cmd success
+    /// return true if set actor cmd success, RefusedError if platform refuses it
     pub fn set_actor_cmd(&self, actor_name: String, action: String) -> Result<bool, PlatformError> {
-        todo!()
+        self.request(ApiRequest::SetActorCmd(ActorCmdRequest {
+            app_name: self.registered_app_name()?,
+            actor_name: Arc::new(actor_name.clone()),
+            action: action.clone(),
+        }))?;
+        info!(
+            "[AppConnector]: set actor cmd({}, {}) -> ok",
+            actor_name, action
+        );
+        Ok(true)
     }
 }
 
@@ -421,61 +523,102 @@ impl AppRemoteConnector {
     /// use sensor name to get sensor info
     /// return sensor info
     pub fn get_sensor_info(&self, sensor_name: String) -> Result<SensorInfo, PlatformError> {
-        todo!()
+        self.request_data(
+            ApiRequest::GetSensorInfo(SensorInfoRequest {
+                sensor_name: Arc::new(sensor_name),
+            }),
+            "sensor",
+        )
     }
 
     /// get all sensor info
     /// return a map of sensor name and sensor info
     pub fn get_all_sensor_info(&self) -> Result<HashMap<String, SensorInfo>, PlatformError> {
-        todo!()
+        self.request_data(ApiRequest::GetAllSensorInfo, "sensors")
     }
 
     /// get sensor info and data
     /// use sensor name to get sensor info and data
     /// return a string to indicate sensor info and data
     pub fn get_sensor_info_and_data(&self, sensor_name: String) -> Result<String, PlatformError> {
-        todo!()
+        let sensor: Value = self.request_data(
+            ApiRequest::GetSensorInfoAndData(SensorInfoRequest {
+                sensor_name: Arc::new(sensor_name),
+            }),
+            "sensor",
+        )?;
+        Ok(sensor.to_string())
     }
 
     /// get all sensor info and data
     /// return a map of sensor name and sensor info and data
     pub fn get_all_sensor_info_and_data(&self) -> Result<HashMap<String, String>, PlatformError> {
-        todo!()
+        let sensors: HashMap<String, Value> =
+            self.request_data(ApiRequest::GetAllSensorInfoAndData, "sensors")?;
+        Ok(sensors
+            .into_iter()
+            .map(|(sensor_name, sensor)| (sensor_name, sensor.to_string()))
+            .collect())
     }
 
     /// get actor info
     /// use actor name to get actor info
     /// return actor info
     pub fn get_actor_info(&self, actor_name: String) -> Result<ActorInfo, PlatformError> {
-        todo!()
+        self.request_data(
+            ApiRequest::GetActorInfo(ActorInfoRequest {
+                actor_name: Arc::new(actor_name),
+            }),
+            "actor",
+        )
     }
 
     /// get all actor info
     /// return a map of actor name and actor info
     pub fn get_all_actor_info(&self) -> Result<HashMap<String, ActorInfo>, PlatformError> {
-        todo!()
+        self.request_data(ApiRequest::GetAllActorInfo, "actors")
     }
 
     /// get app info
     /// return app info
     pub fn get_app_info(&self) -> Result<AppInfo, PlatformError> {
-        todo!()
+        self.request_data(ApiRequest::GetAppInfo(self.app_request()?), "app")
     }
 
     /// get all app info
     /// return a map of app name and app info
     pub fn get_all_app_info(&self) -> Result<HashMap<String, AppInfo>, PlatformError> {
-        todo!()
+        self.request_data(ApiRequest::GetAllAppInfo, "apps")
     }
 
     /// get service info
+    /// config is the config the service was started with, result is not filled
     /// return service info
-    pub fn get_service_info<T, F>(&self) -> Result<ServiceInfo<T, F>, PlatformError>
+    pub fn get_service_info<T, F>(
+        &self,
+        service: ServiceType,
+    ) -> Result<ServiceInfo<T, F>, PlatformError>
     where
-        T: ServiceConfig,
+        T: ServiceConfig + DeserializeOwned,
         F: ServiceResult,
     {
-        todo!()
+        let app_name = self.registered_app_name()?;
+        let request = ApiRequest::GetServiceInfo(ServiceRequest {
+            app_name: app_name.clone(),
+            service_type: service,
+        });
+        let api = request.api();
+        let jo: Value = self.request_data(request, "service")?;
+        let parse_error =
+            |e: serde_json::Error| PlatformError::ParseReturnError(api.to_string(), e.to_string());
+        Ok(ServiceInfo {
+            service_type: service,
+            app_name: app_name.to_string(),
+            state: serde_json::from_value(jo["state"].clone()).map_err(parse_error)?,
+            config: serde_json::from_value(jo["config"].clone()).map_err(parse_error)?,
+            result: None,
+            jo: Some(jo),
+        })
     }
 }
 
@@ -484,36 +627,61 @@ impl AppRemoteConnector {
     /// is service on
     /// return a bool to indicate whether chose service is on
     pub fn is_service_on(&self, service: ServiceType) -> Result<bool, PlatformError> {
-        todo!()
+        self.request_data(
+            ApiRequest::IsServiceOn(ServiceRequest {
+                app_name: self.registered_app_name()?,
+                service_type: service,
+            }),
+            "on",
+        )
     }
 
     /// start service
     /// give service type and service config to start service
-    /// return a bool to indicate whether start service success
+    /// return true if start service success, RefusedError if platform refuses it
     pub fn service_start<T: ServiceConfig>(
         &self,
         service: ServiceType,
         config: T,
     ) -> Result<bool, PlatformError> {
-        todo!()
+        self.request(ApiRequest::StartService(StartServiceRequest {
+            app_name: self.registered_app_name()?,
+            service_type: service,
+            config: config.to_json_object(),
+        }))?;
+        info!("[AppConnector]: start service({}) -> ok", service);
+        Ok(true)
     }
 
     /// stop service
     /// give service type to stop service
-    /// return a bool to indicate whether stop service success
+    /// return true if stop service success, RefusedError if platform refuses it
     pub fn service_stop(&self, service: ServiceType) -> Result<bool, PlatformError> {
-        todo!()
+        self.request(ApiRequest::StopService(ServiceRequest {
+            app_name: self.registered_app_name()?,
+            service_type: service,
+        }))?;
+        info!("[AppConnector]: stop service({}) -> ok", service);
+        Ok(true)
     }
 
     /// call service
     /// give service type, cmd and config to call service
-    /// return a bool to indicate whether call service success
+    /// start and reset use config, reset restarts the service
+    /// return true if call service success, RefusedError if platform refuses it
     pub fn service_call<T: ServiceConfig>(
         &self,
         service: ServiceType,
         cmd: CmdType,
         config: T,
     ) -> Result<bool, PlatformError> {
-        todo!()
+        self.request(ApiRequest::ServiceCall(ServiceCallRequest {
+            app_name: self.registered_app_name()?,
+            service_type: service,
+            cmd,
+            config: config.to_json_object(),
+        }))?;
+        info!("[AppConnector]: call service({}, {}) -> ok", service, cmd);
+        Ok(true)
     }
 }
@@ -1 +1,275 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
 
+use common::structs::api_request::{
+    ApiRequest, DbCellRequest, DbColumnRequest, DbColumnsRequest, DbConditionsRequest,
+    DbCreateRequest, DbInsertRequest, DbInsertsRequest, DbKeyRequest, DbRowsRequest,
+    DbUpdateRequest, TableRequest,
+};
+use common::structs::api_response::ErrorCode;
+pub use common::structs::db_query::{Condition, Order, Row};
+
+use crate::abstract_app::SyncClientAppName;
+use crate::app_remote_connector::{check_return_string, PlatformError, APP_REMOTE_CONNECTOR};
+
+#[derive(Error, Debug)]
+pub enum DbControllerError {
+    #[error("platform err: {0}")]
+    PlatformError(#[from] PlatformError),
+    #[error("parse return err: {0}")]
+    ParseReturnError(#[from] serde_json::Error),
+    #[error("database err: {0}")]
+    DatabaseError(String),
+}
+
+/// TableHeader describes a table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableHeader {
+    pub header: Vec<String>,
+    pub primary_key: String,
+    pub row_limit: usize,
+}
+
+/// DbController operates tables of app on platform
+/// tables are scoped by app, an app cannot see tables of other apps
+pub struct DbController {
+    app_name: SyncClientAppName,
+}
+
+impl DbController {
+    pub fn new(app_name: SyncClientAppName) -> Self {
+        Self { app_name }
+    }
+
+    /// call db api
+    /// return data of the reply, DatabaseError with message of reply if database refuses it
+    fn call(&self, request: ApiRequest) -> Result<Value, DbControllerError> {
+        match check_return_string(request.api(), &APP_REMOTE_CONNECTOR.call(&request)?) {
+            Err(PlatformError::RefusedError(_, ErrorCode::DatabaseError, message)) => {
+                Err(DbControllerError::DatabaseError(message))
+            }
+            ret => Ok(ret?),
+        }
+    }
+
+    fn count_of(ret: &Value) -> Result<usize, DbControllerError> {
+        Ok(serde_json::from_value(ret["count"].clone())?)
+    }
+
+    fn table(&self, table_name: &str) -> TableRequest {
+        TableRequest {
+            app_name: self.app_name.clone(),
+            table_name: table_name.to_string(),
+        }
+    }
+
+    /// create table
+    /// header must contain primary key, row_limit 0 means no limit
+    /// the oldest row is evicted when a table with row limit is full
+    pub fn create(
+        &self,
+        table_name: &str,
+        primary_key: &str,
+        header: &[&str],
+        row_limit: usize,
+    ) -> Result<(), DbControllerError> {
+        self.call(ApiRequest::DbCreate(DbCreateRequest {
+            app_name: self.app_name.clone(),
+            table_name: table_name.to_string(),
+            primary_key: primary_key.to_string(),
+            header: header.iter().map(|column| column.to_string()).collect(),
+            row_limit,
+        }))
+        .map(|_| ())
+    }
+
+    /// drop table
+    pub fn drop(&self, table_name: &str) -> Result<(), DbControllerError> {
+        self.call(ApiRequest::DbDrop(self.table(table_name)))
+            .map(|_| ())
+    }
+
+    /// insert a row
+    /// missing columns are null
+    pub fn insert(&self, table_name: &str, row: Row) -> Result<(), DbControllerError> {
+        self.call(ApiRequest::DbInsert(DbInsertRequest {
+            app_name: self.app_name.clone(),
+            table_name: table_name.to_string(),
+            row,
+        }))
+        .map(|_| ())
+    }
+
+    /// insert rows
+    /// nothing is inserted if any row is invalid
+    /// return the number of inserted rows
+    pub fn inserts(&self, table_name: &str, rows: Vec<Row>) -> Result<usize, DbControllerError> {
+        Self::count_of(&self.call(ApiRequest::DbInserts(DbInsertsRequest {
+            app_name: self.app_name.clone(),
+            table_name: table_name.to_string(),
+            rows,
+        }))?)
+    }
+
+    /// update columns of rows satisfying conditions
+    /// return the number of updated rows
+    pub fn update(
+        &self,
+        table_name: &str,
+        values: Row,
+        conditions: &[Condition],
+    ) -> Result<usize, DbControllerError> {
+        Self::count_of(&self.call(ApiRequest::DbUpdate(DbUpdateRequest {
+            app_name: self.app_name.clone(),
+            table_name: table_name.to_string(),
+            values,
+            conditions: conditions.to_vec(),
+        }))?)
+    }
+
+    /// delete the row of primary key value
+    /// return whether the row existed
+    pub fn delete(&self, table_name: &str, key: Value) -> Result<bool, DbControllerError> {
+        let ret = self.call(ApiRequest::DbDelete(DbKeyRequest {
+            app_name: self.app_name.clone(),
+            table_name: table_name.to_string(),
+            key,
+        }))?;
+        Ok(Self::count_of(&ret)? > 0)
+    }
+
+    /// delete rows satisfying conditions
+    /// return the number of deleted rows
+    pub fn deletes(
+        &self,
+        table_name: &str,
+        conditions: &[Condition],
+    ) -> Result<usize, DbControllerError> {
+        Self::count_of(&self.call(ApiRequest::DbDeletes(DbConditionsRequest {
+            app_name: self.app_name.clone(),
+            table_name: table_name.to_string(),
+            conditions: conditions.to_vec(),
+        }))?)
+    }
+
+    /// get the row of primary key value
+    pub fn row(&self, table_name: &str, key: Value) -> Result<Option<Row>, DbControllerError> {
+        let ret = self.call(ApiRequest::DbRow(DbKeyRequest {
+            app_name: self.app_name.clone(),
+            table_name: table_name.to_string(),
+            key,
+        }))?;
+        Ok(serde_json::from_value(ret["row"].clone())?)
+    }
+
+    /// get rows satisfying conditions
+    /// rows are in insertion order unless order is given
+    pub fn rows(
+        &self,
+        table_name: &str,
+        conditions: &[Condition],
+        order: Option<Order>,
+        limit: Option<usize>,
+    ) -> Result<Vec<Row>, DbControllerError> {
+        let ret = self.call(ApiRequest::DbRows(DbRowsRequest {
+            app_name: self.app_name.clone(),
+            table_name: table_name.to_string(),
+            conditions: conditions.to_vec(),
+            order,
+            limit,
+        }))?;
+        Ok(serde_json::from_value(ret["rows"].clone())?)
+    }
+
+    /// get values of a column of rows satisfying conditions
+    pub fn column(
+        &self,
+        table_name: &str,
+        column: &str,
+        conditions: &[Condition],
+    ) -> Result<Vec<Value>, DbControllerError> {
+        let ret = self.call(ApiRequest::DbColumn(DbColumnRequest {
+            app_name: self.app_name.clone(),
+            table_name: table_name.to_string(),
+            column: column.to_string(),
+            conditions: conditions.to_vec(),
+        }))?;
+        Ok(serde_json::from_value(ret["column"].clone())?)
+    }
+
+    /// get some columns of rows satisfying conditions
+    pub fn columns(
+        &self,
+        table_name: &str,
+        columns: &[&str],
+        conditions: &[Condition],
+    ) -> Result<Vec<Row>, DbControllerError> {
+        let ret = self.call(ApiRequest::DbColumns(DbColumnsRequest {
+            app_name: self.app_name.clone(),
+            table_name: table_name.to_string(),
+            columns: columns.iter().map(|column| column.to_string()).collect(),
+            conditions: conditions.to_vec(),
+        }))?;
+        Ok(serde_json::from_value(ret["columns"].clone())?)
+    }
+
+    /// get a cell of the row of primary key value
+    /// return null if the row does not exist
+    pub fn cell(
+        &self,
+        table_name: &str,
+        key: Value,
+        column: &str,
+    ) -> Result<Value, DbControllerError> {
+        let ret = self.call(ApiRequest::DbCell(DbCellRequest {
+            app_name: self.app_name.clone(),
+            table_name: table_name.to_string(),
+            key,
+            column: column.to_string(),
+        }))?;
+        Ok(ret["cell"].clone())
+    }
+
+    /// get header of table
+    pub fn header(&self, table_name: &str) -> Result<TableHeader, DbControllerError> {
+        Ok(serde_json::from_value(
+            self.call(ApiRequest::DbHeader(self.table(table_name)))?,
+        )?)
+    }
+
+    /// count rows satisfying conditions
+    pub fn count(
+        &self,
+        table_name: &str,
+        conditions: &[Condition],
+    ) -> Result<usize, DbControllerError> {
+        Self::count_of(&self.call(ApiRequest::DbCount(DbConditionsRequest {
+            app_name: self.app_name.clone(),
+            table_name: table_name.to_string(),
+            conditions: conditions.to_vec(),
+        }))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use common::structs::enumeration::compare_type::CompareType;
+    use common::structs::enumeration::order_type::OrderType;
+
+    use super::*;
+
+    #[test]
+    fn test_serialize_condition_and_order() {
+        let conditions = [
+            Condition::new("speed", CompareType::GT, json!(10)),
+            Condition::new("color", CompareType::IN, json!(["red"])),
+        ];
+        let jo = json!({"conditions": conditions, "order": Order::new("speed", OrderType::Desc)});
+        println!("{}", jo);
+        assert_eq!(jo["conditions"][1]["op"], json!("IN"));
+        assert_eq!(jo["order"]["order_type"], json!("Desc"));
+    }
+}
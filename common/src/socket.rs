@@ -1,5 +1,7 @@
 pub mod abstract_tcp;
 pub mod cmd_message;
 pub mod cmd_message_grp_ids;
+pub mod demux;
+pub mod framing;
 pub mod tcp;
 pub mod udp;
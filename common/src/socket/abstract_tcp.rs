@@ -1,10 +1,12 @@
-use std::io::{BufRead, BufReader, BufWriter, Error, Write};
+use std::io::{BufReader, BufWriter, Error};
 use std::net::TcpStream;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Condvar, Mutex, RwLock};
 
 use socket2::Socket;
 
+use crate::socket::framing;
+use crate::socket::framing::Framing;
 use crate::socket::tcp::TCP;
 
 //TODO: should lock AbstractTCP
@@ -21,6 +23,7 @@ pub struct AbstractTCP {
     lock_flag: AtomicBool,
     buf_out: Option<RwLock<BufWriter<TcpStream>>>,
     buf_in: Option<RwLock<BufReader<TcpStream>>>,
+    framing: RwLock<Framing>,
 }
 
 impl TCP for AbstractTCP {
@@ -43,12 +46,13 @@ impl TCP for AbstractTCP {
             *guard = true;
         }
 
-        let str = str.replace("\n", "//huanhang");
         match self.buf_out {
             Some(ref buf_out) => {
                 let mut buf_out = buf_out.write().expect("buf_out write lock failed");
-                buf_out.write_all((str + "\n").as_bytes())?;
-                buf_out.flush()?;
+                match self.get_framing() {
+                    Framing::Newline => framing::write_line(&mut *buf_out, str)?,
+                    Framing::LengthPrefixed => framing::write_frame(&mut *buf_out, str)?,
+                }
             }
             None => {
                 panic!("buf_out is none");
@@ -58,20 +62,18 @@ impl TCP for AbstractTCP {
     }
 
     fn recv_result(&self) -> Result<String, Error> {
-        let mut buf = String::new();
         match self.buf_in {
             Some(ref buf_in) => {
-                buf_in
-                    .write()
-                    .expect("buf_in write lock failed")
-                    .read_line(&mut buf)?;
-                buf = buf.trim_end_matches('\n').to_string();
+                let mut buf_in = buf_in.write().expect("buf_in write lock failed");
+                match self.get_framing() {
+                    Framing::Newline => framing::read_line(&mut *buf_in),
+                    Framing::LengthPrefixed => framing::read_frame(&mut *buf_in),
+                }
             }
             None => {
                 panic!("buf_in is none");
             }
         }
-        Ok(buf.replace("//huanhang", "\n"))
     }
 
     /*fn send(&self, str: &str) -> bool {
@@ -155,6 +157,14 @@ impl TCP for AbstractTCP {
         self.lock_flag.store(flag, Ordering::SeqCst);
     }
 
+    fn get_framing(&self) -> Framing {
+        *self.framing.read().expect("framing read lock failed")
+    }
+
+    fn set_framing(&self, framing: Framing) {
+        *self.framing.write().expect("framing write lock failed") = framing;
+    }
+
     fn unlock(&self) {
         if self.lock_flag.load(Ordering::SeqCst) {
             let (lock, cvar) = &self.lock;
@@ -222,6 +232,7 @@ impl AbstractTCP {
             lock_flag: AtomicBool::new(lock_flag),
             buf_out: out,
             buf_in: input,
+            framing: RwLock::new(Framing::default()),
         }
     }
 
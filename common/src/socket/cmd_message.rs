@@ -3,15 +3,70 @@ use std::fmt::Display;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::socket::demux::RequestId;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CmdMessage {
     pub cmd: Option<String>,
     pub message: Option<Value>,
+    /// frame version requested by register or accepted by register_back
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub framing: Option<u8>,
+    /// id of request, its reply echoes it, see socket::demux
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<RequestId>,
+    /// why a request is refused, e.g. register_back of an invalid resource config
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
 impl CmdMessage {
     pub fn new(cmd: Option<String>, message: Option<Value>) -> Self {
-        Self { cmd, message }
+        Self {
+            cmd,
+            message,
+            framing: None,
+            id: None,
+            error: None,
+        }
+    }
+
+    /// new with id
+    /// a reply passes id of its request
+    pub fn new_with_id(cmd: Option<String>, message: Option<Value>, id: Option<RequestId>) -> Self {
+        Self {
+            cmd,
+            message,
+            framing: None,
+            id,
+            error: None,
+        }
+    }
+
+    pub fn new_with_framing(
+        cmd: Option<String>,
+        message: Option<Value>,
+        framing: Option<u8>,
+    ) -> Self {
+        Self {
+            cmd,
+            message,
+            framing,
+            id: None,
+            error: None,
+        }
+    }
+
+    /// new with error
+    /// a refused request is replied with why it is refused
+    pub fn new_with_error(cmd: Option<String>, message: Option<Value>, error: String) -> Self {
+        Self {
+            cmd,
+            message,
+            framing: None,
+            id: None,
+            error: Some(error),
+        }
     }
 }
 
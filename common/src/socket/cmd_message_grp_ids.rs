@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::socket::cmd_message::CmdMessage;
+use crate::socket::demux::RequestId;
 
 pub type GroupId = i32;
 
@@ -11,6 +12,9 @@ pub struct CmdMessageGrpIds {
     pub message: Option<Value>,
     #[serde(rename = "grpIds")]
     pub grp_ids: Option<Vec<i32>>,
+    /// id of request sent to wrapper, see socket::demux
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<RequestId>,
 }
 
 impl CmdMessageGrpIds {
@@ -19,6 +23,7 @@ impl CmdMessageGrpIds {
             cmd,
             message,
             grp_ids,
+            id: None,
         }
     }
 
@@ -29,12 +34,13 @@ impl CmdMessageGrpIds {
             cmd: cmd.cmd,
             message: cmd.message,
             grp_ids,
+            id: cmd.id,
         }
     }
 
     //TODO: whether clone depends on frequency of on message
     pub fn get_cmd_message(&self) -> CmdMessage {
-        CmdMessage::new(self.cmd.clone(), self.message.clone())
+        CmdMessage::new_with_id(self.cmd.clone(), self.message.clone(), self.id)
     }
 }
 
@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Mutex;
+
+use crate::socket::cmd_message::CmdMessage;
+
+pub type RequestId = u64;
+
+/// Demux passes replies read from a connection to the requests waiting for them.
+/// every request gets an id which its reply should echo,
+/// so several requests can be in flight on one connection.
+/// a reply without id is passed to the oldest request, as an older peer replies in order
+#[derive(Debug)]
+pub struct Demux {
+    next_id: AtomicU64,
+    /// waiters ordered by id, so the first one is the oldest
+    waiters: Mutex<BTreeMap<RequestId, Sender<CmdMessage>>>,
+    closed: AtomicBool,
+}
+
+impl Default for Demux {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Demux {
+    pub fn new() -> Self {
+        Self {
+            next_id: AtomicU64::new(1),
+            waiters: Mutex::new(BTreeMap::new()),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    /// register a request
+    /// return id to send with request and receiver of its reply,
+    /// receiver is disconnected if demux is closed
+    pub fn register(&self) -> (RequestId, Receiver<CmdMessage>) {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (sender, receiver) = channel();
+        let mut waiters = self.waiters.lock().expect("lock waiters fail");
+        // check under lock, so that close does not miss this waiter
+        if !self.closed.load(Ordering::SeqCst) {
+            waiters.insert(id, sender);
+        }
+        (id, receiver)
+    }
+
+    /// cancel a request, e.g. when it fails to be sent
+    /// a late reply of it is dropped
+    pub fn cancel(&self, id: RequestId) {
+        self.waiters.lock().expect("lock waiters fail").remove(&id);
+    }
+
+    /// dispatch a reply to its request
+    /// return false if no request waits for it, e.g. it is cancelled
+    pub fn dispatch(&self, reply: CmdMessage) -> bool {
+        let mut waiters = self.waiters.lock().expect("lock waiters fail");
+        let sender = match reply.id {
+            Some(id) => waiters.remove(&id),
+            None => waiters.pop_first().map(|(_, sender)| sender),
+        };
+        match sender {
+            Some(sender) => sender.send(reply).is_ok(),
+            None => false,
+        }
+    }
+
+    /// close
+    /// requests waiting and registered later get no reply
+    pub fn close(&self) {
+        let mut waiters = self.waiters.lock().expect("lock waiters fail");
+        self.closed.store(true, Ordering::SeqCst);
+        waiters.clear();
+    }
+
+    /// number of requests in flight
+    pub fn get_waiting_num(&self) -> usize {
+        self.waiters.lock().expect("lock waiters fail").len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reply(id: Option<RequestId>, message: &str) -> CmdMessage {
+        CmdMessage::new_with_id(
+            Some("sensory_back".to_string()),
+            Some(serde_json::json!(message)),
+            id,
+        )
+    }
+
+    #[test]
+    fn test_demux() {
+        let demux = Demux::new();
+        let (id1, receiver1) = demux.register();
+        let (id2, receiver2) = demux.register();
+        let (id3, receiver3) = demux.register();
+        assert_eq!(demux.get_waiting_num(), 3);
+
+        // out of order replies with id
+        assert!(demux.dispatch(reply(Some(id2), "2")));
+        assert!(demux.dispatch(reply(Some(id1), "1")));
+        assert_eq!(receiver1.recv().unwrap().message.unwrap(), "1");
+        assert_eq!(receiver2.recv().unwrap().message.unwrap(), "2");
+
+        // a late reply of a cancelled request is dropped
+        demux.cancel(id3);
+        assert!(!demux.dispatch(reply(Some(id3), "3")));
+        assert!(receiver3.recv().is_err());
+
+        // reply without id goes to the oldest request
+        let (_, receiver4) = demux.register();
+        let (_, receiver5) = demux.register();
+        assert!(demux.dispatch(reply(None, "4")));
+        assert_eq!(receiver4.recv().unwrap().message.unwrap(), "4");
+
+        demux.close();
+        assert!(receiver5.recv().is_err());
+        let (_, receiver6) = demux.register();
+        assert!(receiver6.recv().is_err());
+        assert_eq!(demux.get_waiting_num(), 0);
+    }
+}
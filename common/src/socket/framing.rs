@@ -0,0 +1,167 @@
+use std::io::{BufRead, Error, ErrorKind, Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+/// version byte of every frame
+pub const FRAME_VERSION: u8 = 1;
+/// key of requested or accepted frame version in connect and register messages
+pub const FRAMING_KEY: &str = "framing";
+/// a frame longer than this is refused, so that a broken peer can not exhaust memory
+pub const MAX_FRAME_LEN: u32 = 64 * 1024 * 1024;
+
+/// Framing is how messages are delimited on a tcp connection.
+/// every connection starts with Newline, so that older peers are understood,
+/// it switches to LengthPrefixed after connect or register if both sides agree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Framing {
+    /// a message is a line, '\n' inside it is escaped as "//huanhang"
+    #[default]
+    Newline,
+    /// a message is a 4 bytes big endian length, a version byte and the payload
+    LengthPrefixed,
+}
+
+impl Framing {
+    /// framing of an accepted frame version
+    /// none or an unknown version keeps Newline
+    pub fn from_version(version: Option<u8>) -> Self {
+        match version {
+            Some(FRAME_VERSION) => Framing::LengthPrefixed,
+            _ => Framing::Newline,
+        }
+    }
+}
+
+/// negotiate
+/// return frame version accepted for the version requested by peer,
+/// none if peer does not request one, e.g. an older wrapper
+pub fn negotiate(requested: Option<u64>) -> Option<u8> {
+    requested
+        .filter(|version| *version >= FRAME_VERSION as u64)
+        .map(|_| FRAME_VERSION)
+}
+
+/// frame version in FRAMING_KEY of data of an api response, none if it is absent
+pub fn version_of(json_str: &str) -> Option<u8> {
+    serde_json::from_str::<serde_json::Value>(json_str)
+        .ok()
+        .and_then(|json| json["data"][FRAMING_KEY].as_u64())
+        .and_then(|version| u8::try_from(version).ok())
+}
+
+/// write a frame
+pub fn write_frame(writer: &mut impl Write, payload: &str) -> Result<(), Error> {
+    let len = u32::try_from(payload.len())
+        .ok()
+        .filter(|len| *len <= MAX_FRAME_LEN)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("frame of {} bytes is too long", payload.len()),
+            )
+        })?;
+    writer.write_all(&len.to_be_bytes())?;
+    writer.write_all(&[FRAME_VERSION])?;
+    writer.write_all(payload.as_bytes())?;
+    writer.flush()
+}
+
+/// read a frame
+/// return empty string if peer closes connection before a frame
+pub fn read_frame(reader: &mut impl Read) -> Result<String, Error> {
+    let mut header = [0u8; 5];
+    if let Err(e) = reader.read_exact(&mut header) {
+        return match e.kind() {
+            ErrorKind::UnexpectedEof => Ok(String::new()),
+            _ => Err(e),
+        };
+    }
+    let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+    if header[4] != FRAME_VERSION {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("unknown frame version {}", header[4]),
+        ));
+    }
+    if len > MAX_FRAME_LEN {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("frame of {} bytes is too long", len),
+        ));
+    }
+    let mut payload = vec![0u8; len as usize];
+    reader.read_exact(&mut payload)?;
+    String::from_utf8(payload).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+/// write a line of legacy Newline framing
+pub fn write_line(writer: &mut impl Write, payload: &str) -> Result<(), Error> {
+    let line = payload.replace('\n', "//huanhang");
+    writer.write_all((line + "\n").as_bytes())?;
+    writer.flush()
+}
+
+/// read a line of legacy Newline framing
+/// return empty string if peer closes connection
+pub fn read_line(reader: &mut impl BufRead) -> Result<String, Error> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    Ok(line.trim_end_matches('\n').replace("//huanhang", "\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_frame() {
+        let payload = "{\"a\":\"x//huanhang\ny\"}";
+        let mut buf = Vec::new();
+        write_frame(&mut buf, payload).unwrap();
+        write_frame(&mut buf, "").unwrap();
+        assert_eq!(&buf[..5], &[0, 0, 0, payload.len() as u8, FRAME_VERSION]);
+
+        let mut reader = Cursor::new(buf);
+        assert_eq!(read_frame(&mut reader).unwrap(), payload);
+        assert_eq!(read_frame(&mut reader).unwrap(), "");
+        assert_eq!(read_frame(&mut reader).unwrap(), "");
+
+        let mut reader = Cursor::new(vec![0, 0, 0, 1, FRAME_VERSION + 1, b'a']);
+        assert!(read_frame(&mut reader).is_err());
+        let mut too_long = (MAX_FRAME_LEN + 1).to_be_bytes().to_vec();
+        too_long.push(FRAME_VERSION);
+        assert!(read_frame(&mut Cursor::new(too_long)).is_err());
+    }
+
+    #[test]
+    fn test_line() {
+        let mut buf = Vec::new();
+        write_line(&mut buf, "a\nb").unwrap();
+        assert_eq!(buf, b"a//huanhangb\n");
+        assert_eq!(read_line(&mut Cursor::new(buf)).unwrap(), "a\nb");
+    }
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(negotiate(None), None);
+        assert_eq!(negotiate(Some(0)), None);
+        assert_eq!(negotiate(Some(FRAME_VERSION as u64)), Some(FRAME_VERSION));
+        assert_eq!(
+            negotiate(Some(FRAME_VERSION as u64 + 1)),
+            Some(FRAME_VERSION)
+        );
+        assert_eq!(version_of(r#"{"ok":true,"data":{"framing":1}}"#), Some(1));
+        assert_eq!(version_of(r#"{"ok":true,"data":null}"#), None);
+        assert_eq!(Framing::from_version(None), Framing::Newline);
+        assert_eq!(
+            Framing::from_version(Some(FRAME_VERSION + 1)),
+            Framing::Newline
+        );
+        assert_eq!(
+            Framing::from_version(Some(FRAME_VERSION)),
+            Framing::LengthPrefixed
+        );
+    }
+}
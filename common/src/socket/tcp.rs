@@ -4,6 +4,7 @@ use std::net::TcpStream;
 use log::error;
 
 use crate::socket::abstract_tcp::AbstractTCP;
+use crate::socket::framing::Framing;
 
 pub trait TCP {
     fn super_reference(&self) -> &AbstractTCP;
@@ -77,6 +78,14 @@ pub trait TCP {
     fn unlock(&self) {
         self.super_reference().unlock();
     }
+    fn get_framing(&self) -> Framing {
+        self.super_reference().get_framing()
+    }
+    /// set framing
+    /// both sides must switch between the same two messages, see socket::framing
+    fn set_framing(&self, framing: Framing) {
+        self.super_reference().set_framing(framing);
+    }
     //useless for lock will be automatically released when out of scope
     //fn unlock(&self);
 }
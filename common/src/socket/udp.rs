@@ -63,9 +63,19 @@ pub fn recv(port: u16, timeout: Option<u64>) -> Option<String> {
         socket.try_clone().expect("Failed to clone socket")
     };
 
-    let mut buf = [0; 1024];
+    // a datagram carrying inc results may be larger than a plain sensor data
+    let mut buf = vec![0; 65536];
     match socket.recv_from(&mut buf) {
         Ok((size, _)) => Some(String::from_utf8_lossy(&buf[..size]).to_string()),
+        // timeout is not a failure, caller polls with it
+        Err(err)
+            if matches!(
+                err.kind(),
+                std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+            ) =>
+        {
+            None
+        }
         Err(err) => {
             println!("Failed to receive UDP message: {}", err);
             None
@@ -1,11 +1,15 @@
 pub mod actor_info;
+pub mod api_request;
+pub mod api_response;
 pub mod app_info;
 pub mod check_info;
 pub mod ctx_service_config;
 pub mod ctx_service_result;
+pub mod db_query;
 pub mod enumeration;
 pub mod inv_service_config;
 pub mod inv_service_result;
+pub mod request_policy;
 pub mod resource_config;
 pub mod sensor_data;
 pub mod sensor_info;
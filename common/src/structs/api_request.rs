@@ -0,0 +1,436 @@
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use serde::de::Error as DeError;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+use strum::VariantNames;
+use strum_macros::{EnumVariantNames, IntoStaticStr};
+use thiserror::Error;
+
+use crate::structs::api_response::ErrorCode;
+use crate::structs::db_query::{Condition, Order, Row};
+use crate::structs::enumeration::cmd_type::CmdType;
+use crate::structs::enumeration::sensor_mode::SensorMode;
+use crate::structs::enumeration::service_type::ServiceType;
+use crate::structs::time_line::FrequencyType;
+
+/// ApiRequest is a request sent by app to platform,
+/// tagged by api, e.g. {"api": "register_sensor", "app_name": "a", "sensor_name": "s", ...}
+/// app driver dispatches on it and app remote connector builds it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, IntoStaticStr, EnumVariantNames)]
+#[serde(tag = "api", rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum ApiRequest {
+    // platform
+    Connect(ConnectRequest),
+    Disconnect,
+    IsConnected,
+    RegisterApp(AppRequest),
+    UnregisterApp(AppRequest),
+
+    // sensor
+    GetSupportedSensors,
+    GetRegisteredSensors(AppRequest),
+    GetRegisteredSensorsStatus(AppRequest),
+    RegisterSensor(RegisterSensorRequest),
+    CancelSensor(SensorRequest),
+    CancelAllSensors(AppRequest),
+    GetSensorData(SensorRequest),
+    GetAllSensorData(AppRequest),
+    GetMsgThread(MsgThreadRequest),
+
+    // actor
+    GetSupportedActors,
+    GetRegisteredActors(AppRequest),
+    GetRegisteredActorsStatus(AppRequest),
+    RegisterActor(ActorRequest),
+    CancelActor(ActorRequest),
+    CancelAllActors(AppRequest),
+    SetActorCmd(ActorCmdRequest),
+
+    // info
+    GetSensorInfo(SensorInfoRequest),
+    GetSensorInfoAndData(SensorInfoRequest),
+    GetAllSensorInfo,
+    GetAllSensorInfoAndData,
+    GetActorInfo(ActorInfoRequest),
+    GetAllActorInfo,
+    GetAppInfo(AppRequest),
+    GetAllAppInfo,
+    GetServiceInfo(ServiceRequest),
+    GetAllServiceInfo(AppRequest),
+
+    // service
+    IsServiceOn(ServiceRequest),
+    StartService(StartServiceRequest),
+    StopService(ServiceRequest),
+    ServiceCall(ServiceCallRequest),
+    InvMonitor(InvObjsRequest),
+    InvIsMonitored(InvObjsRequest),
+    InvCheck(InvCheckRequest),
+    InvSave(AppRequest),
+    InvLoad(InvLoadRequest),
+    InvCheckGenerated(AppRequest),
+
+    // database
+    DbCreate(DbCreateRequest),
+    DbDrop(TableRequest),
+    DbInsert(DbInsertRequest),
+    DbInserts(DbInsertsRequest),
+    DbUpdate(DbUpdateRequest),
+    DbDelete(DbKeyRequest),
+    DbDeletes(DbConditionsRequest),
+    DbRow(DbKeyRequest),
+    DbRows(DbRowsRequest),
+    DbColumn(DbColumnRequest),
+    DbColumns(DbColumnsRequest),
+    DbCell(DbCellRequest),
+    DbHeader(TableRequest),
+    DbCount(DbConditionsRequest),
+}
+
+/// frame version requested by app, see socket::framing
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ConnectRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub framing: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppRequest {
+    pub app_name: Arc<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SensorRequest {
+    pub app_name: Arc<String>,
+    pub sensor_name: Arc<String>,
+}
+
+/// freq is ignored by an active sensor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegisterSensorRequest {
+    pub app_name: Arc<String>,
+    pub sensor_name: Arc<String>,
+    #[serde(deserialize_with = "from_str_ignore_case")]
+    pub sensor_mode: SensorMode,
+    pub freq: FrequencyType,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MsgThreadRequest {
+    pub app_name: Arc<String>,
+    #[serde(deserialize_with = "from_str_ignore_case")]
+    pub cmd: CmdType,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActorRequest {
+    pub app_name: Arc<String>,
+    pub actor_name: Arc<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActorCmdRequest {
+    pub app_name: Arc<String>,
+    pub actor_name: Arc<String>,
+    pub action: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SensorInfoRequest {
+    pub sensor_name: Arc<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActorInfoRequest {
+    pub actor_name: Arc<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceRequest {
+    pub app_name: Arc<String>,
+    #[serde(deserialize_with = "from_str_ignore_case")]
+    pub service_type: ServiceType,
+}
+
+/// config is null for default config,
+/// config of all services is {"ctx": ctx config, "inv": inv config}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StartServiceRequest {
+    pub app_name: Arc<String>,
+    #[serde(deserialize_with = "from_str_ignore_case")]
+    pub service_type: ServiceType,
+    #[serde(default)]
+    pub config: Value,
+}
+
+/// start and reset use config, reset restarts the service
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceCallRequest {
+    pub app_name: Arc<String>,
+    #[serde(deserialize_with = "from_str_ignore_case")]
+    pub service_type: ServiceType,
+    #[serde(deserialize_with = "from_str_ignore_case")]
+    pub cmd: CmdType,
+    #[serde(default)]
+    pub config: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InvObjsRequest {
+    pub app_name: Arc<String>,
+    pub objs: Vec<String>,
+}
+
+/// objs maps variable name to its value at line_number
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InvCheckRequest {
+    pub app_name: Arc<String>,
+    pub line_number: i32,
+    pub objs: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InvLoadRequest {
+    pub app_name: Arc<String>,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableRequest {
+    pub app_name: Arc<String>,
+    pub table_name: String,
+}
+
+/// row_limit 0 means no limit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DbCreateRequest {
+    pub app_name: Arc<String>,
+    pub table_name: String,
+    pub primary_key: String,
+    pub header: Vec<String>,
+    #[serde(default)]
+    pub row_limit: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DbInsertRequest {
+    pub app_name: Arc<String>,
+    pub table_name: String,
+    pub row: Row,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DbInsertsRequest {
+    pub app_name: Arc<String>,
+    pub table_name: String,
+    pub rows: Vec<Row>,
+}
+
+/// a missing or null conditions means no condition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DbUpdateRequest {
+    pub app_name: Arc<String>,
+    pub table_name: String,
+    pub values: Row,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub conditions: Vec<Condition>,
+}
+
+/// key is the value of primary key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DbKeyRequest {
+    pub app_name: Arc<String>,
+    pub table_name: String,
+    pub key: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DbConditionsRequest {
+    pub app_name: Arc<String>,
+    pub table_name: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub conditions: Vec<Condition>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DbRowsRequest {
+    pub app_name: Arc<String>,
+    pub table_name: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub conditions: Vec<Condition>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<Order>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DbColumnRequest {
+    pub app_name: Arc<String>,
+    pub table_name: String,
+    pub column: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub conditions: Vec<Condition>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DbColumnsRequest {
+    pub app_name: Arc<String>,
+    pub table_name: String,
+    pub columns: Vec<String>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub conditions: Vec<Condition>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DbCellRequest {
+    pub app_name: Arc<String>,
+    pub table_name: String,
+    pub key: Value,
+    pub column: String,
+}
+
+/// deserialize an enum by FromStr, so that "ctx" is accepted as "Ctx"
+fn from_str_ignore_case<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    let value = String::deserialize(deserializer)?;
+    T::from_str(&value).map_err(|e| D::Error::custom(format!("{}: {}", value, e)))
+}
+
+/// deserialize null as default value
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Default,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+#[derive(Error, Debug)]
+pub enum ApiRequestError {
+    #[error("api is none")]
+    MissingApi,
+    #[error("api {0} is not supported")]
+    UnknownApi(String),
+    #[error("parse api {0} err: {1}")]
+    ParseError(String, serde_json::Error),
+}
+
+impl ApiRequestError {
+    /// error code replied to app
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ApiRequestError::MissingApi => ErrorCode::MissingParam,
+            ApiRequestError::UnknownApi(_) => ErrorCode::UnknownApi,
+            // serde_json reports a missing field only by its message
+            ApiRequestError::ParseError(_, e) if e.to_string().starts_with("missing field") => {
+                ErrorCode::MissingParam
+            }
+            ApiRequestError::ParseError(_, _) => ErrorCode::InvalidParam,
+        }
+    }
+}
+
+impl ApiRequest {
+    /// api name, e.g. "register_app"
+    pub fn api(&self) -> &'static str {
+        self.into()
+    }
+
+    /// parse a request sent by app
+    /// api is matched case-insensitively
+    pub fn from_value(mut json: Value) -> Result<Self, ApiRequestError> {
+        let api = match json.get("api").and_then(Value::as_str) {
+            Some(api) => api.to_ascii_lowercase(),
+            None => return Err(ApiRequestError::MissingApi),
+        };
+        if !Self::VARIANTS.contains(&api.as_str()) {
+            return Err(ApiRequestError::UnknownApi(api));
+        }
+        json["api"] = Value::String(api.clone());
+        serde_json::from_value(json).map_err(|e| ApiRequestError::ParseError(api, e))
+    }
+}
+
+impl Display for ApiRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            serde_json::to_string(self).expect("api request to string fail")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_api_request() {
+        let request = ApiRequest::RegisterSensor(RegisterSensorRequest {
+            app_name: Arc::new("app".to_string()),
+            sensor_name: Arc::new("car".to_string()),
+            sensor_mode: SensorMode::Passive,
+            freq: 2,
+        });
+        println!("{}", request);
+        assert_eq!(request.api(), "register_sensor");
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            json!({"api": "register_sensor", "app_name": "app", "sensor_name": "car", "sensor_mode": "Passive", "freq": 2})
+        );
+        assert_eq!(
+            ApiRequest::from_value(serde_json::to_value(&request).unwrap()).unwrap(),
+            request
+        );
+
+        // api and enums are case-insensitive, extra fields are ignored
+        let request = ApiRequest::from_value(
+            json!({"api": "Is_Service_On", "app_name": "app", "service_type": "ctx"}),
+        )
+        .unwrap();
+        assert_eq!(
+            request,
+            ApiRequest::IsServiceOn(ServiceRequest {
+                app_name: Arc::new("app".to_string()),
+                service_type: ServiceType::Ctx,
+            })
+        );
+        let request =
+            ApiRequest::from_value(json!({"api": "get_all_app_info", "app_name": "app"})).unwrap();
+        assert_eq!(request, ApiRequest::GetAllAppInfo);
+        let request = ApiRequest::from_value(
+            json!({"api": "db_count", "app_name": "app", "table_name": "t", "conditions": null}),
+        )
+        .unwrap();
+        println!("{}", request);
+        assert!(matches!(request, ApiRequest::DbCount(r) if r.conditions.is_empty()));
+
+        let code = |json: Value| ApiRequest::from_value(json).unwrap_err().code();
+        assert_eq!(code(json!({})), ErrorCode::MissingParam);
+        assert_eq!(code(json!({"api": "nope"})), ErrorCode::UnknownApi);
+        assert_eq!(
+            code(json!({"api": "register_app"})),
+            ErrorCode::MissingParam
+        );
+        assert_eq!(
+            code(json!({"api": "register_app", "app_name": 1})),
+            ErrorCode::InvalidParam
+        );
+        assert_eq!(
+            code(json!({"api": "get_msg_thread", "app_name": "app", "cmd": "jump"})),
+            ErrorCode::InvalidParam
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use strum_macros::{Display, EnumString};
+
+/// ErrorCode is the stable reason of a failed api call,
+/// clients branch on it instead of matching messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display, EnumString, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum ErrorCode {
+    /// a required parameter is missing
+    MissingParam,
+    /// a parameter has a wrong type or value
+    InvalidParam,
+    /// api is not supported by platform
+    UnknownApi,
+    AppNotRegistered,
+    AppAlreadyRegistered,
+    SensorNotFound,
+    SensorNotRegistered,
+    SensorAlreadyRegistered,
+    SensorNotAlive,
+    /// requested freq is out of the range of sensor
+    FreqOutOfRange,
+    ActorNotFound,
+    ActorNotRegistered,
+    ActorAlreadyRegistered,
+    ActorNotAlive,
+    /// resource does not reply in time
+    Timeout,
+    /// service is off or fails
+    ServiceError,
+    DatabaseError,
+    /// request is understood but not done
+    Refused,
+    /// response can not be parsed, only produced by clients
+    BadResponse,
+}
+
+/// ApiResponse is the envelope of every reply of app driver:
+/// {"ok": true, "data": {...}} or {"ok": false, "code": "...", "message": "...", "data": null}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiResponse {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<ErrorCode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub data: Value,
+}
+
+impl ApiResponse {
+    /// successful response with data, null if there is nothing to return
+    pub fn ok(data: Value) -> Self {
+        Self {
+            ok: true,
+            code: None,
+            message: None,
+            data,
+        }
+    }
+
+    /// failed response with code and message
+    pub fn error(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            code: Some(code),
+            message: Some(message.into()),
+            data: Value::Null,
+        }
+    }
+
+    /// parse a response string
+    /// a string that is not an envelope is a BadResponse error
+    pub fn parse(json_str: &str) -> Self {
+        serde_json::from_str(json_str).unwrap_or_else(|e| {
+            Self::error(
+                ErrorCode::BadResponse,
+                format!("{} is not a response: {}", json_str, e),
+            )
+        })
+    }
+
+    /// into result of data, err with code and message if it failed
+    pub fn into_result(self) -> Result<Value, (ErrorCode, String)> {
+        match self.ok {
+            true => Ok(self.data),
+            false => Err((
+                self.code.unwrap_or(ErrorCode::Refused),
+                self.message.unwrap_or_default(),
+            )),
+        }
+    }
+}
+
+impl Display for ApiResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            serde_json::to_string(self).expect("api response to string fail")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_api_response() {
+        let ok = ApiResponse::ok(json!({"udp_port": 1}));
+        println!("{}", ok);
+        assert_eq!(ok.to_string(), r#"{"ok":true,"data":{"udp_port":1}}"#);
+        assert_eq!(ApiResponse::parse(&ok.to_string()), ok);
+
+        let error = ApiResponse::error(ErrorCode::SensorNotFound, "sensor a is not found");
+        println!("{}", error);
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({"ok": false, "code": "sensor_not_found", "message": "sensor a is not found", "data": null})
+        );
+        assert_eq!(
+            ApiResponse::parse(&error.to_string()).into_result(),
+            Err((
+                ErrorCode::SensorNotFound,
+                "sensor a is not found".to_string()
+            ))
+        );
+        assert_eq!(
+            ErrorCode::from_str("unknown_api").unwrap(),
+            ErrorCode::UnknownApi
+        );
+        assert_eq!(ErrorCode::Timeout.to_string(), "timeout");
+
+        let bad = ApiResponse::parse("{\"state\": true}");
+        println!("{}", bad);
+        assert_eq!(bad.code, Some(ErrorCode::BadResponse));
+    }
+}
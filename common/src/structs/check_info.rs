@@ -1 +1,72 @@
+use std::fmt::Display;
 
+use serde::{Deserialize, Serialize};
+
+use crate::util::util::{get_app_name_line_number_group, is_trace_file};
+
+/// extension of inv trace files
+pub const TRACE_FILE_EXTENSION: &str = "trace";
+
+/// CheckInfo locates a check of inv service:
+/// the app, the line number where check is called and the group of checked variables
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct CheckInfo {
+    pub app_name: String,
+    pub line_number: i32,
+    pub group: i32,
+}
+
+impl CheckInfo {
+    pub fn new(app_name: String, line_number: i32, group: i32) -> Self {
+        Self {
+            app_name,
+            line_number,
+            group,
+        }
+    }
+
+    /// trace file name
+    /// e.g. app-line12-grp0.trace
+    pub fn get_trace_file_name(&self) -> String {
+        format!("{}.{}", self, TRACE_FILE_EXTENSION)
+    }
+
+    /// parse a trace file name, directory of the file is ignored
+    /// return none if file name is not a trace file
+    pub fn from_trace_file_name(file_name: &str) -> Option<Self> {
+        let file_name = file_name.rsplit('/').next().unwrap_or(file_name);
+        if !is_trace_file(file_name) {
+            return None;
+        }
+        let (app_name, line_number, group) = get_app_name_line_number_group(file_name);
+        Some(Self::new(app_name, line_number, group))
+    }
+}
+
+/// display as app-line<N>-grp<M>
+impl Display for CheckInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}-line{}-grp{}",
+            self.app_name, self.line_number, self.group
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_file_name() {
+        let check_info = CheckInfo::new("app".to_string(), 12, 1);
+        let file_name = check_info.get_trace_file_name();
+        assert_eq!(file_name, "app-line12-grp1.trace");
+        assert_eq!(
+            CheckInfo::from_trace_file_name(&format!("output-1/{}", file_name)),
+            Some(check_info)
+        );
+        assert_eq!(CheckInfo::from_trace_file_name("app-line12.trace"), None);
+    }
+}
@@ -1,3 +1,103 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::structs::enumeration::ctx_validator::CtxValidator;
+use crate::structs::service_config::ServiceConfig;
+
+/// CtxServiceConfig is sent by app when it starts ctx service.
+/// every field is optional, a none field falls back to the base config of ctx server
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CtxServiceConfig {
-    //todo
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ctx_validator: Option<CtxValidator>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rule_file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bfunc_file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pattern_file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mfunc_file: Option<String>,
+}
+
+impl CtxServiceConfig {
+    /// constructor
+    /// use base config of ctx server
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// getter
+    pub fn get_ctx_validator(&self) -> Option<CtxValidator> {
+        self.ctx_validator
+    }
+
+    pub fn get_rule_file(&self) -> Option<&str> {
+        self.rule_file.as_deref()
+    }
+
+    pub fn get_bfunc_file(&self) -> Option<&str> {
+        self.bfunc_file.as_deref()
+    }
+
+    pub fn get_pattern_file(&self) -> Option<&str> {
+        self.pattern_file.as_deref()
+    }
+
+    pub fn get_mfunc_file(&self) -> Option<&str> {
+        self.mfunc_file.as_deref()
+    }
+
+    /// setter
+    pub fn set_ctx_validator(&mut self, ctx_validator: CtxValidator) {
+        self.ctx_validator = Some(ctx_validator);
+    }
+
+    pub fn set_rule_file(&mut self, rule_file: String) {
+        self.rule_file = Some(rule_file);
+    }
+
+    pub fn set_bfunc_file(&mut self, bfunc_file: String) {
+        self.bfunc_file = Some(bfunc_file);
+    }
+
+    pub fn set_pattern_file(&mut self, pattern_file: String) {
+        self.pattern_file = Some(pattern_file);
+    }
+
+    pub fn set_mfunc_file(&mut self, mfunc_file: String) {
+        self.mfunc_file = Some(mfunc_file);
+    }
+}
+
+impl ServiceConfig for CtxServiceConfig {
+    fn to_json_string(&self) -> String {
+        serde_json::to_string(self).expect("serialize ctx service config fail")
+    }
+
+    fn to_json_object(&self) -> Value {
+        serde_json::to_value(self).expect("serialize ctx service config fail")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_serialize_and_deserialize() {
+        let mut config = CtxServiceConfig::new();
+        assert_eq!(config.to_json_object(), json!({}));
+
+        config.set_ctx_validator(CtxValidator::PccGeas);
+        config.set_rule_file("rules.json".to_string());
+        println!("{}", config.to_json_string());
+
+        let deserialized: CtxServiceConfig =
+            serde_json::from_value(config.to_json_object()).unwrap();
+        assert_eq!(deserialized, config);
+        assert_eq!(deserialized.get_pattern_file(), None);
+    }
 }
@@ -1 +1,95 @@
+use std::collections::HashMap;
 
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::structs::enumeration::sensor_data_type::SensorDataType;
+use crate::structs::sensor_data::SensorData;
+use crate::structs::service_result::ServiceResult;
+
+/// CtxLinkItem is one variable binding of an inconsistency
+/// it tells which context is bound to the variable of the rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CtxLinkItem {
+    pub var: String,
+    pub ctx_id: u64,
+    pub sensor_name: String,
+    pub fields: HashMap<String, Value>,
+    pub timestamp: u64,
+}
+
+/// CtxServiceResult is an inconsistency found by ctx service.
+/// it is sent to app as sensor data of type IncResult
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CtxServiceResult {
+    pub rule_id: String,
+    pub link: Vec<CtxLinkItem>,
+}
+
+impl CtxServiceResult {
+    pub fn new(rule_id: String, link: Vec<CtxLinkItem>) -> Self {
+        Self { rule_id, link }
+    }
+
+    /// to sensor data
+    /// return a sensor data of type IncResult with field rule_id and link
+    pub fn to_sensor_data(&self) -> SensorData {
+        SensorData::new(
+            SensorDataType::IncResult,
+            vec!["rule_id".to_string(), "link".to_string()],
+            vec![
+                json!(self.rule_id),
+                serde_json::to_value(&self.link).expect("serialize ctx link fail"),
+            ],
+        )
+    }
+
+    /// from sensor data
+    /// if sensor data is not IncResult or lack of fields, return none
+    pub fn from_sensor_data(sensor_data: &SensorData) -> Option<Self> {
+        if sensor_data.get_sensor_data_type() != SensorDataType::IncResult {
+            return None;
+        }
+        let rule_id = sensor_data.get_data("rule_id")?.as_str()?.to_string();
+        let link = serde_json::from_value(sensor_data.get_data("link")?.clone()).ok()?;
+        Some(Self { rule_id, link })
+    }
+}
+
+impl ServiceResult for CtxServiceResult {
+    fn to_json_string(&self) -> String {
+        serde_json::to_string(self).expect("serialize ctx service result fail")
+    }
+
+    fn to_json_object(&self) -> Value {
+        serde_json::to_value(self).expect("serialize ctx service result fail")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sensor_data_convert() {
+        let result = CtxServiceResult::new(
+            "rule_1".to_string(),
+            vec![CtxLinkItem {
+                var: "v1".to_string(),
+                ctx_id: 3,
+                sensor_name: "YellowCar".to_string(),
+                fields: HashMap::from([("speed".to_string(), json!(130))]),
+                timestamp: 0,
+            }],
+        );
+        let sensor_data = result.to_sensor_data();
+        println!("{}", serde_json::to_string(&sensor_data).unwrap());
+        assert_eq!(
+            CtxServiceResult::from_sensor_data(&sensor_data),
+            Some(result)
+        );
+
+        let msg = SensorData::new_with_one_field_with_default_type("rule_id".to_string(), json!(1));
+        assert_eq!(CtxServiceResult::from_sensor_data(&msg), None);
+    }
+}
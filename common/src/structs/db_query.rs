@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::structs::enumeration::compare_type::CompareType;
+use crate::structs::enumeration::order_type::OrderType;
+
+/// Row maps column to value
+pub type Row = Map<String, Value>;
+
+/// Condition filters rows of a table by one column
+/// e.g. {"column": "speed", "op": ">", "value": 10}
+/// value of IN is an array, e.g. {"column": "color", "op": "in", "value": ["red", "green"]}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Condition {
+    pub column: String,
+    pub op: CompareType,
+    pub value: Value,
+}
+
+impl Condition {
+    pub fn new(column: impl Into<String>, op: CompareType, value: Value) -> Self {
+        Self {
+            column: column.into(),
+            op,
+            value,
+        }
+    }
+}
+
+/// Order sorts rows of a table by one column
+/// e.g. {"column": "speed", "order_type": "Desc"}
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Order {
+    pub column: String,
+    pub order_type: OrderType,
+}
+
+impl Order {
+    pub fn new(column: impl Into<String>, order_type: OrderType) -> Self {
+        Self {
+            column: column.into(),
+            order_type,
+        }
+    }
+}
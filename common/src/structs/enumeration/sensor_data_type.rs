@@ -7,6 +7,10 @@ pub enum SensorDataType {
     Msg,
     IncResult,
     InvReport,
+    /// wrapper does not reply in time, see RequestPolicy
+    Timeout,
+    /// wrapper cannot provide data, e.g. sense is not supported
+    Error,
 }
 
 impl Default for SensorDataType {
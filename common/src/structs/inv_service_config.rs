@@ -1 +1,69 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
+use crate::structs::service_config::ServiceConfig;
+
+/// default number of traces recorded before invariants are generated
+pub const DEFAULT_INIT_THRO: usize = 10;
+
+fn default_init_thro() -> usize {
+    DEFAULT_INIT_THRO
+}
+
+/// InvServiceConfig is sent by app when it starts inv service.
+/// init_thro is the number of traces of a check location needed to generate invariants
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InvServiceConfig {
+    #[serde(default = "default_init_thro")]
+    init_thro: usize,
+}
+
+impl Default for InvServiceConfig {
+    fn default() -> Self {
+        Self {
+            init_thro: DEFAULT_INIT_THRO,
+        }
+    }
+}
+
+impl InvServiceConfig {
+    /// constructor
+    /// init_thro less than 1 is treated as 1
+    pub fn new(init_thro: usize) -> Self {
+        Self {
+            init_thro: init_thro.max(1),
+        }
+    }
+
+    /// getter
+    pub fn get_init_thro(&self) -> usize {
+        self.init_thro.max(1)
+    }
+}
+
+impl ServiceConfig for InvServiceConfig {
+    fn to_json_string(&self) -> String {
+        serde_json::to_string(self).expect("serialize inv service config fail")
+    }
+
+    fn to_json_object(&self) -> Value {
+        serde_json::to_value(self).expect("serialize inv service config fail")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_serialize_and_deserialize() {
+        let config: InvServiceConfig = serde_json::from_value(json!({})).unwrap();
+        assert_eq!(config, InvServiceConfig::default());
+
+        let config = InvServiceConfig::new(0);
+        println!("{}", config.to_json_string());
+        assert_eq!(config.get_init_thro(), 1);
+    }
+}
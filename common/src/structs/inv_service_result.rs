@@ -1 +1,103 @@
+use std::collections::BTreeMap;
 
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::structs::enumeration::check_result::CheckResult;
+use crate::structs::enumeration::sensor_data_type::SensorDataType;
+use crate::structs::sensor_data::SensorData;
+use crate::structs::service_result::ServiceResult;
+
+/// InvServiceResult is the result of one inv check.
+/// results maps every checked variable to its check result
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InvServiceResult {
+    pub line_number: i32,
+    pub group: i32,
+    pub results: BTreeMap<String, CheckResult>,
+}
+
+impl InvServiceResult {
+    pub fn new(line_number: i32, group: i32, results: BTreeMap<String, CheckResult>) -> Self {
+        Self {
+            line_number,
+            group,
+            results,
+        }
+    }
+
+    /// whether any variable violates invariants
+    pub fn is_violated(&self) -> bool {
+        self.results
+            .values()
+            .any(|result| *result == CheckResult::InvViolated)
+    }
+
+    /// to sensor data
+    /// return a sensor data of type InvReport with field line_number, group and results
+    pub fn to_sensor_data(&self) -> SensorData {
+        SensorData::new(
+            SensorDataType::InvReport,
+            vec![
+                "line_number".to_string(),
+                "group".to_string(),
+                "results".to_string(),
+            ],
+            vec![
+                json!(self.line_number),
+                json!(self.group),
+                serde_json::to_value(&self.results).expect("serialize inv results fail"),
+            ],
+        )
+    }
+
+    /// from sensor data
+    /// if sensor data is not InvReport or lack of fields, return none
+    pub fn from_sensor_data(sensor_data: &SensorData) -> Option<Self> {
+        if sensor_data.get_sensor_data_type() != SensorDataType::InvReport {
+            return None;
+        }
+        let line_number = sensor_data.get_data("line_number")?.as_i64()? as i32;
+        let group = sensor_data.get_data("group")?.as_i64()? as i32;
+        let results = serde_json::from_value(sensor_data.get_data("results")?.clone()).ok()?;
+        Some(Self {
+            line_number,
+            group,
+            results,
+        })
+    }
+}
+
+impl ServiceResult for InvServiceResult {
+    fn to_json_string(&self) -> String {
+        serde_json::to_string(self).expect("serialize inv service result fail")
+    }
+
+    fn to_json_object(&self) -> Value {
+        serde_json::to_value(self).expect("serialize inv service result fail")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sensor_data_convert() {
+        let result = InvServiceResult::new(
+            12,
+            0,
+            BTreeMap::from([("speed".to_string(), CheckResult::InvViolated)]),
+        );
+        let sensor_data = result.to_sensor_data();
+        println!("{}", serde_json::to_string(&sensor_data).unwrap());
+        assert_eq!(
+            InvServiceResult::from_sensor_data(&sensor_data),
+            Some(result)
+        );
+        assert_eq!(
+            InvServiceResult::from_sensor_data(&SensorData::new_without_data_with_default_type()),
+            None
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// RequestPolicy describes how platform waits for a wrapper to reply
+/// sensory_request or action_request.
+/// a request is sent again after a backoff if it is not replied within timeout_ms,
+/// a resource is not alive after max_timeout_num requests time out in a row
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+pub struct RequestPolicy {
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default = "default_retry_num")]
+    pub retry_num: u32,
+    #[serde(default = "default_backoff_ms")]
+    pub backoff_ms: u64,
+    #[serde(default = "default_max_timeout_num")]
+    pub max_timeout_num: u32,
+}
+
+fn default_timeout_ms() -> u64 {
+    500
+}
+
+fn default_retry_num() -> u32 {
+    2
+}
+
+fn default_backoff_ms() -> u64 {
+    100
+}
+
+fn default_max_timeout_num() -> u32 {
+    3
+}
+
+impl Default for RequestPolicy {
+    fn default() -> Self {
+        Self {
+            timeout_ms: default_timeout_ms(),
+            retry_num: default_retry_num(),
+            backoff_ms: default_backoff_ms(),
+            max_timeout_num: default_max_timeout_num(),
+        }
+    }
+}
+
+impl RequestPolicy {
+    pub fn new(timeout_ms: u64, retry_num: u32, backoff_ms: u64, max_timeout_num: u32) -> Self {
+        Self {
+            timeout_ms,
+            retry_num,
+            backoff_ms,
+            max_timeout_num,
+        }
+    }
+
+    pub fn get_timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+
+    /// backoff before the nth retry, starting from 1
+    /// it doubles every retry
+    pub fn get_backoff(&self, retry: u32) -> Duration {
+        let factor = 1u64 << retry.saturating_sub(1).min(16);
+        Duration::from_millis(self.backoff_ms.saturating_mul(factor))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_request_policy() {
+        let policy: RequestPolicy = serde_json::from_value(json!({"timeout_ms": 200})).unwrap();
+        println!("{:?}", policy);
+        assert_eq!(policy.get_timeout(), Duration::from_millis(200));
+        assert_eq!(policy.retry_num, default_retry_num());
+        assert_eq!(policy.get_backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.get_backoff(3), Duration::from_millis(400));
+    }
+}
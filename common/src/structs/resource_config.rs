@@ -1,6 +1,55 @@
+use std::collections::HashSet;
+use std::path::Path;
+
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use thiserror::Error;
 
 use crate::structs::enumeration::resource_type::ResourceType;
+use crate::structs::request_policy::RequestPolicy;
+use crate::structs::value_type::ValueType;
+
+/// keys a resource config may have, other keys are likely typos
+const KNOWN_KEYS: [&str; 7] = [
+    "name",
+    "type",
+    "fields",
+    "value_type",
+    "min_value_freq",
+    "max_value_freq",
+    "request_policy",
+];
+
+/// value freq range of a sensor if config does not give it
+pub const DEFAULT_MIN_VALUE_FREQ: u64 = 1;
+pub const DEFAULT_MAX_VALUE_FREQ: u64 = 1000;
+
+/// ConfigProblem is a problem found in a resource config,
+/// pointer is the json pointer of the value that has it, empty for the whole config
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{pointer}: {message}")]
+pub struct ConfigProblem {
+    pub pointer: String,
+    pub message: String,
+}
+
+#[derive(Error, Debug)]
+pub enum ResourceConfigError {
+    #[error("read resource config {0} err: {1}")]
+    Read(String, std::io::Error),
+    #[error("parse resource config {0} err: {1}")]
+    Parse(String, serde_json::Error),
+    #[error("invalid resource config {0}: {}", join_problems(.1))]
+    Invalid(String, Vec<ConfigProblem>),
+}
+
+fn join_problems(problems: &[ConfigProblem]) -> String {
+    problems
+        .iter()
+        .map(|problem| problem.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
 
 ///ResourceConfig used to describe the resource and be send to the platform
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
@@ -9,6 +58,9 @@ pub struct ResourceConfig {
     #[serde(rename = "type")]
     pub resource_type: ResourceType,
     pub fields: Option<Vec<String>>,
+    /// timeout and retry of requests to this resource, platform default if it is none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_policy: Option<RequestPolicy>,
 }
 
 impl ResourceConfig {
@@ -21,8 +73,205 @@ impl ResourceConfig {
             name,
             resource_type,
             fields,
+            request_policy: None,
+        }
+    }
+
+    pub fn new_with_request_policy(
+        name: Option<String>,
+        resource_type: ResourceType,
+        fields: Option<Vec<String>>,
+        request_policy: RequestPolicy,
+    ) -> Self {
+        Self {
+            name,
+            resource_type,
+            fields,
+            request_policy: Some(request_policy),
+        }
+    }
+
+    /// validate json of a resource config and deserialize it
+    /// source names where json comes from in error, e.g. file path
+    pub fn from_value_validated(source: &str, value: Value) -> Result<Self, ResourceConfigError> {
+        let problems = validate(&value);
+        if !problems.is_empty() {
+            return Err(ResourceConfigError::Invalid(source.to_string(), problems));
+        }
+        serde_json::from_value(value).map_err(|e| ResourceConfigError::Parse(source.to_string(), e))
+    }
+
+    /// read, validate and deserialize a resource config file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ResourceConfigError> {
+        let source = path.as_ref().display().to_string();
+        let json_str = std::fs::read_to_string(path.as_ref())
+            .map_err(|e| ResourceConfigError::Read(source.clone(), e))?;
+        let value = serde_json::from_str(&json_str)
+            .map_err(|e| ResourceConfigError::Parse(source.clone(), e))?;
+        Self::from_value_validated(&source, value)
+    }
+}
+
+/// validate json of a resource config
+/// it is also checked for keys of SensorMgr and ActorMgr, e.g. value_type
+/// return every problem found, empty if it is valid
+pub fn validate(value: &Value) -> Vec<ConfigProblem> {
+    let object = match value.as_object() {
+        Some(object) => object,
+        None => {
+            return vec![problem(
+                String::new(),
+                "resource config should be an object",
+            )]
+        }
+    };
+    let mut problems = Vec::new();
+
+    for key in object.keys() {
+        if !KNOWN_KEYS.contains(&key.as_str()) {
+            problems.push(problem(
+                pointer_of(key),
+                format!(
+                    "unknown key {}, expected one of {}",
+                    key,
+                    KNOWN_KEYS.join(", ")
+                ),
+            ));
+        }
+    }
+
+    match object.get("name") {
+        None => problems.push(problem(pointer_of("name"), "name is required")),
+        Some(Value::String(name)) if !name.trim().is_empty() => {}
+        Some(_) => problems.push(problem(
+            pointer_of("name"),
+            "name should be a non-empty string",
+        )),
+    }
+
+    let resource_type = match object.get("type") {
+        None => {
+            problems.push(problem(pointer_of("type"), "type is required"));
+            None
         }
+        Some(resource_type) => {
+            let parsed = serde_json::from_value::<ResourceType>(resource_type.clone()).ok();
+            if parsed.is_none() {
+                problems.push(problem(
+                    pointer_of("type"),
+                    format!(
+                        "unknown type {}, expected one of Sensor, Actor, Hybrid",
+                        resource_type
+                    ),
+                ));
+            }
+            parsed
+        }
+    };
+
+    let is_sensor = matches!(
+        resource_type,
+        Some(ResourceType::Sensor) | Some(ResourceType::Hybrid)
+    );
+    match object.get("fields") {
+        None | Some(Value::Null) if is_sensor => problems.push(problem(
+            pointer_of("fields"),
+            "fields is required by a sensor",
+        )),
+        None | Some(Value::Null) => {}
+        Some(Value::Array(fields)) => {
+            if fields.is_empty() && is_sensor {
+                problems.push(problem(
+                    pointer_of("fields"),
+                    "fields should not be empty for a sensor",
+                ));
+            }
+            let mut names = HashSet::new();
+            for (i, field) in fields.iter().enumerate() {
+                let pointer = format!("{}/{}", pointer_of("fields"), i);
+                match field.as_str() {
+                    None => problems.push(problem(pointer, "field should be a string")),
+                    Some(name) if !names.insert(name) => {
+                        problems.push(problem(pointer, format!("duplicate field {}", name)))
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        Some(_) => problems.push(problem(
+            pointer_of("fields"),
+            "fields should be an array of strings",
+        )),
+    }
+
+    if let Some(value_type) = object.get("value_type") {
+        if serde_json::from_value::<ValueType>(value_type.clone()).is_err() {
+            problems.push(problem(
+                pointer_of("value_type"),
+                format!(
+                    "unknown value_type {}, expected one of String, Int, Double",
+                    value_type
+                ),
+            ));
+        }
+    }
+
+    let min_value_freq = freq_of(object, "min_value_freq", &mut problems);
+    let max_value_freq = freq_of(object, "max_value_freq", &mut problems);
+    //an absent one takes its default, so a single key is also checked against the other
+    if min_value_freq.is_some() || max_value_freq.is_some() {
+        let min_value_freq = min_value_freq.unwrap_or(DEFAULT_MIN_VALUE_FREQ);
+        let max_value_freq = max_value_freq.unwrap_or(DEFAULT_MAX_VALUE_FREQ);
+        if min_value_freq > max_value_freq {
+            problems.push(problem(
+                pointer_of("min_value_freq"),
+                format!(
+                    "min_value_freq {} is greater than max_value_freq {}",
+                    min_value_freq, max_value_freq
+                ),
+            ));
+        }
+    }
+
+    if let Some(request_policy) = object.get("request_policy") {
+        if let Err(e) = serde_json::from_value::<RequestPolicy>(request_policy.clone()) {
+            problems.push(problem(
+                pointer_of("request_policy"),
+                format!("invalid request_policy: {}", e),
+            ));
+        }
+    }
+
+    problems
+}
+
+fn problem(pointer: String, message: impl Into<String>) -> ConfigProblem {
+    ConfigProblem {
+        pointer,
+        message: message.into(),
+    }
+}
+
+/// json pointer of a key of the config, see RFC 6901
+fn pointer_of(key: &str) -> String {
+    format!("/{}", key.replace('~', "~0").replace('/', "~1"))
+}
+
+/// frequency under key, none if it is absent or invalid
+fn freq_of(
+    object: &Map<String, Value>,
+    key: &str,
+    problems: &mut Vec<ConfigProblem>,
+) -> Option<u64> {
+    let freq = object.get(key)?;
+    let parsed = freq.as_u64();
+    if parsed.is_none() {
+        problems.push(problem(
+            pointer_of(key),
+            format!("{} should be a non-negative integer", key),
+        ));
     }
+    parsed
 }
 
 #[cfg(test)]
@@ -58,4 +307,69 @@ mod tests {
         let resource_config: ResourceConfig = serde_json::from_value(value).unwrap();
         println!("{:?}", resource_config);
     }
+
+    #[test]
+    fn test_validate() {
+        let value = json!({
+            "nmae": "test",
+            "type": "Sensor",
+            "fields": ["speed", 1, "speed"],
+            "value_type": "Float",
+            "min_value_freq": 10,
+            "max_value_freq": 5,
+        });
+        let problems = validate(&value);
+        for problem in &problems {
+            println!("{}", problem);
+        }
+        let pointers: Vec<&str> = problems
+            .iter()
+            .map(|problem| problem.pointer.as_str())
+            .collect();
+        assert_eq!(
+            pointers,
+            vec![
+                "/nmae",
+                "/name",
+                "/fields/1",
+                "/fields/2",
+                "/value_type",
+                "/min_value_freq"
+            ]
+        );
+
+        let value =
+            json!({"name": "test", "type": "Sensor", "fields": ["speed"], "min_value_freq": 2000});
+        assert_eq!(validate(&value)[0].pointer, "/min_value_freq");
+        let value =
+            json!({"name": "test", "type": "Sensor", "fields": ["speed"], "max_value_freq": 0});
+        assert_eq!(validate(&value)[0].pointer, "/min_value_freq");
+        let value =
+            json!({"name": "test", "type": "Sensor", "fields": ["speed"], "max_value_freq": 1});
+        assert!(validate(&value).is_empty());
+
+        let value = json!({"name": "test", "type": "Actor"});
+        assert!(validate(&value).is_empty());
+        let value = json!({"name": "test", "type": "Hybrid", "fields": []});
+        assert_eq!(validate(&value)[0].pointer, "/fields");
+        assert_eq!(validate(&json!([]))[0].pointer, "");
+
+        let e = ResourceConfig::from_value_validated("test.json", json!({"type": "sensor"}))
+            .unwrap_err();
+        println!("{}", e);
+        assert!(matches!(e, ResourceConfigError::Invalid(_, ref problems) if problems.len() == 2));
+    }
+
+    #[test]
+    fn test_from_file() {
+        for entry in std::fs::read_dir("../resources/config/wrapper").unwrap() {
+            let path = entry.unwrap().path();
+            let resource_config = ResourceConfig::from_file(&path).unwrap();
+            println!("{}: {:?}", path.display(), resource_config);
+        }
+        assert!(matches!(
+            ResourceConfig::from_file("../resources/config/wrapper/none.json"),
+            Err(ResourceConfigError::Read(_, _))
+        ));
+    }
 }
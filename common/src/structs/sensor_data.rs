@@ -119,6 +119,30 @@ impl SensorData {
     pub fn default_error() -> Self {
         Self::new_with_one_field_with_default_type("default".to_string(), json!("@#$%"))
     }
+
+    /// timeout
+    /// sent instead of data when wrapper does not reply after all attempts
+    pub fn new_timeout(attempts: u32, timeout_ms: u64) -> Self {
+        Self::new(
+            SensorDataType::Timeout,
+            vec!["attempts".to_string(), "timeout_ms".to_string()],
+            vec![json!(attempts), json!(timeout_ms)],
+        )
+    }
+
+    pub fn is_timeout(&self) -> bool {
+        self.sensor_data_type == SensorDataType::Timeout
+    }
+
+    /// error
+    /// sent instead of data when wrapper cannot provide it, with the reason
+    pub fn new_error(reason: &str) -> Self {
+        Self::new_with_one_field(SensorDataType::Error, "error".to_string(), json!(reason))
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.sensor_data_type == SensorDataType::Error
+    }
 }
 
 #[cfg(test)]
@@ -146,6 +170,29 @@ mod tests {
         println!("{}", serde_json::to_string(&sensor_data).unwrap());
     }
 
+    #[test]
+    fn test_new_timeout() {
+        let sensor_data = SensorData::new_timeout(3, 500);
+        let json_str = serde_json::to_string(&sensor_data).unwrap();
+        println!("{}", json_str);
+        let sensor_data: SensorData = serde_json::from_str(&json_str).unwrap();
+        assert!(sensor_data.is_timeout());
+        assert_eq!(sensor_data.get_data("attempts"), Some(&json!(3)));
+    }
+
+    #[test]
+    fn test_new_error() {
+        let sensor_data = SensorData::new_error("sense not supported");
+        let json_str = serde_json::to_string(&sensor_data).unwrap();
+        let sensor_data: SensorData = serde_json::from_str(&json_str).unwrap();
+        assert!(sensor_data.is_error());
+        assert!(!sensor_data.is_timeout());
+        assert_eq!(
+            sensor_data.get_data("error"),
+            Some(&json!("sense not supported"))
+        );
+    }
+
     #[test]
     fn test_new_with_json_str() {
         let json_str = r#"{
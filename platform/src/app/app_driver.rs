@@ -1,31 +1,55 @@
 use std::fmt::Display;
 use std::net::TcpStream;
-use std::str::FromStr;
 use std::sync::{Arc, RwLock, RwLockReadGuard, Weak};
+use std::time::{Duration, Instant};
 
+use dashmap::DashSet;
 use log::{debug, error, info, trace};
+use serde::de::DeserializeOwned;
 use serde_json::error::Category::Eof;
-use serde_json::{json, Value};
+use serde_json::{json, Map, Value};
 use thiserror::Error;
 
-use common::socket::cmd_message_grp_ids::GroupId;
+use common::socket::cmd_message_grp_ids::{CmdMessageGrpIds, GroupId};
+use common::socket::framing;
+use common::socket::framing::{Framing, FRAMING_KEY};
 use common::socket::tcp::TCP;
+use common::socket::udp;
+use common::structs::api_request::{ApiRequest, ApiRequestError};
+use common::structs::api_response::{ApiResponse, ErrorCode};
+use common::structs::ctx_service_config::CtxServiceConfig;
 use common::structs::enumeration::cmd_type::CmdType;
 use common::structs::enumeration::sensor_mode::SensorMode;
 use common::structs::enumeration::service_type::ServiceType;
-use common::structs::service_config::ServiceConfig;
+use common::structs::inv_service_config::InvServiceConfig;
+use common::structs::sensor_data::SensorData;
+use common::structs::service_result::ServiceResult;
 use common::structs::sync::synchronous_string::SynchronousString;
 use common::structs::time_line::FrequencyType;
 use common::SyncString;
 
 use crate::app::app_driver::app_driver_tcp::AppDriverTCP;
-use crate::app::app_mgr::{RwLockOptionSyncAppMgr, SyncAppName};
+use crate::app::app_mgr::{RwLockOptionSyncAppMgr, SyncAppMgr, SyncAppName};
 use crate::app::app_mgr_thread::{AppPort, IpString, SyncIpString, APP_MGR_THREAD};
+use crate::config::configuration::PUBSUB_CONFIG;
+use crate::database::condition::Condition;
+use crate::database::database::{Database, DbError};
+use crate::database::order::Order;
+use crate::database::table::Row;
 use crate::pubsub::abstract_subscriber;
 use crate::pubsub::abstract_subscriber::{AbstractSubscriber, SubscriberId};
+use crate::pubsub::channel::{
+    get_actor, get_actor_request, get_channel, get_sensor, get_sensor_request, ACTOR_SUFFIX,
+    DEFAULT_PRIO_ID, SENSOR_SUFFIX,
+};
+use crate::pubsub::publisher;
 use crate::pubsub::subscriber::Subscriber;
 use crate::resource::actor_mgr::SyncActorName;
-use crate::resource::sensor_mgr::SyncSensorName;
+use crate::resource::res_mgr_thread::RES_MGR_THREAD;
+use crate::resource::sensor_mgr::{SyncSensorMgr, SyncSensorName};
+use crate::service::ctx;
+use crate::service::ctx::ctx_server::AppCtxServer;
+use crate::service::inv;
 
 pub mod app_driver_tcp;
 
@@ -37,6 +61,11 @@ pub type RwLockGroupID = RwLock<GroupId>;
 pub type SyncAppDriver = Arc<AppDriver>;
 pub type RwLockOptionWeakAppDriver = RwLock<Option<Weak<AppDriver>>>;
 
+/// time to wait for sensor data requested by app in ms
+pub const SENSOR_DATA_TIMEOUT_MS: u64 = 3000;
+/// time to wait for action back of actor in ms
+pub const ACTOR_CMD_TIMEOUT_MS: u64 = 3000;
+
 pub struct AppDriver {
     abstract_subscriber: AbstractSubscriber,
     tcp: AppDriverTCP,
@@ -47,21 +76,94 @@ pub struct AppDriver {
     app_mgr: RwLockOptionSyncAppMgr,
     _get_sensor_data: SynchronousString,
     _actor_cmd: SynchronousString,
+    /// sensors whose data is waited by get sensor data
+    sensor_data_requests: DashSet<String>,
 }
 
 #[derive(Error, Debug)]
 pub enum AppDriverError {
-    #[error("parse api get None err: {0}")]
-    ParseApiGetNoneError(String),
+    #[error("parse api err: {0}")]
+    ParseApiError(#[from] ApiRequestError),
     #[error("parse api mismatch err: {0}")]
     ParseApiMismatchError(#[from] serde_json::Error),
 }
 
-fn option_to_app_driver_error<'a, T: ?Sized>(
-    option: Option<&'a T>,
-    msg: &str,
-) -> Result<&'a T, AppDriverError> {
-    option.ok_or_else(|| AppDriverError::ParseApiGetNoneError(msg.to_string()))
+impl AppDriverError {
+    /// error code replied to app
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppDriverError::ParseApiError(e) => e.code(),
+            AppDriverError::ParseApiMismatchError(_) => ErrorCode::InvalidParam,
+        }
+    }
+}
+
+/// parse service config sent by app, null means default config
+fn parse_service_config<T: DeserializeOwned + Default>(
+    config: &Value,
+) -> Result<T, AppDriverError> {
+    if config.is_null() {
+        Ok(T::default())
+    } else {
+        Ok(serde_json::from_value(config.clone())?)
+    }
+}
+
+/// parse sensor data published to sensor channel, data which is not json is kept as a string
+fn parse_sensor_data(msg: &str) -> Value {
+    serde_json::from_str(msg).unwrap_or_else(|_| json!(msg))
+}
+
+/// successful response with data, null if there is nothing to return
+fn ok_response(data: Value) -> String {
+    ApiResponse::ok(data).to_string()
+}
+
+/// failed response with error code and message
+fn error_response(code: ErrorCode, message: impl Display) -> String {
+    ApiResponse::error(code, message.to_string()).to_string()
+}
+
+fn app_not_registered(app_name: &str) -> String {
+    error_response(
+        ErrorCode::AppNotRegistered,
+        format!("app {} is not registered", app_name),
+    )
+}
+
+fn sensor_not_found(sensor_name: &str) -> String {
+    error_response(
+        ErrorCode::SensorNotFound,
+        format!("sensor {} does not exist", sensor_name),
+    )
+}
+
+fn sensor_not_registered(sensor_name: &str) -> String {
+    error_response(
+        ErrorCode::SensorNotRegistered,
+        format!("sensor {} is not registered", sensor_name),
+    )
+}
+
+fn actor_not_found(actor_name: &str) -> String {
+    error_response(
+        ErrorCode::ActorNotFound,
+        format!("actor {} does not exist", actor_name),
+    )
+}
+
+fn actor_not_registered(actor_name: &str) -> String {
+    error_response(
+        ErrorCode::ActorNotRegistered,
+        format!("actor {} is not registered", actor_name),
+    )
+}
+
+fn inv_service_off(app_name: &str) -> String {
+    error_response(
+        ErrorCode::ServiceError,
+        format!("inv service of app {} is off", app_name),
+    )
 }
 
 //todo: in java while app drop tcp connection, it will throw null pointer exception, which conflict with its intended behavior: drop app manger
@@ -79,6 +181,7 @@ impl AppDriver {
         let app_mgr = RwLock::new(None);
         let _get_sensor_data = SynchronousString::new();
         let _actor_cmd = SynchronousString::new();
+        let sensor_data_requests = DashSet::new();
         Self {
             abstract_subscriber,
             tcp,
@@ -89,16 +192,13 @@ impl AppDriver {
             app_mgr,
             _get_sensor_data,
             _actor_cmd,
+            sensor_data_requests,
         }
     }
 
     ///add to static set Abstract Subscriber objs
     pub fn add_to_subscriber_objs(stream: TcpStream) -> SyncAppDriver {
-        let mut subscriber_objs = abstract_subscriber::get_objs()
-            .write()
-            .expect("get subscriber objs write lock fail");
-        let app_driver = Arc::new(Self::new(stream, subscriber_objs.len() as SubscriberId));
-        subscriber_objs.push(app_driver.clone());
+        let app_driver = abstract_subscriber::register(|id| Self::new(stream, id));
 
         app_driver.tcp.set_app_driver_weak(&app_driver);
 
@@ -142,8 +242,18 @@ impl AppDriver {
             if let Some(msg_from_client) = driver.tcp.recv() {
                 trace!("{}", msg_from_client);
                 //todo: notion java中应该所有线程都关闭才借书，注意一下getmsgthread
-                let json_object: Value =
-                    serde_json::from_str(&msg_from_client).expect("parse json fail");
+                // a line which is not json is refused, app driver keeps serving the app
+                let json_object: Value = match serde_json::from_str(&msg_from_client) {
+                    Ok(json_object) => json_object,
+                    Err(e) => {
+                        error!("{} -> platform: Error: not json: {}", msg_from_client, e);
+                        driver.tcp.send(&error_response(
+                            ErrorCode::InvalidParam,
+                            format!("request is not json: {}", e),
+                        ));
+                        continue;
+                    }
+                };
 
                 // log the message
                 if driver.app_mgr.read().expect("read app mgr fail").is_some() {
@@ -163,21 +273,28 @@ impl AppDriver {
                 }
 
                 // get and parse api
-                let api = json_object["api"].as_str();
-                let ret: Option<String> = match Self::parse_api(driver.clone(), api, &json_object) {
-                    Ok(ret) => Some(ret),
+                let request = ApiRequest::from_value(json_object);
+                let is_connect = matches!(request, Ok(ApiRequest::Connect(_)));
+                let is_disconnect = matches!(request, Ok(ApiRequest::Disconnect));
+                let ret = match request
+                    .map_err(AppDriverError::from)
+                    .and_then(|request| Self::parse_api(driver.clone(), request))
+                {
+                    Ok(ret) => ret,
                     Err(e) => {
                         error!("{} -> platform: Error: {}", msg_from_client, e);
-                        None
+                        error_response(e.code(), e)
                     }
                 };
 
                 // send result
-                if let Some(ret) = &ret {
-                    driver.tcp.send(&ret);
-                } else {
-                    //todo: in java, it will return null pointer exception, how to handle it in rust
-                    panic!("ret is none")
+                driver.tcp.send(&ret);
+
+                // reply of connect is the last line, frames start after it
+                if is_connect {
+                    driver
+                        .tcp
+                        .set_framing(Framing::from_version(framing::version_of(&ret)));
                 }
 
                 // log platform to app
@@ -191,390 +308,118 @@ impl AppDriver {
                             .as_ref()
                             .expect("app mgr is none")
                             .get_app_name_clone(),
-                        ret.expect("ret is none")
+                        ret
                     );
                 } else {
-                    info!("[Platform -> AppDriver]: {}", ret.expect("ret is none"))
+                    info!("[Platform -> AppDriver]: {}", ret)
                 }
 
                 // disconnect break
-                // the reason here can use expect is that api is none, it will panic above
-                if api.expect("api is none").to_ascii_lowercase().as_str() == "disconnect" {
+                if is_disconnect {
                     break;
                 }
             } else {
                 break;
             }
         }
-        trace!(
-            "app tcp close {}: run success",
-            driver
-                .tcp
-                .get_socket()
-                .peer_addr()
-                .expect("get peer addr fail")
-        );
+        // close releases app too, whether app disconnects or tcp is broken
         driver.tcp.close();
+        trace!("app tcp close: run success");
     }
 
     /// parse api and do the corresponding operation
     /// return a result of string
-    fn parse_api(
-        driver: SyncAppDriver,
-        api: Option<&str>,
-        json_object: &Value,
-    ) -> Result<String, AppDriverError> {
-        if let Some(api) = api {
-            match api.to_ascii_lowercase().as_str() {
-                "connect" => {
-                    return Ok(driver.connect_platform());
-                }
-                "disconnect" => {
-                    return Ok(driver.disconnect_platform());
-                }
-                "is_connected" => {
-                    return Ok(driver.check_connect());
-                }
-                "register_app" => {
-                    let app_name = option_to_app_driver_error(
-                        json_object["app_name"].as_str(),
-                        "app_name is none",
-                    )?;
-                    return Ok(Self::register_app(
-                        driver.clone(),
-                        Arc::new(app_name.to_string()),
-                    ));
-                }
-                "unregister_app" => {
-                    let app_name = option_to_app_driver_error(
-                        json_object["app_name"].as_str(),
-                        "app_name is none",
-                    )?;
-                    return Ok(driver.unregister_app(Arc::new(app_name.to_string())));
-                }
-                "get_supported_sensors" => {
-                    return Ok(driver.get_supported_sensors());
-                }
-                "get_registered_sensors" => {
-                    let app_name = option_to_app_driver_error(
-                        json_object["app_name"].as_str(),
-                        "app_name is none",
-                    )?;
-                    return Ok(driver.get_registered_sensors(Arc::new(app_name.to_string())));
-                }
-                "get_registered_sensors_status" => {
-                    let app_name = option_to_app_driver_error(
-                        json_object["app_name"].as_str(),
-                        "app_name is none",
-                    )?;
-                    return Ok(driver.get_registered_sensors_status(Arc::new(app_name.to_string())));
-                }
-                "register_sensor" => {
-                    let app_name = option_to_app_driver_error(
-                        json_object["app_name"].as_str(),
-                        "app_name is none",
-                    )?;
-                    let sensor_name = option_to_app_driver_error(
-                        json_object["sensor_name"].as_str(),
-                        "sensor_name is none",
-                    )?;
-                    let sensor_mode: SensorMode =
-                        serde_json::from_str(option_to_app_driver_error(
-                            json_object["sensor_mode"].as_str(),
-                            "sensor_mode is none",
-                        )?)?;
-                    let freq = option_to_app_driver_error(
-                        json_object["freq"].as_u64().as_ref(),
-                        "freq is none",
-                    )?
-                    .clone();
-                    return Ok(driver.register_sensor(
-                        Arc::new(app_name.to_string()),
-                        Arc::new(sensor_name.to_string()),
-                        sensor_mode,
-                        freq as FrequencyType,
-                    ));
-                }
-                "cancel_sensor" => {
-                    let app_name = option_to_app_driver_error(
-                        json_object["app_name"].as_str(),
-                        "app_name is none",
-                    )?;
-                    let sensor_name = option_to_app_driver_error(
-                        json_object["sensor_name"].as_str(),
-                        "sensor_name is none",
-                    )?;
-                    return Ok(driver.cancel_sensor(
-                        &Arc::new(app_name.to_string()),
-                        &Arc::new(sensor_name.to_string()),
-                    ));
-                }
-                "cancel_all_sensors" => {
-                    let app_name = option_to_app_driver_error(
-                        json_object["app_name"].as_str(),
-                        "app_name is none",
-                    )?;
-                    return Ok(driver.cancel_all_sensors(&Arc::new(app_name.to_string())));
-                }
-                "get_sensor_data" => {
-                    let app_name = option_to_app_driver_error(
-                        json_object["app_name"].as_str(),
-                        "app_name is none",
-                    )?;
-                    let sensor_name = option_to_app_driver_error(
-                        json_object["sensor_name"].as_str(),
-                        "sensor_name is none",
-                    )?;
-                    return Ok(driver.get_sensor_data(
-                        Arc::new(app_name.to_string()),
-                        Arc::new(sensor_name.to_string()),
-                    ));
-                }
-                "get_all_sensor_data" => {
-                    let app_name = option_to_app_driver_error(
-                        json_object["app_name"].as_str(),
-                        "app_name is none",
-                    )?;
-                    return Ok(driver.get_all_sensor_data(Arc::new(app_name.to_string())));
-                }
-                "get_msg_thread" => {
-                    let cmd = serde_json::from_str(option_to_app_driver_error(
-                        json_object["cmd"].as_str(),
-                        "cmd is none",
-                    )?)?;
-                    let app_name = option_to_app_driver_error(
-                        json_object["app_name"].as_str(),
-                        "app_name is none",
-                    )?;
-                    return Ok(driver.get_msg_thread(Arc::new(app_name.to_string()), cmd));
-                }
-                "get_supported_actors" => {
-                    return Ok(driver.get_supported_actors());
-                }
-                "get_registered_actors" => {
-                    let app_name = option_to_app_driver_error(
-                        json_object["app_name"].as_str(),
-                        "app_name is none",
-                    )?;
-                    return Ok(driver.get_registered_actors(Arc::new(app_name.to_string())));
-                }
-                "get_registered_actors_status" => {
-                    let app_name = option_to_app_driver_error(
-                        json_object["app_name"].as_str(),
-                        "app_name is none",
-                    )?;
-                    return Ok(driver.get_registered_actors_status(Arc::new(app_name.to_string())));
-                }
-                "register_actor" => {
-                    let app_name = option_to_app_driver_error(
-                        json_object["app_name"].as_str(),
-                        "app_name is none",
-                    )?;
-                    let actor_name = option_to_app_driver_error(
-                        json_object["actor_name"].as_str(),
-                        "actor_name is none",
-                    )?;
-                    return Ok(driver.register_actor(
-                        Arc::new(app_name.to_string()),
-                        Arc::new(actor_name.to_string()),
-                    ));
-                }
-                "cancel_actor" => {
-                    let app_name = option_to_app_driver_error(
-                        json_object["app_name"].as_str(),
-                        "app_name is none",
-                    )?;
-                    let actor_name = option_to_app_driver_error(
-                        json_object["actor_name"].as_str(),
-                        "actor_name is none",
-                    )?;
-                    return Ok(driver.cancel_actor(
-                        &Arc::new(app_name.to_string()),
-                        &Arc::new(actor_name.to_string()),
-                    ));
-                }
-                "cancel_all_actors" => {
-                    let app_name = option_to_app_driver_error(
-                        json_object["app_name"].as_str(),
-                        "app_name is none",
-                    )?;
-                    return Ok(driver.cancel_all_actors(&Arc::new(app_name.to_string())));
-                }
-                "set_actor_cmd" => {
-                    let app_name = option_to_app_driver_error(
-                        json_object["app_name"].as_str(),
-                        "app_name is none",
-                    )?;
-                    let actor_name = option_to_app_driver_error(
-                        json_object["actor_name"].as_str(),
-                        "actor_name is none",
-                    )?;
-                    let action = option_to_app_driver_error(
-                        json_object["action"].as_str(),
-                        "action is none",
-                    )?;
-                    return Ok(driver.set_actor_cmd(
-                        Arc::new(app_name.to_string()),
-                        Arc::new(actor_name.to_string()),
-                        action.to_string(),
-                    ));
-                }
-                "is_service_on" => {
-                    let app_name = option_to_app_driver_error(
-                        json_object["app_name"].as_str(),
-                        "app_name is none",
-                    )?;
-                    let service = serde_json::from_str(option_to_app_driver_error(
-                        json_object["service_type"].as_str(),
-                        "service is none",
-                    )?)?;
-                    return Ok(driver.is_service_on(Arc::new(app_name.to_string()), service));
-                }
-                "get_sensor_info" => {
-                    let sensor_name = option_to_app_driver_error(
-                        json_object["sensor_name"].as_str(),
-                        "sensor_name is none",
-                    )?;
-                    return Ok(driver.get_sensor_info(Arc::new(sensor_name.to_string())));
-                }
-                "get_sensor_info_and_data" => {
-                    let sensor_name = option_to_app_driver_error(
-                        json_object["sensor_name"].as_str(),
-                        "sensor_name is none",
-                    )?;
-                    return Ok(driver.get_sensor_info_and_data(Arc::new(sensor_name.to_string())));
-                }
-                "get_all_sensor_info" => {
-                    return Ok(driver.get_supported_sensors());
-                }
-                "get_all_sensor_info_and_data" => {
-                    return Ok(driver.get_all_sensor_info_and_data());
-                }
-                "get_actor_info" => {
-                    let actor_name = option_to_app_driver_error(
-                        json_object["actor_name"].as_str(),
-                        "actor_name is none",
-                    )?;
-                    return Ok(driver.get_actor_info(Arc::new(actor_name.to_string())));
-                }
-                "get_all_actor_info" => {
-                    return Ok(driver.get_supported_actors());
-                }
-                "get_app_info" => {
-                    let app_name = option_to_app_driver_error(
-                        json_object["app_name"].as_str(),
-                        "app_name is none",
-                    )?;
-                    return Ok(driver.get_app_info(Arc::new(app_name.to_string())));
-                }
-                "get_all_app_info" => {
-                    return Ok(driver.get_all_app_info());
-                }
-                "get_service_info" => {
-                    let service = serde_json::from_str(option_to_app_driver_error(
-                        json_object["service_type"].as_str(),
-                        "service is none",
-                    )?)?;
-                    let app_name = option_to_app_driver_error(
-                        json_object["app_name"].as_str(),
-                        "app_name is none",
-                    )?;
-                    return Ok(driver.get_service_info(Arc::new(app_name.to_string()), service));
-                }
-                "get_all_service_info" => {
-                    let app_name = option_to_app_driver_error(
-                        json_object["app_name"].as_str(),
-                        "app_name is none",
-                    )?;
-                    return Ok(driver.get_all_service_info(Arc::new(app_name.to_string())));
-                }
-
-                "start_service" => {
-                    todo!()
-                }
-                "stop_service" => {
-                    todo!()
-                }
-                "service-call" => {
-                    todo!()
-                }
-                "inv_monitor" => {
-                    todo!()
-                }
-                "inv_is_monitored" => {
-                    todo!()
-                }
-                "inv_check" => {
-                    todo!()
-                }
-                "inv_save" => {
-                    todo!()
-                }
-                "inv_load" => {
-                    todo!()
-                }
-                "inv_check_generated" => {
-                    todo!()
-                }
-
-                "db_create" => {
-                    todo!()
-                }
-                "db_drop" => {
-                    todo!()
-                }
-                "db_insert" => {
-                    todo!()
-                }
-                "db_inserts" => {
-                    todo!()
-                }
-                "db_update" => {
-                    todo!()
-                }
-                "db_delete" => {
-                    todo!()
-                }
-                "db_deletes" => {
-                    todo!()
-                }
-                "db_row" => {
-                    todo!()
-                }
-                "db_rows" => {
-                    todo!()
-                }
-                "db_column" => {
-                    todo!()
-                }
-                "db_columns" => {
-                    todo!()
-                }
-                "db_cell" => {
-                    todo!()
-                }
-                "db_header" => {
-                    todo!()
-                }
-                "db_count" => {
-                    todo!()
-                }
-
-                _ => {
-                    return Err(AppDriverError::ParseApiGetNoneError(format!(
-                        "api: {} is not supported",
-                        api
-                    )));
-                }
+    fn parse_api(driver: SyncAppDriver, request: ApiRequest) -> Result<String, AppDriverError> {
+        Ok(match request {
+            ApiRequest::Connect(r) => driver.connect_platform(r.framing),
+            ApiRequest::Disconnect => driver.disconnect_platform(),
+            ApiRequest::IsConnected => driver.check_connect(),
+            ApiRequest::RegisterApp(r) => Self::register_app(driver, r.app_name),
+            ApiRequest::UnregisterApp(r) => driver.unregister_app(r.app_name),
+
+            ApiRequest::GetSupportedSensors => driver.get_supported_sensors(),
+            ApiRequest::GetRegisteredSensors(r) => driver.get_registered_sensors(r.app_name),
+            ApiRequest::GetRegisteredSensorsStatus(r) => {
+                driver.get_registered_sensors_status(r.app_name)
             }
-        } else {
-            return Err(AppDriverError::ParseApiGetNoneError(
-                "api is none".to_string(),
-            ));
-        }
-
-        //todo
-        Ok("".to_string())
+            ApiRequest::RegisterSensor(r) => {
+                driver.register_sensor(r.app_name, r.sensor_name, r.sensor_mode, r.freq)
+            }
+            ApiRequest::CancelSensor(r) => driver.cancel_sensor(&r.app_name, &r.sensor_name),
+            ApiRequest::CancelAllSensors(r) => driver.cancel_all_sensors(&r.app_name),
+            ApiRequest::GetSensorData(r) => driver.get_sensor_data(r.app_name, r.sensor_name),
+            ApiRequest::GetAllSensorData(r) => driver.get_all_sensor_data(r.app_name),
+            ApiRequest::GetMsgThread(r) => driver.get_msg_thread(r.app_name, r.cmd),
+
+            ApiRequest::GetSupportedActors => driver.get_supported_actors(),
+            ApiRequest::GetRegisteredActors(r) => driver.get_registered_actors(r.app_name),
+            ApiRequest::GetRegisteredActorsStatus(r) => {
+                driver.get_registered_actors_status(r.app_name)
+            }
+            ApiRequest::RegisterActor(r) => driver.register_actor(r.app_name, r.actor_name),
+            ApiRequest::CancelActor(r) => driver.cancel_actor(&r.app_name, &r.actor_name),
+            ApiRequest::CancelAllActors(r) => driver.cancel_all_actors(&r.app_name),
+            ApiRequest::SetActorCmd(r) => driver.set_actor_cmd(r.app_name, r.actor_name, r.action),
+
+            ApiRequest::GetSensorInfo(r) => driver.get_sensor_info(r.sensor_name),
+            ApiRequest::GetSensorInfoAndData(r) => driver.get_sensor_info_and_data(r.sensor_name),
+            ApiRequest::GetAllSensorInfo => driver.get_supported_sensors(),
+            ApiRequest::GetAllSensorInfoAndData => driver.get_all_sensor_info_and_data(),
+            ApiRequest::GetActorInfo(r) => driver.get_actor_info(r.actor_name),
+            ApiRequest::GetAllActorInfo => driver.get_supported_actors(),
+            ApiRequest::GetAppInfo(r) => driver.get_app_info(r.app_name),
+            ApiRequest::GetAllAppInfo => driver.get_all_app_info(),
+            ApiRequest::GetServiceInfo(r) => driver.get_service_info(r.app_name, r.service_type),
+            ApiRequest::GetAllServiceInfo(r) => driver.get_all_service_info(r.app_name),
+
+            ApiRequest::IsServiceOn(r) => driver.is_service_on(r.app_name, r.service_type),
+            ApiRequest::StartService(r) => {
+                driver.start_service(r.app_name, r.service_type, &r.config)?
+            }
+            ApiRequest::StopService(r) => driver.stop_service(r.app_name, r.service_type),
+            ApiRequest::ServiceCall(r) => {
+                driver.service_call(r.app_name, r.service_type, r.cmd, &r.config)?
+            }
+            ApiRequest::InvMonitor(r) => driver.monitor(r.app_name, r.objs),
+            ApiRequest::InvIsMonitored(r) => driver.is_monitored(r.app_name, r.objs),
+            ApiRequest::InvCheck(r) => driver.check(r.app_name, r.line_number, r.objs),
+            ApiRequest::InvSave(r) => driver.save(r.app_name),
+            ApiRequest::InvLoad(r) => driver.load(r.app_name, r.content),
+            ApiRequest::InvCheckGenerated(r) => driver.check_generated(r.app_name),
+
+            ApiRequest::DbCreate(r) => driver.database_create(
+                r.app_name,
+                r.table_name,
+                r.primary_key,
+                r.header,
+                r.row_limit,
+            ),
+            ApiRequest::DbDrop(r) => driver.database_drop(r.app_name, r.table_name),
+            ApiRequest::DbInsert(r) => driver.database_insert(r.app_name, r.table_name, r.row),
+            ApiRequest::DbInserts(r) => driver.database_inserts(r.app_name, r.table_name, r.rows),
+            ApiRequest::DbUpdate(r) => {
+                driver.database_update(r.app_name, r.table_name, r.values, r.conditions)
+            }
+            ApiRequest::DbDelete(r) => driver.database_delete(r.app_name, r.table_name, &r.key),
+            ApiRequest::DbDeletes(r) => {
+                driver.database_deletes(r.app_name, r.table_name, r.conditions)
+            }
+            ApiRequest::DbRow(r) => driver.database_row(r.app_name, r.table_name, &r.key),
+            ApiRequest::DbRows(r) => {
+                driver.database_rows(r.app_name, r.table_name, r.conditions, r.order, r.limit)
+            }
+            ApiRequest::DbColumn(r) => {
+                driver.database_column(r.app_name, r.table_name, &r.column, r.conditions)
+            }
+            ApiRequest::DbColumns(r) => {
+                driver.database_columns(r.app_name, r.table_name, r.columns, r.conditions)
+            }
+            ApiRequest::DbCell(r) => {
+                driver.database_cell(r.app_name, r.table_name, &r.key, &r.column)
+            }
+            ApiRequest::DbHeader(r) => driver.database_header(r.app_name, r.table_name),
+            ApiRequest::DbCount(r) => driver.database_count(r.app_name, r.table_name, r.conditions),
+        })
     }
 }
 
@@ -586,39 +431,29 @@ impl AppDriver {
 impl AppDriver {
     /// connect platform
     /// return a string about connect state
-    fn connect_platform(&self) -> String {
-        let ret_json = json!({"state" : true});
-        ret_json.to_string()
+    /// frame version requested by app is accepted in data of reply,
+    /// see socket::framing
+    fn connect_platform(&self, framing: Option<u64>) -> String {
+        match framing::negotiate(framing) {
+            Some(frame_version) => ok_response(json!({ FRAMING_KEY: frame_version })),
+            None => ok_response(Value::Null),
+        }
     }
 
     /// disconnect platform
+    /// release registered app, it is fine that no app is registered
     /// return a string about disconnect state
     fn disconnect_platform(&self) -> String {
-        let app_option = self
-            .app_mgr
-            .read()
-            .expect("read app mgr fail")
-            .as_ref()
-            .map(|app_mgr| app_mgr.clone());
-
-        if let Some(app) = app_option {
-            self.unregister_app(app.get_app_name_clone())
-        } else {
-            let ret_json = json!({"state" : true});
-            ret_json.to_string()
-        }
+        self.release_app();
+        ok_response(Value::Null)
     }
 
     /// check connect
+    /// connected is true if an app is registered by this driver
     /// return a string about connect state
     fn check_connect(&self) -> String {
-        if let Some(app) = self.app_mgr.read().expect("read app mgr fail").as_ref() {
-            let ret_json = json!({"state" : true});
-            ret_json.to_string()
-        } else {
-            let ret_json = json!({"state" : false});
-            ret_json.to_string()
-        }
+        let connected = self.app_mgr.read().expect("read app mgr fail").is_some();
+        ok_response(json!({"connected" : connected}))
     }
 
     /// register app
@@ -642,8 +477,10 @@ impl AppDriver {
                     .expect("get peer addr fail"),
                 app_name
             );
-            let ret_json = json!({"state" : false});
-            ret_json.to_string()
+            error_response(
+                ErrorCode::AppAlreadyRegistered,
+                format!("app {} is already registered", app.get_app_name_clone()),
+            )
         } else {
             driver
                 .client_ip
@@ -684,91 +521,148 @@ impl AppDriver {
                 .expect("app mgr is none")
                 .set_app_driver(&driver);
 
-            let ret_json = json!({"state" : true, "udp_port" : udp_port});
-            ret_json.to_string()
+            ok_response(json!({"udp_port" : udp_port}))
         }
     }
 
     /// unregister app
     /// return a string about app info
     fn unregister_app(&self, app_name: SyncAppName) -> String {
-        let app_option = self
+        let is_registered = self
             .app_mgr
             .read()
             .expect("read app mgr fail")
             .as_ref()
-            .map(|app_mgr| app_mgr.clone());
-
-        match app_option {
-            Some(app) if app.get_app_name_clone().eq_ignore_ascii_case(&app_name) => {
-                //todo:
-                //self.cancel_all_sensors(&app_name);
-                //self.cancel_all_actors(&app_name);
-
-                //todo: remove app.database
+            .is_some_and(|app| app.get_app_name_clone().eq_ignore_ascii_case(&app_name));
 
-                self.app_mgr.write().expect("write app mgr fail").take();
-
-                APP_MGR_THREAD.unregister_app_mgr(&app_name);
-                APP_MGR_THREAD.remove_app_port(self.tcp.get_socket(), self.get_udp_port());
-
-                self.client_ip.write().expect("write client ip fail").take();
-                self.client_udp_port
-                    .write()
-                    .expect("write client udp port fail")
-                    .take();
-                //todo: check msg thread later
-                *self
-                    .get_msg_thread_state
-                    .write()
-                    .expect("write get msg thread state fail") = false;
-                //todo: does grd id be set to -1? :question: can we do set actor for other app?
-
-                //todo: service call
+        if is_registered && self.release_app() {
+            ok_response(Value::Null)
+        } else {
+            app_not_registered(&app_name)
+        }
+    }
 
-                let ret_json = json!({"state" : true});
-                ret_json.to_string()
-            }
-            _ => {
-                let ret_json = json!({"state" : false});
-                ret_json.to_string()
-            }
+    /// release app
+    /// the only cleanup path of app, used by unregister app, disconnect platform and broken tcp
+    /// sensors stop polling for app and nothing of app is left in app mgr thread or channels
+    /// return false if no app is registered
+    pub(crate) fn release_app(&self) -> bool {
+        // take app mgr first, so that concurrent releases clean up once
+        let app_mgr = match self.app_mgr.write().expect("write app mgr fail").take() {
+            Some(app_mgr) => app_mgr,
+            None => return false,
+        };
+        let app_name = app_mgr.get_app_name_clone();
+
+        self._cancel_all_sensors(&app_mgr);
+        self._cancel_all_actors(&app_mgr);
+        // database of app is kept, so app finds its tables after it registers back
+        if let Some(ctx_server) = app_mgr.take_ctx_server() {
+            ctx_server.close();
         }
+        app_mgr.take_inv_service();
+        // channels subscribed without a sensor or actor of app mgr
+        self.unsubscribe_all();
+        self.sensor_data_requests.clear();
+
+        APP_MGR_THREAD.unregister_app_mgr(&app_name);
+        APP_MGR_THREAD.remove_grp_id(&app_name);
+        let client_ip = self.client_ip.write().expect("write client ip fail").take();
+        let udp_port = self
+            .client_udp_port
+            .write()
+            .expect("write client udp port fail")
+            .take();
+        if let (Some(client_ip), Some(udp_port)) = (client_ip, udp_port) {
+            APP_MGR_THREAD.remove_app_port(&client_ip, udp_port);
+        }
+        *self.grp_id.write().expect("write grp id fail") = -1;
+        *self
+            .get_msg_thread_state
+            .write()
+            .expect("write get msg thread state fail") = false;
+
+        info!("app {} release success", app_name);
+        true
     }
 }
 
 /// sensor related
 impl AppDriver {
     /// get supported sensors
-    /// return a string about sensor info
+    /// return a string about sensor info of all sensors
     fn get_supported_sensors(&self) -> String {
-        todo!()
+        ok_response(json!({"sensors" : RES_MGR_THREAD.create_all_sensor_info()}))
     }
 
     /// get registered sensors
-    /// return a string about sensor name of app
+    /// return a string about sensor info of sensors registered by app
     fn get_registered_sensors(&self, app_name: SyncAppName) -> String {
-        todo!()
+        let app_mgr = match self.get_app_mgr_by_name(&app_name) {
+            Some(app_mgr) => app_mgr,
+            None => return app_not_registered(&app_name),
+        };
+        let sensors: Map<String, Value> = app_mgr
+            .get_sensor_names_vec()
+            .iter()
+            .filter_map(|sensor_name| RES_MGR_THREAD.get_sensor_mgrs().get(sensor_name))
+            .map(|sensor_mgr| {
+                (
+                    sensor_mgr.key().to_string(),
+                    json!(sensor_mgr.create_sensor_info()),
+                )
+            })
+            .collect();
+        ok_response(json!({"sensors" : sensors}))
     }
 
     /// get registered sensors status
+    /// status is true if all sensors registered by app are alive
     /// return a string about sensor status of app
     fn get_registered_sensors_status(&self, app_name: SyncAppName) -> String {
-        todo!()
+        let app_mgr = match self.get_app_mgr_by_name(&app_name) {
+            Some(app_mgr) => app_mgr,
+            None => return app_not_registered(&app_name),
+        };
+        let status = app_mgr.get_sensor_names_vec().iter().all(|sensor_name| {
+            RES_MGR_THREAD
+                .get_sensor_mgrs()
+                .get(sensor_name)
+                .is_some_and(|sensor_mgr| sensor_mgr.is_alive())
+        });
+        ok_response(json!({"status" : status}))
     }
 
     /// register sensor inner
+    /// sensor data reaches app driver at the lowest priority of its group,
+    /// so that ctx server can check it before
     fn _register_sensor(
         &self,
-        app_name: SyncAppName,
-        sensor_name: SyncSensorName,
+        app_mgr: &SyncAppMgr,
+        sensor_mgr: &SyncSensorMgr,
         sensor_mode: SensorMode,
         freq: FrequencyType,
     ) {
-        todo!()
+        let sensor_name = sensor_mgr.get_sensor_name().clone();
+        app_mgr.add_sensor(sensor_name.clone());
+        self.subscribe(
+            &get_sensor(&sensor_name),
+            Some(app_mgr.get_grp_id_clone()),
+            Some(DEFAULT_PRIO_ID),
+        );
+        if let Some(ctx_server) = app_mgr.get_ctx_server() {
+            ctx_server.attach_sensor(&sensor_name);
+        }
+        let freq = match sensor_mode {
+            SensorMode::Passive => Some(freq),
+            SensorMode::Active => None,
+        };
+        sensor_mgr.add_app(app_mgr, freq);
     }
 
     /// register sensor
+    /// passive sensor is requested freq times per second for app,
+    /// freq of active sensor is ignored
     /// return a string about whether register sensor success
     fn register_sensor(
         &self,
@@ -777,85 +671,344 @@ impl AppDriver {
         sensor_mode: SensorMode,
         freq: FrequencyType,
     ) -> String {
-        todo!()
+        let app_mgr = match self.get_app_mgr_by_name(&app_name) {
+            Some(app_mgr) => app_mgr,
+            None => return app_not_registered(&app_name),
+        };
+        if app_mgr.get_sensors().contains(&sensor_name) {
+            return error_response(
+                ErrorCode::SensorAlreadyRegistered,
+                format!("sensor {} is already registered", sensor_name),
+            );
+        }
+        let sensor_mgr = match RES_MGR_THREAD.get_sensor_mgrs().get(&sensor_name) {
+            Some(sensor_mgr) if sensor_mgr.is_alive() => sensor_mgr.clone(),
+            Some(_) => {
+                return error_response(
+                    ErrorCode::SensorNotAlive,
+                    format!("sensor {} is not alive", sensor_name),
+                )
+            }
+            None => return sensor_not_found(&sensor_name),
+        };
+        if sensor_mode == SensorMode::Passive && !sensor_mgr.check_value_freq(freq as u64) {
+            return error_response(
+                ErrorCode::FreqOutOfRange,
+                format!("freq {} of sensor {} is out of range", freq, sensor_name),
+            );
+        }
+        self._register_sensor(&app_mgr, &sensor_mgr, sensor_mode, freq);
+        info!(
+            "app {} register sensor {} in {:?} mode success",
+            app_name, sensor_name, sensor_mode
+        );
+        ok_response(Value::Null)
     }
 
     /// cancel sensor inner
-    fn _cancel_sensor(&self, app_name: &SyncAppName, sensor_name: &SyncSensorName) {
-        todo!()
+    fn _cancel_sensor(&self, app_mgr: &SyncAppMgr, sensor_name: &SyncSensorName) {
+        self.unsubscribe(&get_sensor(sensor_name));
+        if let Some(ctx_server) = app_mgr.get_ctx_server() {
+            ctx_server.detach_sensor(sensor_name);
+        }
+        if let Some(sensor_mgr) = RES_MGR_THREAD.get_sensor_mgrs().get(sensor_name) {
+            sensor_mgr.remove_app(&app_mgr.get_app_name_clone());
+        }
     }
 
     /// cancel sensor
     /// return a string about whether cancel sensor success
     fn cancel_sensor(&self, app_name: &SyncAppName, sensor_name: &SyncSensorName) -> String {
-        todo!()
+        match self.get_app_mgr_by_name(app_name) {
+            Some(app_mgr) if app_mgr.remove_sensor(sensor_name) => {
+                self._cancel_sensor(&app_mgr, sensor_name);
+                info!("app {} cancel sensor {} success", app_name, sensor_name);
+                ok_response(Value::Null)
+            }
+            Some(_) => sensor_not_registered(sensor_name),
+            None => app_not_registered(app_name),
+        }
     }
 
     /// cancel all sensors of an app
     /// return a string about whether cancel all sensors success
     fn cancel_all_sensors(&self, app_name: &SyncAppName) -> String {
-        todo!()
+        match self.get_app_mgr_by_name(app_name) {
+            Some(app_mgr) => {
+                self._cancel_all_sensors(&app_mgr);
+                ok_response(Value::Null)
+            }
+            None => app_not_registered(app_name),
+        }
+    }
+
+    /// cancel all sensors inner
+    fn _cancel_all_sensors(&self, app_mgr: &SyncAppMgr) {
+        for sensor_name in app_mgr.get_sensor_names_vec() {
+            if app_mgr.remove_sensor(&sensor_name) {
+                self._cancel_sensor(app_mgr, &sensor_name);
+            }
+        }
+    }
+
+    /// request sensor data
+    /// publish sensory_request of sensors for app and wait for their data
+    /// sensors whose retained data is fresh are not requested
+    /// return data of sensors that reply in time
+    fn request_sensor_data(
+        &self,
+        app_mgr: &SyncAppMgr,
+        sensor_names: &[SyncSensorName],
+    ) -> Map<String, Value> {
+        let mut sensor_data = Self::get_retained_sensor_data(app_mgr, sensor_names);
+        let sensor_names: Vec<&SyncSensorName> = sensor_names
+            .iter()
+            .filter(|sensor_name| !sensor_data.contains_key(sensor_name.as_str()))
+            .collect();
+        if sensor_names.is_empty() {
+            return sensor_data;
+        }
+        let expected = sensor_data.len() + sensor_names.len();
+
+        self._get_sensor_data.clear();
+        let msg = CmdMessageGrpIds::new(
+            Some("sensory_request".to_string()),
+            None,
+            Some(vec![app_mgr.get_grp_id_clone()]),
+        );
+        let msg: SyncString = Arc::new(serde_json::to_string(&msg).expect("serialize msg fail"));
+        for sensor_name in sensor_names.iter() {
+            self.sensor_data_requests.insert(sensor_name.to_string());
+            publisher::publish(&get_sensor_request(sensor_name), None, None, msg.clone());
+        }
+
+        let deadline = Instant::now() + Duration::from_millis(SENSOR_DATA_TIMEOUT_MS);
+        while sensor_data.len() < expected {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let reply = match self
+                ._get_sensor_data
+                .block_take_timeout(timeout.as_millis() as u64)
+                .and_then(|reply| serde_json::from_str::<Value>(&reply).ok())
+            {
+                Some(reply) => reply,
+                None => break,
+            };
+            if let Some(sensor_name) = reply["sensor_name"].as_str() {
+                sensor_data.insert(sensor_name.to_string(), reply["sensor_data"].clone());
+            }
+        }
+        for sensor_name in sensor_names {
+            self.sensor_data_requests.remove(sensor_name.as_str());
+        }
+        sensor_data
+    }
+
+    /// get retained data of sensors, which is not older than retained max age of pubsub config
+    /// data of an app with ctx server is checked by it, so it is always requested
+    fn get_retained_sensor_data(
+        app_mgr: &SyncAppMgr,
+        sensor_names: &[SyncSensorName],
+    ) -> Map<String, Value> {
+        let max_age = PUBSUB_CONFIG
+            .lock()
+            .expect("get pubsub config fail")
+            .get_retained_max_age_ms();
+        if max_age == 0 || app_mgr.get_ctx_server().is_some() {
+            return Map::new();
+        }
+        sensor_names
+            .iter()
+            .filter_map(|sensor_name| {
+                let retained = get_channel(&get_sensor(sensor_name))
+                    .get_retained(Some(Duration::from_millis(max_age)))?;
+                Some((sensor_name.to_string(), parse_sensor_data(&retained.msg)))
+            })
+            .collect()
     }
 
     /// get sensor data
+    /// retained data of the sensor is returned if it is fresh, see get_retained_sensor_data,
+    /// otherwise request the sensor once and wait for its data
     /// return a string about sensor data
     fn get_sensor_data(&self, app_name: SyncAppName, sensor_name: SyncSensorName) -> String {
-        todo!()
+        let app_mgr = match self.get_app_mgr_by_name(&app_name) {
+            Some(app_mgr) => app_mgr,
+            None => return app_not_registered(&app_name),
+        };
+        if !app_mgr.get_sensors().contains(&sensor_name) {
+            return sensor_not_registered(&sensor_name);
+        }
+        match self
+            .request_sensor_data(&app_mgr, std::slice::from_ref(&sensor_name))
+            .remove(sensor_name.as_str())
+        {
+            Some(sensor_data) => ok_response(json!({"sensor_data" : sensor_data})),
+            None => error_response(
+                ErrorCode::Timeout,
+                format!("get data of sensor {} timeout", sensor_name),
+            ),
+        }
     }
 
     /// get all sensor data
+    /// sensors that do not reply in time are left out
     /// return a string about all sensor data
     fn get_all_sensor_data(&self, app_name: SyncAppName) -> String {
-        todo!()
+        let app_mgr = match self.get_app_mgr_by_name(&app_name) {
+            Some(app_mgr) => app_mgr,
+            None => return app_not_registered(&app_name),
+        };
+        let sensor_data = self.request_sensor_data(&app_mgr, &app_mgr.get_sensor_names_vec());
+        ok_response(json!({"sensor_data" : sensor_data}))
     }
 
     /// get msg thread
     /// open or close get msg thread depend on cmd
+    /// sensor data is pushed to app by udp while get msg thread is on
     /// return a string about get msg thread state
     fn get_msg_thread(&self, app_name: SyncAppName, cmd: CmdType) -> String {
-        todo!()
+        if self.get_app_mgr_by_name(&app_name).is_none() {
+            return app_not_registered(&app_name);
+        }
+        let on = match cmd {
+            CmdType::Start | CmdType::Reset => true,
+            CmdType::Stop => false,
+        };
+        *self
+            .get_msg_thread_state
+            .write()
+            .expect("write get msg thread state fail") = on;
+        info!("app {} get msg thread {}", app_name, cmd);
+        ok_response(Value::Null)
     }
 }
 
 /// actor related
 impl AppDriver {
     /// get supported actors
-    /// return a string about actor info
+    /// return a string about actor info of all actors
     fn get_supported_actors(&self) -> String {
-        todo!()
+        ok_response(json!({"actors" : RES_MGR_THREAD.create_all_actor_info()}))
     }
 
     /// get registered actors
-    /// return a string about actor name of app
+    /// return a string about actor info of actors registered by app
     fn get_registered_actors(&self, app_name: SyncAppName) -> String {
-        todo!()
+        let app_mgr = match self.get_app_mgr_by_name(&app_name) {
+            Some(app_mgr) => app_mgr,
+            None => return app_not_registered(&app_name),
+        };
+        let actors: Map<String, Value> = app_mgr
+            .get_actor_names_vec()
+            .iter()
+            .filter_map(|actor_name| RES_MGR_THREAD.get_actor_mgrs().get(actor_name))
+            .map(|actor_mgr| {
+                (
+                    actor_mgr.key().to_string(),
+                    json!(actor_mgr.create_actor_info()),
+                )
+            })
+            .collect();
+        ok_response(json!({"actors" : actors}))
     }
 
     /// get registered actors status
+    /// status is true if all actors registered by app are alive
     /// return a string about actor status of app
     fn get_registered_actors_status(&self, app_name: SyncAppName) -> String {
-        todo!()
+        let app_mgr = match self.get_app_mgr_by_name(&app_name) {
+            Some(app_mgr) => app_mgr,
+            None => return app_not_registered(&app_name),
+        };
+        let status = app_mgr.get_actor_names_vec().iter().all(|actor_name| {
+            RES_MGR_THREAD
+                .get_actor_mgrs()
+                .get(actor_name)
+                .is_some_and(|actor_mgr| actor_mgr.is_alive())
+        });
+        ok_response(json!({"status" : status}))
     }
 
     /// register actor
+    /// action back of actor reaches app driver in its group
     /// return a string about whether register actor success
     fn register_actor(&self, app_name: SyncAppName, actor_name: SyncActorName) -> String {
-        todo!()
+        let app_mgr = match self.get_app_mgr_by_name(&app_name) {
+            Some(app_mgr) => app_mgr,
+            None => return app_not_registered(&app_name),
+        };
+        if app_mgr.get_actors().contains(&actor_name) {
+            return error_response(
+                ErrorCode::ActorAlreadyRegistered,
+                format!("actor {} is already registered", actor_name),
+            );
+        }
+        let actor_mgr = match RES_MGR_THREAD.get_actor_mgrs().get(&actor_name) {
+            Some(actor_mgr) if actor_mgr.is_alive() => actor_mgr.clone(),
+            Some(_) => {
+                return error_response(
+                    ErrorCode::ActorNotAlive,
+                    format!("actor {} is not alive", actor_name),
+                )
+            }
+            None => return actor_not_found(&actor_name),
+        };
+        app_mgr.add_actor(actor_name.clone());
+        actor_mgr.add_app(&app_mgr);
+        self.subscribe(
+            &get_actor(&actor_name),
+            Some(app_mgr.get_grp_id_clone()),
+            Some(DEFAULT_PRIO_ID),
+        );
+        info!("app {} register actor {} success", app_name, actor_name);
+        ok_response(Value::Null)
+    }
+
+    /// cancel actor inner
+    fn _cancel_actor(&self, app_mgr: &SyncAppMgr, actor_name: &SyncActorName) {
+        self.unsubscribe(&get_actor(actor_name));
+        if let Some(actor_mgr) = RES_MGR_THREAD.get_actor_mgrs().get(actor_name) {
+            actor_mgr.remove_app(&app_mgr.get_app_name_clone());
+        }
     }
 
     /// cancel actor
     /// return a string about whether cancel actor success
     fn cancel_actor(&self, app_name: &SyncAppName, actor_name: &SyncActorName) -> String {
-        todo!()
+        match self.get_app_mgr_by_name(app_name) {
+            Some(app_mgr) if app_mgr.remove_actor(actor_name) => {
+                self._cancel_actor(&app_mgr, actor_name);
+                info!("app {} cancel actor {} success", app_name, actor_name);
+                ok_response(Value::Null)
+            }
+            Some(_) => actor_not_registered(actor_name),
+            None => app_not_registered(app_name),
+        }
     }
 
     /// cancel all actors of an app
     /// return a string about whether cancel all actors success
     fn cancel_all_actors(&self, app_name: &SyncAppName) -> String {
-        todo!()
+        match self.get_app_mgr_by_name(app_name) {
+            Some(app_mgr) => {
+                self._cancel_all_actors(&app_mgr);
+                ok_response(Value::Null)
+            }
+            None => app_not_registered(app_name),
+        }
+    }
+
+    /// cancel all actors inner
+    fn _cancel_all_actors(&self, app_mgr: &SyncAppMgr) {
+        for actor_name in app_mgr.get_actor_names_vec() {
+            if app_mgr.remove_actor(&actor_name) {
+                self._cancel_actor(app_mgr, &actor_name);
+            }
+        }
     }
 
     /// set actor cmd
+    /// publish action_request to actor and wait for its action back
     /// return a string about whether set actor cmd success
     fn set_actor_cmd(
         &self,
@@ -863,125 +1016,457 @@ impl AppDriver {
         actor_name: SyncActorName,
         action: String,
     ) -> String {
-        todo!()
+        let app_mgr = match self.get_app_mgr_by_name(&app_name) {
+            Some(app_mgr) => app_mgr,
+            None => return app_not_registered(&app_name),
+        };
+        if !app_mgr.get_actors().contains(&actor_name) {
+            return actor_not_registered(&actor_name);
+        }
+        self._actor_cmd.clear();
+        let msg = CmdMessageGrpIds::new(
+            Some("action_request".to_string()),
+            Some(json!(action)),
+            Some(vec![app_mgr.get_grp_id_clone()]),
+        );
+        publisher::publish(
+            &get_actor_request(&actor_name),
+            None,
+            None,
+            Arc::new(serde_json::to_string(&msg).expect("serialize msg fail")),
+        );
+        match self._actor_cmd.block_take_timeout(ACTOR_CMD_TIMEOUT_MS) {
+            Some(reply) => {
+                let reply = serde_json::from_str::<Value>(&reply).unwrap_or(Value::Null);
+                // resource driver replies a timeout if wrapper does not reply
+                if let Ok(timeout) = serde_json::from_value::<SensorData>(reply.clone()) {
+                    if timeout.is_timeout() {
+                        return error_response(
+                            ErrorCode::Timeout,
+                            format!(
+                                "actor {} does not reply after {} attempts",
+                                actor_name,
+                                timeout.get_data("attempts").unwrap_or(&Value::Null)
+                            ),
+                        );
+                    }
+                }
+                // wrapper replies "true" if the action is done
+                let done = match reply {
+                    Value::Bool(done) => done,
+                    Value::String(done) => done.eq_ignore_ascii_case("true"),
+                    _ => false,
+                };
+                match done {
+                    true => ok_response(Value::Null),
+                    false => error_response(
+                        ErrorCode::Refused,
+                        format!("actor {} does not do action {}", actor_name, action),
+                    ),
+                }
+            }
+            None => error_response(
+                ErrorCode::Timeout,
+                format!("set cmd of actor {} timeout", actor_name),
+            ),
+        }
     }
 }
 
 /// info related
 impl AppDriver {
-    /// below is for the whole platform, does not depend on app
+    // below is for the whole platform, does not depend on app
 
     /// get Sensor info
     /// return a string about sensor info
     fn get_sensor_info(&self, sensor_name: SyncSensorName) -> String {
-        todo!()
+        match RES_MGR_THREAD.get_sensor_mgrs().get(&sensor_name) {
+            Some(sensor_mgr) => ok_response(json!({"sensor" : sensor_mgr.create_sensor_info()})),
+            None => sensor_not_found(&sensor_name),
+        }
     }
 
     /// get sensor info and data
+    /// data is the latest value sent by wrapper, null if there is none
     /// return a string about sensor info and data
     fn get_sensor_info_and_data(&self, sensor_name: SyncSensorName) -> String {
-        todo!()
+        match RES_MGR_THREAD.get_sensor_mgrs().get(&sensor_name) {
+            Some(sensor_mgr) => ok_response(json!({
+                "sensor" : sensor_mgr.create_sensor_info_and_data(),
+            })),
+            None => sensor_not_found(&sensor_name),
+        }
     }
 
     /// get all sensor info and data
     /// return a string about all sensor info and data
     fn get_all_sensor_info_and_data(&self) -> String {
-        todo!()
+        ok_response(json!({"sensors" : RES_MGR_THREAD.create_all_sensor_info_and_data()}))
     }
 
     /// get actor info
     /// return a string about actor info
     fn get_actor_info(&self, actor_name: SyncActorName) -> String {
-        todo!()
+        match RES_MGR_THREAD.get_actor_mgrs().get(&actor_name) {
+            Some(actor_mgr) => ok_response(json!({"actor" : actor_mgr.create_actor_info()})),
+            None => actor_not_found(&actor_name),
+        }
     }
 
-    //todo: why not get all actor info
-
     /// get app info
+    /// any registered app can be queried
     /// return a string about app info
     fn get_app_info(&self, app_name: SyncAppName) -> String {
-        todo!()
+        match APP_MGR_THREAD.get_app_mgrs().get(&app_name) {
+            Some(app_mgr) => ok_response(json!({"app" : app_mgr.create_app_info()})),
+            None => app_not_registered(&app_name),
+        }
     }
 
     /// get all app info
     /// return a string about all app info
     fn get_all_app_info(&self) -> String {
-        todo!()
+        ok_response(json!({"apps" : APP_MGR_THREAD.create_all_app_info()}))
     }
 
     /// get service info
     /// return a string about service info
     fn get_service_info(&self, app_name: SyncAppName, service: ServiceType) -> String {
-        todo!()
+        let app_mgr = match self.get_app_mgr_by_name(&app_name) {
+            Some(app_mgr) => app_mgr,
+            None => return app_not_registered(&app_name),
+        };
+        match app_mgr.create_service_info(service) {
+            Some(service_info) => ok_response(json!({"service" : service_info})),
+            None => error_response(
+                ErrorCode::ServiceError,
+                format!("no info of service {}", service),
+            ),
+        }
     }
 
     /// get all service info
     /// return a string about all service info
     fn get_all_service_info(&self, app_name: SyncAppName) -> String {
-        todo!()
+        let app_mgr = match self.get_app_mgr_by_name(&app_name) {
+            Some(app_mgr) => app_mgr,
+            None => return app_not_registered(&app_name),
+        };
+        ok_response(json!({"services" : app_mgr.create_all_service_info()}))
     }
 }
 
 /// service related: todo
 impl AppDriver {
+    /// get registered app mgr
+    /// return none if app_name is not the app registered by this driver
+    fn get_app_mgr_by_name(&self, app_name: &SyncAppName) -> Option<SyncAppMgr> {
+        self.app_mgr
+            .read()
+            .expect("read app mgr fail")
+            .as_ref()
+            .filter(|app_mgr| app_mgr.get_app_name_clone().eq_ignore_ascii_case(app_name))
+            .cloned()
+    }
+
     /// is service on
     /// return a string about service state
     fn is_service_on(&self, app_name: SyncAppName, service: ServiceType) -> String {
-        todo!()
+        let app_mgr = match self.get_app_mgr_by_name(&app_name) {
+            Some(app_mgr) => app_mgr,
+            None => return app_not_registered(&app_name),
+        };
+        let on = match service {
+            ServiceType::Ctx => app_mgr.get_ctx_server().is_some(),
+            ServiceType::Inv => app_mgr.is_inv_service_on(),
+            ServiceType::All => app_mgr.get_ctx_server().is_some() && app_mgr.is_inv_service_on(),
+        };
+        ok_response(json!({"on" : on}))
+    }
+
+    /// start service
+    /// config is the service config sent by app, null means default config
+    /// config of all services is {"ctx": ctx config, "inv": inv config}
+    /// return a string about start service state
+    fn start_service(
+        &self,
+        app_name: SyncAppName,
+        service: ServiceType,
+        config: &Value,
+    ) -> Result<String, AppDriverError> {
+        if self.get_app_mgr_by_name(&app_name).is_none() {
+            return Ok(app_not_registered(&app_name));
+        }
+        let state = match service {
+            ServiceType::Ctx => {
+                self.start_ctx_service(app_name.clone(), parse_service_config(config)?)
+            }
+            ServiceType::Inv => {
+                self.start_inv_service(app_name.clone(), parse_service_config(config)?)
+            }
+            ServiceType::All => {
+                let ctx_service_config = parse_service_config(&config["ctx"])?;
+                let inv_service_config = parse_service_config(&config["inv"])?;
+                self.start_ctx_service(app_name.clone(), ctx_service_config)
+                    & self.start_inv_service(app_name.clone(), inv_service_config)
+            }
+        };
+        Ok(match state {
+            true => ok_response(Value::Null),
+            false => error_response(
+                ErrorCode::ServiceError,
+                format!("start {} service of app {} fail", service, app_name),
+            ),
+        })
+    }
+
+    /// stop service
+    /// return a string about stop service state
+    fn stop_service(&self, app_name: SyncAppName, service: ServiceType) -> String {
+        if self.get_app_mgr_by_name(&app_name).is_none() {
+            return app_not_registered(&app_name);
+        }
+        let state = match service {
+            ServiceType::Ctx => self.stop_ctx_service(&app_name),
+            ServiceType::Inv => self.stop_inv_service(&app_name),
+            ServiceType::All => self.stop_ctx_service(&app_name) | self.stop_inv_service(&app_name),
+        };
+        match state {
+            true => ok_response(Value::Null),
+            false => error_response(
+                ErrorCode::ServiceError,
+                format!("{} service of app {} is off", service, app_name),
+            ),
+        }
+    }
+
+    /// start ctx service
+    /// ctx server checks sensors registered now and sensors registered later
+    /// return whether ctx service is started
+    fn start_ctx_service(
+        &self,
+        app_name: SyncAppName,
+        ctx_service_config: CtxServiceConfig,
+    ) -> bool {
+        let app_mgr = match self.get_app_mgr_by_name(&app_name) {
+            Some(app_mgr) if app_mgr.get_ctx_server().is_none() => app_mgr,
+            _ => return false,
+        };
+        let service = match ctx::new_ctx_service(&ctx_service_config) {
+            Ok(service) => service,
+            Err(e) => {
+                error!("app {} start ctx service fail: {}", app_name, e);
+                return false;
+            }
+        };
+        let ctx_server = AppCtxServer::add_to_subscriber_objs(
+            app_name.clone(),
+            app_mgr.get_grp_id_clone(),
+            service,
+        );
+        for sensor_name in app_mgr.get_sensor_names_vec() {
+            ctx_server.attach_sensor(&sensor_name);
+        }
+        app_mgr.set_ctx_service_config(ctx_service_config);
+        app_mgr.set_ctx_server(ctx_server);
+        info!("app {} start ctx service success", app_name);
+        true
+    }
+
+    /// stop ctx service
+    /// return whether ctx service was on
+    fn stop_ctx_service(&self, app_name: &SyncAppName) -> bool {
+        match self
+            .get_app_mgr_by_name(app_name)
+            .and_then(|app_mgr| app_mgr.take_ctx_server())
+        {
+            Some(ctx_server) => {
+                ctx_server.close();
+                info!("app {} stop ctx service success", app_name);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// start inv service
+    /// return whether inv service is started
+    fn start_inv_service(
+        &self,
+        app_name: SyncAppName,
+        inv_service_config: InvServiceConfig,
+    ) -> bool {
+        let app_mgr = match self.get_app_mgr_by_name(&app_name) {
+            Some(app_mgr) if !app_mgr.is_inv_service_on() => app_mgr,
+            _ => return false,
+        };
+        match inv::new_inv_service(app_name.to_string(), inv_service_config) {
+            Ok(service) => {
+                app_mgr.set_inv_service(service);
+                info!("app {} start inv service success", app_name);
+                true
+            }
+            Err(e) => {
+                error!("app {} start inv service fail: {}", app_name, e);
+                false
+            }
+        }
+    }
+
+    /// stop inv service
+    /// invariants not saved by app are dropped
+    /// return whether inv service was on
+    fn stop_inv_service(&self, app_name: &SyncAppName) -> bool {
+        match self
+            .get_app_mgr_by_name(app_name)
+            .and_then(|app_mgr| app_mgr.take_inv_service())
+        {
+            Some(_) => {
+                info!("app {} stop inv service success", app_name);
+                true
+            }
+            None => false,
+        }
     }
 
     /// service call
+    /// start or stop service, reset restarts service with config
     /// return a string about service call state
-    fn service_call<T: ServiceConfig>(
+    fn service_call(
         &self,
         app_name: SyncAppName,
         service: ServiceType,
         cmd: CmdType,
-        config: T,
+        config: &Value,
+    ) -> Result<String, AppDriverError> {
+        match cmd {
+            CmdType::Start => self.start_service(app_name, service, config),
+            CmdType::Stop => Ok(self.stop_service(app_name, service)),
+            CmdType::Reset => {
+                self.stop_service(app_name.clone(), service);
+                self.start_service(app_name, service, config)
+            }
+        }
+    }
+
+    /// call f with inv service of app
+    /// return a string about app not registered or inv service off if f is not called
+    fn inv_call(
+        &self,
+        app_name: &SyncAppName,
+        f: impl FnOnce(&mut inv::InvService) -> String,
     ) -> String {
-        todo!()
+        match self.get_app_mgr_by_name(app_name) {
+            Some(app_mgr) => app_mgr
+                .with_inv_service(f)
+                .unwrap_or_else(|| inv_service_off(app_name)),
+            None => app_not_registered(app_name),
+        }
     }
 
     /// monitor
     /// return a string about monitor state
-    fn monitor(&self, app_name: SyncAppName, objs: Value) -> String {
-        todo!()
+    fn monitor(&self, app_name: SyncAppName, objs: Vec<String>) -> String {
+        self.inv_call(&app_name, |service| match service.monitor(&objs) {
+            true => ok_response(Value::Null),
+            false => error_response(
+                ErrorCode::InvalidParam,
+                format!("some name of {:?} is empty or contains whitespace", objs),
+            ),
+        })
     }
 
     /// is monitored
-    /// return a string about monitor state
-    fn is_monitored(&self, app_name: SyncAppName, objs: Value) -> String {
-        todo!()
+    /// return a string about monitor state of every obj
+    fn is_monitored(&self, app_name: SyncAppName, objs: Vec<String>) -> String {
+        self.inv_call(&app_name, |service| {
+            ok_response(json!({"objs" : service.is_monitored(&objs)}))
+        })
     }
 
     /// check
-    /// return a string about check state
-    fn check(&self, app_name: SyncAppName, objs: Value) -> String {
-        todo!()
+    /// objs maps variable name to its value at line_number
+    /// return a string about check result of every monitored variable
+    fn check(&self, app_name: SyncAppName, line_number: i32, objs: Map<String, Value>) -> String {
+        self.inv_call(&app_name, |service| {
+            match service.check(line_number, &objs) {
+                Some(result) => {
+                    if result.is_violated() {
+                        info!("app {} violates invariants: {:?}", app_name, result);
+                    }
+                    ok_response(json!({"result" : result.to_json_object()}))
+                }
+                None => error_response(
+                    ErrorCode::InvalidParam,
+                    format!("no obj at line {} is monitored", line_number),
+                ),
+            }
+        })
     }
 
     /// save
     /// return a string about save content
     fn save(&self, app_name: SyncAppName) -> String {
-        todo!()
+        self.inv_call(&app_name, |service| {
+            ok_response(json!({"content" : service.save()}))
+        })
     }
 
     /// load
     /// return a string about load state
     fn load(&self, app_name: SyncAppName, content: String) -> String {
-        todo!()
+        self.inv_call(&app_name, |service| match service.load(&content) {
+            Ok(count) => {
+                info!("app {} load invariants of {} locations", app_name, count);
+                ok_response(Value::Null)
+            }
+            Err(e) => {
+                error!("app {} load invariants fail: {}", app_name, e);
+                error_response(
+                    ErrorCode::ServiceError,
+                    format!("load invariants fail: {}", e),
+                )
+            }
+        })
     }
 
     /// check generated
-    /// return a string about check generated state
+    /// generated is true if invariants of every check location are generated
+    /// return a string about generated state
     fn check_generated(&self, app_name: SyncAppName) -> String {
-        todo!()
+        self.inv_call(&app_name, |service| {
+            ok_response(json!({"generated" : service.is_generated()}))
+        })
     }
 }
 
-/// database related: todo
+/// database related
 impl AppDriver {
+    /// call f with database of app
+    /// return a string with data returned by f if it succeeds,
+    /// else a string with database_error
+    fn database_call(
+        &self,
+        app_name: SyncAppName,
+        f: impl FnOnce(&mut Database) -> Result<Value, DbError>,
+    ) -> String {
+        let app_mgr = match self.get_app_mgr_by_name(&app_name) {
+            Some(app_mgr) => app_mgr,
+            None => return app_not_registered(&app_name),
+        };
+        let database = app_mgr.get_database();
+        let result = f(&mut database.write().expect("write database fail"));
+        match result {
+            Ok(data) => ok_response(data),
+            Err(e) => {
+                debug!("app {} database call fail: {}", app_name, e);
+                error_response(ErrorCode::DatabaseError, e)
+            }
+        }
+    }
+
     /// database create
+    /// row_limit 0 means no limit
     /// return a string about database create state
     fn database_create(
         &self,
@@ -989,30 +1474,189 @@ impl AppDriver {
         table_name: String,
         primary_key: String,
         header: Vec<String>,
-        row_limit: i32,
+        row_limit: usize,
     ) -> String {
-        todo!()
+        self.database_call(app_name, |db| {
+            db.create_table(table_name, primary_key, header, row_limit)
+                .map(|_| Value::Null)
+        })
     }
 
     /// database drop
     /// return a string about database drop state
     fn database_drop(&self, app_name: SyncAppName, table_name: String) -> String {
-        todo!()
+        self.database_call(app_name, |db| {
+            db.drop_table(&table_name).map(|_| Value::Null)
+        })
     }
 
     /// database insert
-    /// return a string about database insert state\
-    fn database_insert() -> String {
-        todo!()
+    /// return a string about database insert state
+    fn database_insert(&self, app_name: SyncAppName, table_name: String, row: Row) -> String {
+        self.database_call(app_name, |db| {
+            db.insert(&table_name, row).map(|_| Value::Null)
+        })
+    }
+
+    /// database inserts
+    /// return a string about the number of inserted rows
+    fn database_inserts(
+        &self,
+        app_name: SyncAppName,
+        table_name: String,
+        rows: Vec<Row>,
+    ) -> String {
+        self.database_call(app_name, |db| {
+            let count = db.inserts(&table_name, rows)?;
+            Ok(json!({"count" : count}))
+        })
+    }
+
+    /// database update
+    /// return a string about the number of updated rows
+    fn database_update(
+        &self,
+        app_name: SyncAppName,
+        table_name: String,
+        values: Row,
+        conditions: Vec<Condition>,
+    ) -> String {
+        self.database_call(app_name, |db| {
+            let count = db.update(&table_name, values, conditions)?;
+            Ok(json!({"count" : count}))
+        })
+    }
+
+    /// database delete
+    /// return a string about the number of deleted rows
+    fn database_delete(&self, app_name: SyncAppName, table_name: String, key: &Value) -> String {
+        self.database_call(app_name, |db| {
+            let count = db.delete(&table_name, key)? as usize;
+            Ok(json!({"count" : count}))
+        })
+    }
+
+    /// database deletes
+    /// return a string about the number of deleted rows
+    fn database_deletes(
+        &self,
+        app_name: SyncAppName,
+        table_name: String,
+        conditions: Vec<Condition>,
+    ) -> String {
+        self.database_call(app_name, |db| {
+            let count = db.deletes(&table_name, conditions)?;
+            Ok(json!({"count" : count}))
+        })
+    }
+
+    /// database row
+    /// return a string about the row of primary key value, null if it does not exist
+    fn database_row(&self, app_name: SyncAppName, table_name: String, key: &Value) -> String {
+        self.database_call(app_name, |db| {
+            let row = db.get_table(&table_name)?.row(key).cloned();
+            Ok(json!({"row" : row}))
+        })
+    }
+
+    /// database rows
+    /// return a string about rows satisfying conditions
+    fn database_rows(
+        &self,
+        app_name: SyncAppName,
+        table_name: String,
+        conditions: Vec<Condition>,
+        order: Option<Order>,
+        limit: Option<usize>,
+    ) -> String {
+        self.database_call(app_name, |db| {
+            let rows = db
+                .get_table(&table_name)?
+                .rows(&conditions, order.as_ref(), limit)?;
+            Ok(json!({"rows" : rows}))
+        })
+    }
+
+    /// database column
+    /// return a string about values of column
+    fn database_column(
+        &self,
+        app_name: SyncAppName,
+        table_name: String,
+        column: &str,
+        conditions: Vec<Condition>,
+    ) -> String {
+        self.database_call(app_name, |db| {
+            let values = db.get_table(&table_name)?.column(column, &conditions)?;
+            Ok(json!({"column" : values}))
+        })
+    }
+
+    /// database columns
+    /// return a string about rows with only given columns
+    fn database_columns(
+        &self,
+        app_name: SyncAppName,
+        table_name: String,
+        columns: Vec<String>,
+        conditions: Vec<Condition>,
+    ) -> String {
+        self.database_call(app_name, |db| {
+            let rows = db.get_table(&table_name)?.columns(&columns, &conditions)?;
+            Ok(json!({"columns" : rows}))
+        })
+    }
+
+    /// database cell
+    /// return a string about the cell, null if row does not exist
+    fn database_cell(
+        &self,
+        app_name: SyncAppName,
+        table_name: String,
+        key: &Value,
+        column: &str,
+    ) -> String {
+        self.database_call(app_name, |db| {
+            let cell = db.get_table(&table_name)?.cell(key, column)?;
+            Ok(json!({"cell" : cell}))
+        })
     }
 
-    //todo: add other database operation
+    /// database header
+    /// return a string about header, primary key and row limit
+    fn database_header(&self, app_name: SyncAppName, table_name: String) -> String {
+        self.database_call(app_name, |db| {
+            let table = db.get_table(&table_name)?;
+            Ok(json!({
+                "header" : table.get_header(),
+                "primary_key" : table.get_primary_key(),
+                "row_limit" : table.get_row_limit(),
+            }))
+        })
+    }
+
+    /// database count
+    /// return a string about the number of rows satisfying conditions
+    fn database_count(
+        &self,
+        app_name: SyncAppName,
+        table_name: String,
+        conditions: Vec<Condition>,
+    ) -> String {
+        self.database_call(app_name, |db| {
+            let count = db.get_table(&table_name)?.count(&conditions)?;
+            Ok(json!({"count" : count}))
+        })
+    }
 }
 
 /// display trait
 impl Display for AppDriver {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!()
+        match self.app_mgr.read().expect("read app mgr fail").as_ref() {
+            Some(app_mgr) => write!(f, "AppDriver({})", app_mgr.get_app_name_clone()),
+            None => write!(f, "AppDriver({})", self.id()),
+        }
     }
 }
 
@@ -1022,7 +1666,69 @@ impl Subscriber for AppDriver {
         &self.abstract_subscriber
     }
 
+    /// actor channel: put action to actor cmd queue
+    /// sensor channel: put to sensor data queue if it is requested by get sensor data,
+    /// push to app by udp if get msg thread is on
     fn on_message(&self, channel: SyncString, msg: SyncString) {
-        todo!()
+        if channel.ends_with(ACTOR_SUFFIX) {
+            self._actor_cmd.put(msg.to_string());
+            return;
+        }
+
+        let sensor_name = channel.strip_suffix(SENSOR_SUFFIX).unwrap_or(&channel);
+        let sensor_data = parse_sensor_data(&msg);
+        let sensor_msg = json!({"sensor_name": sensor_name, "sensor_data": sensor_data});
+
+        if self.sensor_data_requests.remove(sensor_name).is_some() {
+            self._get_sensor_data.put(sensor_msg.to_string());
+        }
+        if *self
+            .get_msg_thread_state
+            .read()
+            .expect("read get msg thread state fail")
+        {
+            let client_ip = self.get_client_ip().clone();
+            let client_udp_port = *self
+                .client_udp_port
+                .read()
+                .expect("read client udp port fail");
+            if let (Some(client_ip), Some(client_udp_port)) = (client_ip, client_udp_port) {
+                udp::send(&client_ip, client_udp_port, &sensor_msg.to_string());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_run_with_malformed_line() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let driver = AppDriver::add_to_subscriber_objs(listener.accept().unwrap().0);
+        let run = thread::spawn(move || AppDriver::run(driver));
+
+        let mut reader = BufReader::new(client.try_clone().unwrap());
+        for line in ["not json\n", "{\"api\": \n"] {
+            client.write_all(line.as_bytes()).unwrap();
+            let mut reply = String::new();
+            reader.read_line(&mut reply).unwrap();
+            println!("{}", reply.trim_end());
+            let (code, _) = ApiResponse::parse(reply.trim_end())
+                .into_result()
+                .unwrap_err();
+            assert_eq!(code, ErrorCode::InvalidParam);
+        }
+
+        // driver is still running until app closes connection
+        assert!(!run.is_finished());
+        client.shutdown(std::net::Shutdown::Both).unwrap();
+        run.join().expect("app driver panics");
     }
 }
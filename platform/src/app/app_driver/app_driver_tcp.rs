@@ -30,10 +30,17 @@ impl AppDriverTCP {
             .write()
             .expect("write app driver weak fail")
             .replace(Arc::downgrade(app_driver));
-        trace!(
-            "app tcp connection {}: set app driver weak success",
-            self.abstract_tcp.get_socket().peer_addr().unwrap()
-        );
+        trace!("app tcp connection: set app driver weak success");
+    }
+
+    /// get app driver
+    /// return none if app driver is dropped or not set
+    fn get_app_driver(&self) -> Option<SyncAppDriver> {
+        self.app_driver_weak
+            .read()
+            .expect("read app driver weak fail")
+            .as_ref()
+            .and_then(|app_driver| app_driver.upgrade())
     }
 }
 
@@ -43,76 +50,40 @@ impl TCP for AppDriverTCP {
     }
 
     fn close(&self) {
-        trace!(
-            "app tcp connection {}: close",
-            self.super_reference().get_socket().peer_addr().unwrap()
-        );
-        self.super_reference()
+        if let Err(e) = self
+            .super_reference()
             .get_socket()
             .shutdown(std::net::Shutdown::Both)
-            .expect("shutdown socket fail");
-        trace!("close success");
-        //disconnect platform
-        self.app_driver_weak
-            .read()
-            .expect("read app driver weak fail")
-            .as_ref()
-            .expect("app driver weak is none")
-            .upgrade()
-            .expect("upgrade app driver weak fail")
-            .disconnect_platform();
+        {
+            trace!("app tcp connection: shutdown fail: {}", e);
+        }
+        trace!("app tcp connection: close success");
+        //release app
+        if let Some(app_driver) = self.get_app_driver() {
+            app_driver.release_app();
+        }
     }
 
     fn callback(&self) {
-        let mut name = String::from("AppDrive");
-        if self
-            .app_driver_weak
-            .read()
-            .expect("read app driver weak fail")
-            .as_ref()
-            .expect("app driver weak is none")
-            .upgrade()
-            .expect("upgrade app driver weak fail")
+        let app_driver = match self.get_app_driver() {
+            Some(app_driver) => app_driver,
+            None => return,
+        };
+        let name = app_driver
             .app_mgr
             .read()
             .expect("read app mgr fail")
-            .is_some()
-        {
-            name = self
-                .app_driver_weak
-                .read()
-                .expect("read app driver weak fail")
-                .as_ref()
-                .expect("app driver weak is none")
-                .upgrade()
-                .expect("upgrade app driver weak fail")
-                .app_mgr
-                .read()
-                .expect("read app mgr fail")
-                .as_ref()
-                .expect("app mgr is none")
-                .get_app_name_clone()
-                .as_str()
-                .to_string();
-        }
+            .as_ref()
+            .map(|app_mgr| app_mgr.get_app_name_clone().to_string())
+            .unwrap_or_else(|| String::from("AppDriver"));
         error!(
             "[{}]: TCP connection is broken. Start releasing app resources...",
             name
         );
-        if self
-            .app_driver_weak
-            .read()
-            .expect("read app driver weak fail")
-            .as_ref()
-            .expect("app driver weak is none")
-            .upgrade()
-            .expect("upgrade app driver weak fail")
-            .disconnect_platform()
-            .eq("{\"state\":true}")
-        {
+        if app_driver.release_app() {
             info!("[{}]: Release app resources success", name);
         } else {
-            error!("[{}]: Release app resources fail", name);
+            info!("[{}]: No app resources to release", name);
         }
     }
 }
@@ -1,15 +1,24 @@
 use std::fmt::Display;
-use std::sync::{Arc, RwLock, Weak};
+use std::sync::{Arc, Mutex, RwLock, Weak};
 
 use dashmap::{DashMap, DashSet};
+use serde_json::{json, Map, Value};
 
 use common::socket::cmd_message_grp_ids::GroupId;
+use common::structs::app_info::AppInfo;
 use common::structs::ctx_service_config::CtxServiceConfig;
+use common::structs::enumeration::service_type::ServiceType;
+use common::structs::service_config::ServiceConfig;
+use common::structs::state::State;
 use common::structs::sync::synchronous_string::SynchronousString;
 
 use crate::app::app_driver::{RwLockGroupID, RwLockOptionWeakAppDriver, SyncAppDriver};
+use crate::database::database;
+use crate::database::database::SyncDatabase;
 use crate::resource::actor_mgr::SyncActorNameSet;
 use crate::resource::sensor_mgr::SyncSensorNameSet;
+use crate::service::ctx::ctx_server::SyncAppCtxServer;
+use crate::service::inv::InvService;
 
 pub type SyncAppMgr = Arc<AppMgr>;
 pub type RwLockOptionSyncAppMgr = RwLock<Option<SyncAppMgr>>;
@@ -26,7 +35,9 @@ pub struct AppMgr {
     //use app driver for udp port. This is the reason why
     app_driver: RwLockOptionWeakAppDriver,
     request_map: ChannelRequestSet,
-    //todo:add database, ctx service and inv daikon related fields
+    ctx_service_config: RwLock<Option<CtxServiceConfig>>,
+    ctx_server: RwLock<Option<SyncAppCtxServer>>,
+    inv_service: Mutex<Option<InvService>>,
 }
 
 impl AppMgr {
@@ -39,6 +50,9 @@ impl AppMgr {
         let actors = DashSet::new();
         let app_driver = RwLock::new(None);
         let request_map = DashMap::new();
+        let ctx_service_config = RwLock::new(None);
+        let ctx_server = RwLock::new(None);
+        let inv_service = Mutex::new(None);
         Self {
             app_name,
             grp_id,
@@ -46,6 +60,9 @@ impl AppMgr {
             actors,
             app_driver,
             request_map,
+            ctx_service_config,
+            ctx_server,
+            inv_service,
         }
     }
 
@@ -59,7 +76,7 @@ impl AppMgr {
     /// get grp id clone
     /// return a clone of grp id
     pub fn get_grp_id_clone(&self) -> GroupId {
-        todo!("get grp id clone")
+        *self.grp_id.read().expect("get grp id fail")
     }
 
     /// set grp id
@@ -82,21 +99,24 @@ impl AppMgr {
     /// get sensor names vec
     /// return a vec of sensor names
     pub fn get_sensor_names_vec(&self) -> Vec<SyncAppName> {
-        todo!("get sensor names vec")
+        self.sensors
+            .iter()
+            .map(|sensor| sensor.key().clone())
+            .collect()
     }
 
     /// add sensor
     /// if contain sensor, return false
     /// else add sensor and return true
     pub fn add_sensor(&self, sensor_name: SyncAppName) -> bool {
-        todo!("add sensor")
+        self.sensors.insert(sensor_name)
     }
 
     /// remove sensor
     /// if contain sensor, remove sensor and return true
     /// else return false
     pub fn remove_sensor(&self, sensor_name: &SyncAppName) -> bool {
-        todo!("remove sensor")
+        self.sensors.remove(sensor_name).is_some()
     }
 
     /// get actors
@@ -108,21 +128,24 @@ impl AppMgr {
     /// get actor names vec
     /// return a vec of actor names
     pub fn get_actor_names_vec(&self) -> Vec<SyncAppName> {
-        todo!("get actor names vec")
+        self.actors
+            .iter()
+            .map(|actor| actor.key().clone())
+            .collect()
     }
 
     /// add actor
     /// if contain actor, return false
     /// else add actor and return true
     pub fn add_actor(&self, actor_name: SyncAppName) -> bool {
-        todo!("add actor")
+        self.actors.insert(actor_name)
     }
 
     /// remove actor
     /// if contain actor, remove actor and return true
     /// else return false
     pub fn remove_actor(&self, actor_name: &SyncAppName) -> bool {
-        todo!("remove actor")
+        self.actors.remove(actor_name).is_some()
     }
 
     /// set app driver
@@ -139,25 +162,149 @@ impl AppMgr {
         todo!("get app driver")
     }
 
+    /// generate app information
+    pub fn create_app_info(&self) -> AppInfo {
+        AppInfo::new(
+            Some(self.app_name.clone()),
+            Some(self.get_ctx_server().is_some()),
+            Some(self.is_inv_service_on()),
+            State::On,
+            self.get_sensor_names_vec()
+                .iter()
+                .map(|sensor| sensor.to_string())
+                .collect(),
+            self.get_actor_names_vec()
+                .iter()
+                .map(|actor| actor.to_string())
+                .collect(),
+        )
+    }
+
+    /// generate service information
+    /// return none for ServiceType::All
+    pub fn create_service_info(&self, service: ServiceType) -> Option<Value> {
+        let (on, config) = match service {
+            ServiceType::Ctx => (
+                self.get_ctx_server().is_some(),
+                self.get_ctx_service_config_clone()
+                    .map(|config| config.to_json_object()),
+            ),
+            ServiceType::Inv => (
+                self.is_inv_service_on(),
+                self.with_inv_service(|service| service.get_config().to_json_object()),
+            ),
+            ServiceType::All => return None,
+        };
+        Some(json!({
+            "service_type" : service,
+            "app_name" : self.app_name,
+            "state" : if on { State::On } else { State::Off },
+            "config" : config,
+        }))
+    }
+
+    /// generate information of all services, keyed by service type
+    pub fn create_all_service_info(&self) -> Map<String, Value> {
+        [ServiceType::Ctx, ServiceType::Inv]
+            .into_iter()
+            .filter_map(|service| {
+                self.create_service_info(service)
+                    .map(|info| (service.to_string(), info))
+            })
+            .collect()
+    }
+
     //below is ctx service related
 
     /// set ctx service config
     /// an init function for ctx service of app mgr
     pub fn set_ctx_service_config(&self, ctx_service_config: CtxServiceConfig) {
-        todo!()
+        *self
+            .ctx_service_config
+            .write()
+            .expect("set ctx service config fail") = Some(ctx_service_config);
     }
 
-    //todo: add other ctx service related function
+    /// get ctx service config clone
+    /// return none if ctx service has never been started
+    pub fn get_ctx_service_config_clone(&self) -> Option<CtxServiceConfig> {
+        self.ctx_service_config
+            .read()
+            .expect("get ctx service config fail")
+            .clone()
+    }
+
+    /// set ctx server
+    /// ctx service is on while ctx server is set
+    pub fn set_ctx_server(&self, ctx_server: SyncAppCtxServer) {
+        *self.ctx_server.write().expect("set ctx server fail") = Some(ctx_server);
+    }
 
-    //below is inv daikon related
-    //todo: add inv daikon related function
+    /// get ctx server
+    /// return none if ctx service is off
+    pub fn get_ctx_server(&self) -> Option<SyncAppCtxServer> {
+        self.ctx_server.read().expect("get ctx server fail").clone()
+    }
+
+    /// take ctx server
+    /// turn ctx service off and return the ctx server
+    pub fn take_ctx_server(&self) -> Option<SyncAppCtxServer> {
+        self.ctx_server
+            .write()
+            .expect("take ctx server fail")
+            .take()
+    }
+
+    //below is inv service related
+
+    /// set inv service
+    /// inv service is on while it is set
+    pub fn set_inv_service(&self, inv_service: InvService) {
+        *self.inv_service.lock().expect("set inv service fail") = Some(inv_service);
+    }
+
+    /// is inv service on
+    pub fn is_inv_service_on(&self) -> bool {
+        self.inv_service
+            .lock()
+            .expect("get inv service fail")
+            .is_some()
+    }
+
+    /// take inv service
+    /// turn inv service off and return it
+    pub fn take_inv_service(&self) -> Option<InvService> {
+        self.inv_service
+            .lock()
+            .expect("take inv service fail")
+            .take()
+    }
+
+    /// with inv service
+    /// call f with inv service, return none if inv service is off
+    pub fn with_inv_service<R>(&self, f: impl FnOnce(&mut InvService) -> R) -> Option<R> {
+        self.inv_service
+            .lock()
+            .expect("get inv service fail")
+            .as_mut()
+            .map(f)
+    }
 
     //below is database related
-    //todo: get database and remove database
+
+    /// get database
+    /// tables of app are kept after app mgr is dropped
+    pub fn get_database(&self) -> SyncDatabase {
+        database::get_database(&self.app_name)
+    }
 }
 
 impl Display for AppMgr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        todo!("app mgr display")
+        write!(
+            f,
+            "{}",
+            serde_json::to_string(&self.create_app_info()).expect("app info to string fail")
+        )
     }
 }
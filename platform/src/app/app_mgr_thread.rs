@@ -2,18 +2,22 @@
 
 use std::fmt::format;
 use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
 
 use dashmap::{DashMap, DashSet};
-use log::{debug, trace};
+use log::{debug, info, trace};
 use once_cell::sync::Lazy;
+use serde_json::{json, Map, Value};
 
 use common::socket::cmd_message_grp_ids::GroupId;
 
 use crate::app::app_driver::AppDriver;
 use crate::app::app_mgr::{AppMgr, SyncAppMgr, SyncAppName};
-use crate::config;
+use crate::pubsub::abstract_subscriber;
+use crate::pubsub::subscriber::Subscriber;
+use crate::{config, platform_ctrl};
 
 pub type IpString = String;
 pub type SyncIpString = Arc<IpString>;
@@ -21,6 +25,7 @@ pub type AppPort = u16;
 
 pub struct AppMgrThread {
     listener: TcpListener,
+    running: AtomicBool,
 
     //store and manage all app_mgrs
     port_map: DashMap<IpString, DashSet<AppPort>>,
@@ -56,6 +61,7 @@ impl AppMgrThread {
                     .get_app_listen_port()
             ))
             .expect("bind app tcp port fail"),
+            running: AtomicBool::new(true),
             port_map: DashMap::new(),
             app_grp_id_map: DashMap::new(),
             grp_id_app_map: DashMap::new(),
@@ -67,13 +73,23 @@ impl AppMgrThread {
         //create an app driver and add it to subscriber objs
         let app_mgr = AppDriver::add_to_subscriber_objs(stream);
         //run app driver
+        let id = app_mgr.id();
         AppDriver::run(app_mgr);
+        //app is released when run returns, app driver leaves subscriber objs
+        abstract_subscriber::deregister(id);
     }
 
     pub fn run(&self) {
-        //todo: add platform control
+        info!(
+            "app mgr thread listen on {}",
+            self.listener.local_addr().expect("get local addr fail")
+        );
+        platform_ctrl::incr_mgr_start_flag();
 
         for stream in self.listener.incoming() {
+            if !self.running.load(Ordering::SeqCst) {
+                break;
+            }
             let stream = stream.expect("new app connection fail");
             trace!(
                 "New app connection: {}",
@@ -86,6 +102,19 @@ impl AppMgrThread {
         }
     }
 
+    /// stop
+    /// stop accepting new app connections and let run return
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+        // wake up the blocking accept in run
+        let port = self
+            .listener
+            .local_addr()
+            .expect("get local addr fail")
+            .port();
+        let _ = TcpStream::connect(("127.0.0.1", port));
+    }
+
     /// get new app port
     /// generate a new app port for udp
     /// store socket port and udp port in port_map
@@ -114,18 +143,14 @@ impl AppMgrThread {
     }
 
     /// remove app port
-    /// remove socket port and udp port in port_map
-    pub fn remove_app_port(&self, stream: &TcpStream, udp_port: AppPort) {
-        let client_host = stream
-            .peer_addr()
-            .expect("get peer addr fail")
-            .ip()
-            .to_string();
-        let client_port = stream.peer_addr().expect("get peer addr fail").port();
-        let port_set = self.port_map.get(&client_host).expect("get port set fail");
-        //todo: should we remove port_set if it is empty?
-        port_set.remove(&client_port);
-        port_set.remove(&udp_port);
+    /// remove udp port of client host in port_map
+    /// port set of client host is removed when it is empty
+    pub fn remove_app_port(&self, client_host: &str, udp_port: AppPort) {
+        if let Some(port_set) = self.port_map.get(client_host) {
+            port_set.remove(&udp_port);
+        }
+        self.port_map
+            .remove_if(client_host, |_, port_set| port_set.is_empty());
     }
 
     /// get new grp id
@@ -177,6 +202,14 @@ impl AppMgrThread {
     pub fn get_app_mgrs(&self) -> &DashMap<SyncAppName, SyncAppMgr> {
         &self.app_mgrs
     }
+
+    /// generate information of all registered apps, keyed by app name
+    pub fn create_all_app_info(&self) -> Map<String, Value> {
+        self.app_mgrs
+            .iter()
+            .map(|app_mgr| (app_mgr.key().to_string(), json!(app_mgr.create_app_info())))
+            .collect()
+    }
 }
 
 //todo: create instance of app_mgr_thread
@@ -197,4 +230,27 @@ mod tests {
         assert_eq!(app_mgr_thread.get_app_name(grp_id), None);
         assert_eq!(app_mgr_thread.get_grp_id(&app_name), None);
     }
+
+    #[test]
+    fn test_remove_app_port() {
+        let app_mgr_thread = AppMgrThread {
+            listener: TcpListener::bind("127.0.0.1:0").unwrap(),
+            running: AtomicBool::new(true),
+            port_map: DashMap::new(),
+            app_grp_id_map: DashMap::new(),
+            grp_id_app_map: DashMap::new(),
+            app_mgrs: DashMap::new(),
+        };
+        let client = TcpStream::connect(app_mgr_thread.listener.local_addr().unwrap()).unwrap();
+        let udp_port = app_mgr_thread.get_new_app_port(&client);
+        let client_host = client.peer_addr().unwrap().ip().to_string();
+        println!("{}: {}", client_host, udp_port);
+        assert!(app_mgr_thread
+            .port_map
+            .get(&client_host)
+            .unwrap()
+            .contains(&udp_port));
+        app_mgr_thread.remove_app_port(&client_host, udp_port);
+        assert!(!app_mgr_thread.port_map.contains_key(&client_host));
+    }
 }
@@ -1,4 +1,8 @@
 pub mod configuration;
 pub mod ctx_server_config;
+pub mod database_config;
+pub mod inv_server_config;
+pub mod pubsub_config;
 pub mod tcp_config;
 pub mod udp_config;
+pub mod web_server_config;
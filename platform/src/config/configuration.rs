@@ -7,7 +7,11 @@ use once_cell::sync::Lazy;
 use serde_json::{json, Value};
 
 use crate::config::ctx_server_config::CtxServerConfig;
+use crate::config::database_config::DatabaseConfig;
+use crate::config::inv_server_config::InvServerConfig;
+use crate::config::pubsub_config::PubsubConfig;
 use crate::config::tcp_config::TcpConfig;
+use crate::config::web_server_config::WebServerConfig;
 
 /// analyze config file and init config
 /// config file format: JSON(should not have comma at the end of the line)
@@ -22,6 +26,12 @@ pub static CTX_SERVER_CONFIG: Lazy<Mutex<CtxServerConfig>> = Lazy::new(|| {
     })))
 });
 
+pub static INV_SERVER_CONFIG: Lazy<Mutex<InvServerConfig>> =
+    Lazy::new(|| Mutex::new(InvServerConfig::default()));
+
+pub static DATABASE_CONFIG: Lazy<Mutex<DatabaseConfig>> =
+    Lazy::new(|| Mutex::new(DatabaseConfig::default()));
+
 pub static TCP_CONFIG: Lazy<Mutex<TcpConfig>> = Lazy::new(|| {
     Mutex::new(TcpConfig::tcp_config_init(json!({
         "app_listen_port": 0,
@@ -29,22 +39,48 @@ pub static TCP_CONFIG: Lazy<Mutex<TcpConfig>> = Lazy::new(|| {
     })))
 });
 
+pub static WEB_SERVER_CONFIG: Lazy<Mutex<WebServerConfig>> =
+    Lazy::new(|| Mutex::new(WebServerConfig::default()));
+
+pub static PUBSUB_CONFIG: Lazy<Mutex<PubsubConfig>> =
+    Lazy::new(|| Mutex::new(PubsubConfig::default()));
+
 pub fn config_analyze(config_file: &Path) {
     match fs::read_to_string(config_file) {
         Ok(config_str) => match serde_json::from_str::<Value>(&config_str) {
             Ok(config_json) => {
                 let ctx_server_config = config_json["ctx_server_config"].clone();
+                let inv_server_config = config_json["inv_server_config"].clone();
+                let database_config = config_json["database_config"].clone();
                 let tcp_config = config_json["tcp_config"].clone();
+                let web_server_config = config_json["web_server_config"].clone();
+                let pubsub_config = config_json["pubsub_config"].clone();
 
                 let mut ctx_server_config_mut = CTX_SERVER_CONFIG.lock().unwrap();
                 *ctx_server_config_mut = CtxServerConfig::ctx_server_config_init(ctx_server_config);
 
+                let mut inv_server_config_mut = INV_SERVER_CONFIG.lock().unwrap();
+                *inv_server_config_mut = InvServerConfig::inv_server_config_init(inv_server_config);
+
+                let mut database_config_mut = DATABASE_CONFIG.lock().unwrap();
+                *database_config_mut = DatabaseConfig::database_config_init(database_config);
+
                 let mut tcp_config_mut = TCP_CONFIG.lock().unwrap();
                 *tcp_config_mut = TcpConfig::tcp_config_init(tcp_config);
 
+                let mut web_server_config_mut = WEB_SERVER_CONFIG.lock().unwrap();
+                *web_server_config_mut = WebServerConfig::web_server_config_init(web_server_config);
+
+                let mut pubsub_config_mut = PUBSUB_CONFIG.lock().unwrap();
+                *pubsub_config_mut = PubsubConfig::pubsub_config_init(pubsub_config);
+
                 info!("config file analyze success");
                 info!("ctx_server_config: {:?}", *ctx_server_config_mut);
+                info!("inv_server_config: {:?}", *inv_server_config_mut);
+                info!("database_config: {:?}", *database_config_mut);
                 info!("tcp_config: {:?}", *tcp_config_mut);
+                info!("web_server_config: {:?}", *web_server_config_mut);
+                info!("pubsub_config: {:?}", *pubsub_config_mut);
             }
             Err(e) => {
                 error!("parse config file error: {}", e);
@@ -64,7 +100,7 @@ mod tests {
 
     #[test]
     fn test_config_analyze() {
-        Builder::new().parse_filters("info").init();
+        let _ = Builder::new().parse_filters("info").try_init();
         let config_file = "./configfile".to_string();
         config_analyze(config_file.as_ref());
     }
@@ -1,8 +1,11 @@
 use std::collections::HashMap;
+use std::str::FromStr;
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use common::structs::enumeration::ctx_validator::CtxValidator;
+
 use crate::resource::sensor_mgr::SensorMgr;
 
 /// CtxServerConfig is a struct that contains the configuration of ctx_server.
@@ -32,6 +35,13 @@ impl CtxServerConfig {
         &self.ctx_validator
     }
 
+    /// get ctx validator type
+    /// join checker and scheduler, e.g. "ECC+IMD" is EccImd
+    /// return none if ctx validator is unknown
+    pub fn get_ctx_validator_type(&self) -> Option<CtxValidator> {
+        CtxValidator::from_str(&format!("{}{}", self.ctx_checker, self.ctx_scheduler)).ok()
+    }
+
     pub fn get_ctx_checker(&self) -> &str {
         &self.ctx_checker
     }
@@ -127,5 +137,23 @@ mod tests {
         });
         let ctx_server_config: CtxServerConfig = CtxServerConfig::ctx_server_config_init(value);
         println!("{:?}", ctx_server_config);
+        assert_eq!(ctx_server_config.get_ctx_validator_type(), None);
+    }
+
+    #[test]
+    fn test_get_ctx_validator_type() {
+        let value = json!({
+            "server_on": true,
+            "ctx_validator": "ConC+GEAS",
+            "base_rule_file": "base_rule_file",
+            "base_bfunc_file": "base_bfunc_file",
+            "base_pattern_file": "base_pattern_file",
+            "base_mfunc_file": "base_mfunc_file",
+        });
+        let ctx_server_config: CtxServerConfig = CtxServerConfig::ctx_server_config_init(value);
+        assert_eq!(
+            ctx_server_config.get_ctx_validator_type(),
+            Some(CtxValidator::ConCGeas)
+        );
     }
 }
@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// default number of ops between two snapshots of an app database
+pub const DEFAULT_SNAPSHOT_INTERVAL: usize = 1000;
+
+fn default_snapshot_interval() -> usize {
+    DEFAULT_SNAPSHOT_INTERVAL
+}
+
+/// DatabaseConfig is a struct that contains the configuration of app databases.
+/// databases are persisted in data_dir, an empty data_dir keeps them in memory only
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DatabaseConfig {
+    #[serde(default)]
+    data_dir: String,
+    #[serde(default = "default_snapshot_interval")]
+    snapshot_interval: usize,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            data_dir: String::new(),
+            snapshot_interval: DEFAULT_SNAPSHOT_INTERVAL,
+        }
+    }
+}
+
+impl DatabaseConfig {
+    //getter
+    pub fn get_data_dir(&self) -> &str {
+        &self.data_dir
+    }
+
+    pub fn get_snapshot_interval(&self) -> usize {
+        self.snapshot_interval
+    }
+
+    /// whether databases are persisted
+    pub fn is_persistent(&self) -> bool {
+        !self.data_dir.is_empty()
+    }
+
+    //init
+    /// null means the config file has no database_config, use default
+    pub fn database_config_init(json_object: Value) -> Self {
+        if json_object.is_null() {
+            return Self::default();
+        }
+        serde_json::from_value(json_object).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_database_config_init() {
+        let database_config = DatabaseConfig::database_config_init(Value::Null);
+        println!("{:?}", database_config);
+        assert!(!database_config.is_persistent());
+
+        let database_config =
+            DatabaseConfig::database_config_init(json!({"data_dir": "output/db"}));
+        assert!(database_config.is_persistent());
+        assert_eq!(
+            database_config.get_snapshot_interval(),
+            DEFAULT_SNAPSHOT_INTERVAL
+        );
+    }
+}
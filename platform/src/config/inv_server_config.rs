@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+fn default_server_on() -> bool {
+    true
+}
+
+fn default_trace_dir() -> String {
+    "output/inv".to_string()
+}
+
+/// InvServerConfig is a struct that contains the configuration of inv_server.
+/// traces of checks are recorded in trace_dir, an empty trace_dir records nothing
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct InvServerConfig {
+    #[serde(default = "default_server_on")]
+    server_on: bool,
+    #[serde(default = "default_trace_dir")]
+    trace_dir: String,
+}
+
+impl Default for InvServerConfig {
+    fn default() -> Self {
+        Self {
+            server_on: default_server_on(),
+            trace_dir: default_trace_dir(),
+        }
+    }
+}
+
+impl InvServerConfig {
+    //getter
+    pub fn is_server_on(&self) -> bool {
+        self.server_on
+    }
+
+    pub fn get_trace_dir(&self) -> &str {
+        &self.trace_dir
+    }
+
+    //init
+    /// null means the config file has no inv_server_config, use default
+    pub fn inv_server_config_init(json_object: Value) -> Self {
+        if json_object.is_null() {
+            return Self::default();
+        }
+        serde_json::from_value(json_object).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_inv_server_config_init() {
+        let inv_server_config = InvServerConfig::inv_server_config_init(Value::Null);
+        println!("{:?}", inv_server_config);
+        assert_eq!(inv_server_config, InvServerConfig::default());
+
+        let inv_server_config =
+            InvServerConfig::inv_server_config_init(json!({"server_on": false, "trace_dir": ""}));
+        println!("{}", serde_json::to_string(&inv_server_config).unwrap());
+        assert!(!inv_server_config.is_server_on());
+        assert_eq!(inv_server_config.get_trace_dir(), "");
+    }
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// PubsubConfig is a struct that contains the configuration of pubsub.
+/// get sensor data is answered by the retained message of sensor channel
+/// if it is not older than retained_max_age_ms, 0 always requests the sensor
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct PubsubConfig {
+    #[serde(default)]
+    retained_max_age_ms: u64,
+}
+
+impl PubsubConfig {
+    //getter
+    pub fn get_retained_max_age_ms(&self) -> u64 {
+        self.retained_max_age_ms
+    }
+
+    //init
+    /// null means the config file has no pubsub_config, use default
+    pub fn pubsub_config_init(json_object: Value) -> Self {
+        if json_object.is_null() {
+            return Self::default();
+        }
+        serde_json::from_value(json_object).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_pubsub_config_init() {
+        let pubsub_config = PubsubConfig::pubsub_config_init(Value::Null);
+        println!("{:?}", pubsub_config);
+        assert_eq!(pubsub_config.get_retained_max_age_ms(), 0);
+
+        let pubsub_config = PubsubConfig::pubsub_config_init(json!({"retained_max_age_ms": 500}));
+        assert_eq!(pubsub_config.get_retained_max_age_ms(), 500);
+    }
+}
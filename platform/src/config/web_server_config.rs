@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// default port of web server
+pub const DEFAULT_WEB_PORT: u16 = 8080;
+
+fn default_port() -> u16 {
+    DEFAULT_WEB_PORT
+}
+
+/// WebServerConfig is a struct that contains the configuration of web server.
+/// web server serves a dashboard and json api of platform state, it is off by default
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WebServerConfig {
+    #[serde(default)]
+    server_on: bool,
+    #[serde(default = "default_port")]
+    port: u16,
+}
+
+impl Default for WebServerConfig {
+    fn default() -> Self {
+        Self {
+            server_on: false,
+            port: DEFAULT_WEB_PORT,
+        }
+    }
+}
+
+impl WebServerConfig {
+    //getter
+    pub fn is_server_on(&self) -> bool {
+        self.server_on
+    }
+
+    pub fn get_port(&self) -> u16 {
+        self.port
+    }
+
+    //init
+    /// null means the config file has no web_server_config, use default
+    pub fn web_server_config_init(json_object: Value) -> Self {
+        if json_object.is_null() {
+            return Self::default();
+        }
+        serde_json::from_value(json_object).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_web_server_config_init() {
+        let web_server_config = WebServerConfig::web_server_config_init(Value::Null);
+        println!("{:?}", web_server_config);
+        assert!(!web_server_config.is_server_on());
+
+        let web_server_config = WebServerConfig::web_server_config_init(json!({"server_on": true}));
+        assert!(web_server_config.is_server_on());
+        assert_eq!(web_server_config.get_port(), DEFAULT_WEB_PORT);
+    }
+}
@@ -1,4 +1,5 @@
 pub mod condition;
 pub mod database;
 pub mod order;
+pub mod store;
 pub mod table;
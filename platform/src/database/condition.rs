@@ -1 +1,44 @@
+use serde_json::{Map, Value};
 
+pub use common::structs::db_query::Condition;
+
+use crate::service::ctx::expr::compare;
+
+/// whether row satisfies condition
+/// a missing column is null
+pub fn matches(condition: &Condition, row: &Map<String, Value>) -> bool {
+    compare(
+        condition.op,
+        row.get(&condition.column).unwrap_or(&Value::Null),
+        &condition.value,
+    )
+}
+
+/// whether row satisfies all conditions
+pub fn matches_all(conditions: &[Condition], row: &Map<String, Value>) -> bool {
+    conditions.iter().all(|condition| matches(condition, row))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use common::structs::enumeration::compare_type::CompareType;
+
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        let row = json!({"id": 1, "speed": 30, "color": "red"});
+        let row = row.as_object().unwrap();
+        let conditions: Vec<Condition> = serde_json::from_value(json!([
+            {"column": "speed", "op": ">", "value": 10},
+            {"column": "color", "op": "in", "value": ["red", "green"]}
+        ]))
+        .unwrap();
+        assert!(matches_all(&conditions, row));
+
+        let condition = Condition::new("speed".to_string(), CompareType::LE, json!(20));
+        assert!(!matches(&condition, row));
+    }
+}
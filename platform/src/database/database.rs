@@ -1 +1,405 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
 
+use dashmap::DashMap;
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::config::configuration::DATABASE_CONFIG;
+use crate::database::condition::Condition;
+use crate::database::store::{decode_dir_name, encode_dir_name, Op, Store};
+use crate::database::table::{Row, Table};
+
+pub type SyncDatabase = Arc<RwLock<Database>>;
+
+/// DATABASES maps app name to its database.
+/// a database outlives app registration, so an app sees its tables again after it registers back
+static DATABASES: Lazy<DashMap<String, SyncDatabase>> = Lazy::new(DashMap::new);
+
+#[derive(Error, Debug)]
+pub enum DbError {
+    #[error("table {0} already exists")]
+    TableExistsError(String),
+    #[error("table {0} does not exist")]
+    TableNotFoundError(String),
+    #[error("invalid header of table {0}: {1}")]
+    InvalidHeaderError(String, String),
+    #[error("table {0} has no column {1}")]
+    UnknownColumnError(String, String),
+    #[error("table {0} lacks primary key {1}")]
+    MissingKeyError(String, String),
+    #[error("table {0} already has primary key value {1}")]
+    DuplicateKeyError(String, String),
+    #[error("primary key {1} of table {0} cannot be updated")]
+    UpdateKeyError(String, String),
+    #[error("persist database err: {0}")]
+    PersistError(String),
+}
+
+/// directory of app database if databases are persisted
+fn get_database_dir(app_name: &str) -> Option<(PathBuf, usize)> {
+    let config = DATABASE_CONFIG.lock().expect("get database config fail");
+    config.is_persistent().then(|| {
+        (
+            Path::new(config.get_data_dir()).join(encode_dir_name(app_name)),
+            config.get_snapshot_interval(),
+        )
+    })
+}
+
+/// get database of app
+/// an empty database is created for a new app,
+/// a database that cannot be opened is kept in memory only
+pub fn get_database(app_name: &str) -> SyncDatabase {
+    DATABASES
+        .entry(app_name.to_string())
+        .or_insert_with(|| {
+            let database = match get_database_dir(app_name) {
+                Some((dir, snapshot_interval)) => Database::open(&dir, snapshot_interval)
+                    .unwrap_or_else(|e| {
+                        error!("open database of app {} fail: {}", app_name, e);
+                        Database::new()
+                    }),
+                None => Database::new(),
+            };
+            Arc::new(RwLock::new(database))
+        })
+        .clone()
+}
+
+/// restore databases
+/// open every app database in data dir, called when platform starts
+/// return the number of restored databases
+pub fn restore_databases() -> usize {
+    let data_dir = {
+        let config = DATABASE_CONFIG.lock().expect("get database config fail");
+        if !config.is_persistent() {
+            return 0;
+        }
+        PathBuf::from(config.get_data_dir())
+    };
+    let entries = match fs::read_dir(&data_dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            info!("no database restored from {:?}: {}", data_dir, e);
+            return 0;
+        }
+    };
+    let mut count = 0;
+    for entry in entries.flatten() {
+        let app_name = match entry.file_name().to_str().and_then(decode_dir_name) {
+            Some(app_name) if entry.path().is_dir() => app_name,
+            _ => continue,
+        };
+        let database = get_database(&app_name);
+        let tables = database
+            .read()
+            .expect("read database fail")
+            .get_table_names();
+        info!("restore database of app {}: {:?}", app_name, tables);
+        count += 1;
+    }
+    count
+}
+
+/// snapshot databases
+/// called when platform shuts down so that logs are compacted
+pub fn snapshot_databases() {
+    for entry in DATABASES.iter() {
+        if let Err(e) = entry
+            .value()
+            .write()
+            .expect("write database fail")
+            .snapshot()
+        {
+            error!("snapshot database of app {} fail: {}", entry.key(), e);
+        }
+    }
+}
+
+/// Database holds tables of one app
+/// every change goes through execute, so it is logged when database is persisted
+#[derive(Default)]
+pub struct Database {
+    tables: HashMap<String, Table>,
+    store: Option<Store>,
+}
+
+impl Database {
+    /// new
+    /// a database in memory only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// open a persisted database in dir
+    /// tables are restored from snapshot and log
+    pub fn open(dir: &Path, snapshot_interval: usize) -> Result<Self, DbError> {
+        let (store, snapshot, ops) = Store::open(dir, snapshot_interval)?;
+        let mut database = Self::new();
+        for table in snapshot.tables {
+            let table = table.into_table()?;
+            database.tables.insert(table.get_name().to_string(), table);
+        }
+        for op in &ops {
+            if let Err(e) = database.apply(op) {
+                warn!("replay {:?} of database {:?} fail: {}", op, dir, e);
+            }
+        }
+        database.store = Some(store);
+        Ok(database)
+    }
+
+    /// apply op to tables
+    /// return the number of changed rows
+    fn apply(&mut self, op: &Op) -> Result<usize, DbError> {
+        match op {
+            Op::Create {
+                table_name,
+                primary_key,
+                header,
+                row_limit,
+            } => {
+                if self.tables.contains_key(table_name) {
+                    return Err(DbError::TableExistsError(table_name.clone()));
+                }
+                let table = Table::new(
+                    table_name.clone(),
+                    primary_key.clone(),
+                    header.clone(),
+                    *row_limit,
+                )?;
+                self.tables.insert(table_name.clone(), table);
+                Ok(0)
+            }
+            Op::Drop { table_name } => self
+                .tables
+                .remove(table_name)
+                .map(|_| 0)
+                .ok_or_else(|| DbError::TableNotFoundError(table_name.clone())),
+            Op::Inserts { table_name, rows } => {
+                self.get_table_mut(table_name)?.inserts(rows.clone())
+            }
+            Op::Update {
+                table_name,
+                values,
+                conditions,
+            } => self
+                .get_table_mut(table_name)?
+                .update(values.clone(), conditions),
+            Op::Delete { table_name, key } => {
+                Ok(self.get_table_mut(table_name)?.delete(key) as usize)
+            }
+            Op::Deletes {
+                table_name,
+                conditions,
+            } => self.get_table_mut(table_name)?.deletes(conditions),
+        }
+    }
+
+    /// execute op
+    /// apply op, then append it to log and take a snapshot if it is time
+    /// an op changing nothing is not logged
+    /// return the number of changed rows
+    pub fn execute(&mut self, op: Op) -> Result<usize, DbError> {
+        let count = self.apply(&op)?;
+        let changed = count > 0 || matches!(op, Op::Create { .. } | Op::Drop { .. });
+        if let Some(store) = self.store.as_mut().filter(|_| changed) {
+            store.append(&op)?;
+            if store.should_snapshot() {
+                store.snapshot(self.tables.values())?;
+            }
+        }
+        Ok(count)
+    }
+
+    /// snapshot
+    /// write all tables to snapshot file and truncate log, nothing is done in memory only
+    pub fn snapshot(&mut self) -> Result<(), DbError> {
+        match self.store.as_mut() {
+            Some(store) => store.snapshot(self.tables.values()),
+            None => Ok(()),
+        }
+    }
+
+    /// create table
+    /// row_limit 0 means no limit
+    pub fn create_table(
+        &mut self,
+        table_name: String,
+        primary_key: String,
+        header: Vec<String>,
+        row_limit: usize,
+    ) -> Result<(), DbError> {
+        self.execute(Op::Create {
+            table_name,
+            primary_key,
+            header,
+            row_limit,
+        })
+        .map(|_| ())
+    }
+
+    /// drop table
+    pub fn drop_table(&mut self, table_name: &str) -> Result<(), DbError> {
+        self.execute(Op::Drop {
+            table_name: table_name.to_string(),
+        })
+        .map(|_| ())
+    }
+
+    /// insert a row
+    pub fn insert(&mut self, table_name: &str, row: Row) -> Result<(), DbError> {
+        self.inserts(table_name, vec![row]).map(|_| ())
+    }
+
+    /// insert rows
+    /// return the number of inserted rows
+    pub fn inserts(&mut self, table_name: &str, rows: Vec<Row>) -> Result<usize, DbError> {
+        self.execute(Op::Inserts {
+            table_name: table_name.to_string(),
+            rows,
+        })
+    }
+
+    /// update columns of rows satisfying conditions
+    /// return the number of updated rows
+    pub fn update(
+        &mut self,
+        table_name: &str,
+        values: Row,
+        conditions: Vec<Condition>,
+    ) -> Result<usize, DbError> {
+        self.execute(Op::Update {
+            table_name: table_name.to_string(),
+            values,
+            conditions,
+        })
+    }
+
+    /// delete the row of primary key value
+    /// return whether the row existed
+    pub fn delete(&mut self, table_name: &str, key: &Value) -> Result<bool, DbError> {
+        self.execute(Op::Delete {
+            table_name: table_name.to_string(),
+            key: key.clone(),
+        })
+        .map(|count| count > 0)
+    }
+
+    /// delete rows satisfying conditions
+    /// return the number of deleted rows
+    pub fn deletes(
+        &mut self,
+        table_name: &str,
+        conditions: Vec<Condition>,
+    ) -> Result<usize, DbError> {
+        self.execute(Op::Deletes {
+            table_name: table_name.to_string(),
+            conditions,
+        })
+    }
+
+    /// get table
+    pub fn get_table(&self, table_name: &str) -> Result<&Table, DbError> {
+        self.tables
+            .get(table_name)
+            .ok_or_else(|| DbError::TableNotFoundError(table_name.to_string()))
+    }
+
+    fn get_table_mut(&mut self, table_name: &str) -> Result<&mut Table, DbError> {
+        self.tables
+            .get_mut(table_name)
+            .ok_or_else(|| DbError::TableNotFoundError(table_name.to_string()))
+    }
+
+    /// get table names
+    pub fn get_table_names(&self) -> Vec<String> {
+        self.tables.keys().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn row(value: Value) -> Row {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_database() {
+        let mut db = Database::new();
+        db.create_table(
+            "cars".to_string(),
+            "name".to_string(),
+            vec!["name".to_string(), "speed".to_string()],
+            0,
+        )
+        .unwrap();
+        assert!(db
+            .create_table(
+                "cars".to_string(),
+                "name".to_string(),
+                vec!["name".to_string()],
+                0
+            )
+            .is_err());
+        db.insert("cars", row(json!({"name": "a", "speed": 1})))
+            .unwrap();
+        assert_eq!(db.get_table("cars").unwrap().len(), 1);
+        db.drop_table("cars").unwrap();
+        assert!(db.drop_table("cars").is_err());
+
+        // databases are scoped by app
+        let database = get_database("test_database_app");
+        assert!(Arc::ptr_eq(&database, &get_database("test_database_app")));
+        assert!(!Arc::ptr_eq(
+            &database,
+            &get_database("test_database_other_app")
+        ));
+    }
+
+    #[test]
+    fn test_open_persisted_database() {
+        let dir = std::env::temp_dir().join(format!("db_open_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut db = Database::open(&dir, 3).unwrap();
+        db.create_table(
+            "cars".to_string(),
+            "id".to_string(),
+            vec!["id".to_string(), "speed".to_string()],
+            2,
+        )
+        .unwrap();
+        for id in 0..4 {
+            db.insert("cars", row(json!({"id": id, "speed": id * 10})))
+                .unwrap();
+        }
+        let red = vec![Condition::new(
+            "id".to_string(),
+            common::structs::enumeration::compare_type::CompareType::EQ,
+            json!(3),
+        )];
+        assert_eq!(db.update("cars", row(json!({"speed": 0})), red).unwrap(), 1);
+        assert!(!db.delete("cars", &json!(0)).unwrap());
+        drop(db);
+
+        let db = Database::open(&dir, 3).unwrap();
+        let table = db.get_table("cars").unwrap();
+        let rows = table.rows(&[], None, None).unwrap();
+        println!("{:?}", rows);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["id"], json!(2));
+        assert_eq!(table.cell(&json!(3), "speed").unwrap(), Some(json!(0)));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
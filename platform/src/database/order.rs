@@ -1 +1,74 @@
+use std::cmp::Ordering;
 
+use serde_json::{Map, Value};
+
+pub use common::structs::db_query::Order;
+use common::structs::enumeration::order_type::OrderType;
+
+/// rank of value types, values of different types are ordered by rank
+fn type_rank(value: &Value) -> u8 {
+    match value {
+        Value::Null => 0,
+        Value::Bool(_) => 1,
+        Value::Number(_) => 2,
+        Value::String(_) => 3,
+        Value::Array(_) => 4,
+        Value::Object(_) => 5,
+    }
+}
+
+/// total order of values
+/// null < bool < number < string < array < object
+pub fn cmp_value(left: &Value, right: &Value) -> Ordering {
+    match (left, right) {
+        (Value::Bool(l), Value::Bool(r)) => l.cmp(r),
+        (Value::Number(l), Value::Number(r)) => l
+            .as_f64()
+            .unwrap_or(f64::NAN)
+            .total_cmp(&r.as_f64().unwrap_or(f64::NAN)),
+        (Value::String(l), Value::String(r)) => l.cmp(r),
+        _ => type_rank(left)
+            .cmp(&type_rank(right))
+            .then_with(|| left.to_string().cmp(&right.to_string())),
+    }
+}
+
+/// compare two rows by order
+/// a missing column is null
+pub fn cmp_rows(order: &Order, left: &Map<String, Value>, right: &Map<String, Value>) -> Ordering {
+    let ordering = cmp_value(
+        left.get(&order.column).unwrap_or(&Value::Null),
+        right.get(&order.column).unwrap_or(&Value::Null),
+    );
+    match order.order_type {
+        OrderType::Asc => ordering,
+        OrderType::Desc => ordering.reverse(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_cmp_rows() {
+        let mut rows: Vec<Map<String, Value>> = [
+            json!({"v": 2}),
+            json!({"v": "a"}),
+            json!({}),
+            json!({"v": 1.5}),
+        ]
+        .into_iter()
+        .map(|row| row.as_object().unwrap().clone())
+        .collect();
+        let order: Order =
+            serde_json::from_value(json!({"column": "v", "order_type": "Desc"})).unwrap();
+        rows.sort_by(|l, r| cmp_rows(&order, l, r));
+        println!("{:?}", rows);
+        assert_eq!(rows[0]["v"], json!("a"));
+        assert_eq!(rows[1]["v"], json!(2));
+        assert!(rows[3].is_empty());
+    }
+}
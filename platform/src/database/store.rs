@@ -0,0 +1,396 @@
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::database::condition::Condition;
+use crate::database::database::DbError;
+use crate::database::table::{Row, Table};
+
+const SNAPSHOT_FILE: &str = "snapshot.json";
+const SNAPSHOT_TMP_FILE: &str = "snapshot.json.tmp";
+const LOG_FILE: &str = "log.jsonl";
+
+/// Op is a successful change of a database, it is appended to log and replayed on restore
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Op {
+    Create {
+        table_name: String,
+        primary_key: String,
+        header: Vec<String>,
+        row_limit: usize,
+    },
+    Drop {
+        table_name: String,
+    },
+    Inserts {
+        table_name: String,
+        rows: Vec<Row>,
+    },
+    Update {
+        table_name: String,
+        values: Row,
+        conditions: Vec<Condition>,
+    },
+    Delete {
+        table_name: String,
+        key: Value,
+    },
+    Deletes {
+        table_name: String,
+        conditions: Vec<Condition>,
+    },
+}
+
+/// one line of log file
+#[derive(Debug, Serialize, Deserialize)]
+struct LogRecord {
+    seq: u64,
+    #[serde(flatten)]
+    op: Op,
+}
+
+/// TableSnapshot is a table in snapshot file, rows are in insertion order
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TableSnapshot {
+    table_name: String,
+    primary_key: String,
+    header: Vec<String>,
+    row_limit: usize,
+    rows: Vec<Row>,
+}
+
+impl TableSnapshot {
+    pub fn from_table(table: &Table) -> Self {
+        Self {
+            table_name: table.get_name().to_string(),
+            primary_key: table.get_primary_key().to_string(),
+            header: table.get_header().to_vec(),
+            row_limit: table.get_row_limit(),
+            rows: table
+                .rows(&[], None, None)
+                .expect("get rows without conditions fail"),
+        }
+    }
+
+    pub fn into_table(self) -> Result<Table, DbError> {
+        let mut table = Table::new(
+            self.table_name,
+            self.primary_key,
+            self.header,
+            self.row_limit,
+        )?;
+        table.inserts(self.rows)?;
+        Ok(table)
+    }
+}
+
+/// snapshot file holds all tables after op seq is applied
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub seq: u64,
+    pub tables: Vec<TableSnapshot>,
+}
+
+/// Store persists a database in a directory with a snapshot file and an append-only log.
+///
+/// every op is appended to log as a JSON line with an increasing seq.
+/// a snapshot is written to a temp file and renamed, then log is truncated.
+/// ops whose seq is not greater than seq of snapshot are skipped on restore,
+/// so a crash between rename and truncate replays nothing twice
+pub struct Store {
+    dir: PathBuf,
+    log: File,
+    seq: u64,
+    ops_since_snapshot: usize,
+    snapshot_interval: usize,
+}
+
+fn persist_error(path: &Path, e: impl std::fmt::Display) -> DbError {
+    DbError::PersistError(format!("{}: {}", path.display(), e))
+}
+
+/// read log file and truncate a torn tail
+/// a tail is torn if its last line has no newline or is not a valid record,
+/// an invalid line before the last one is corruption, valid records after it are not dropped
+fn read_log(path: &Path) -> Result<Vec<LogRecord>, DbError> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(persist_error(path, e)),
+    };
+    let mut records = Vec::new();
+    let mut valid_len = 0;
+    while let Some(end) = bytes[valid_len..].iter().position(|b| *b == b'\n') {
+        match serde_json::from_slice::<LogRecord>(&bytes[valid_len..valid_len + end]) {
+            Ok(record) => {
+                records.push(record);
+                valid_len += end + 1;
+            }
+            // only the last line can be torn by a crash while appending
+            Err(_) if valid_len + end + 1 == bytes.len() => break,
+            Err(e) => {
+                return Err(persist_error(
+                    path,
+                    format!("invalid record at offset {}: {}", valid_len, e),
+                ))
+            }
+        }
+    }
+    if valid_len < bytes.len() {
+        warn!(
+            "truncate torn tail of {}: {} bytes after offset {}",
+            path.display(),
+            bytes.len() - valid_len,
+            valid_len
+        );
+        let file = OpenOptions::new()
+            .write(true)
+            .open(path)
+            .map_err(|e| persist_error(path, e))?;
+        file.set_len(valid_len as u64)
+            .and_then(|_| file.sync_all())
+            .map_err(|e| persist_error(path, e))?;
+    }
+    Ok(records)
+}
+
+impl Store {
+    /// open store in dir
+    /// return the store, the snapshot and ops after the snapshot
+    /// snapshot_interval 0 means a snapshot is only taken on demand
+    pub fn open(
+        dir: &Path,
+        snapshot_interval: usize,
+    ) -> Result<(Self, Snapshot, Vec<Op>), DbError> {
+        fs::create_dir_all(dir).map_err(|e| persist_error(dir, e))?;
+
+        let snapshot_path = dir.join(SNAPSHOT_FILE);
+        let snapshot: Snapshot = match fs::read(&snapshot_path) {
+            Ok(bytes) => {
+                serde_json::from_slice(&bytes).map_err(|e| persist_error(&snapshot_path, e))?
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Snapshot::default(),
+            Err(e) => return Err(persist_error(&snapshot_path, e)),
+        };
+
+        let log_path = dir.join(LOG_FILE);
+        let records = read_log(&log_path)?;
+        let seq = records
+            .last()
+            .map_or(snapshot.seq, |record| record.seq.max(snapshot.seq));
+        let ops: Vec<Op> = records
+            .into_iter()
+            .filter(|record| record.seq > snapshot.seq)
+            .map(|record| record.op)
+            .collect();
+        let log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log_path)
+            .map_err(|e| persist_error(&log_path, e))?;
+
+        let store = Self {
+            dir: dir.to_path_buf(),
+            log,
+            seq,
+            ops_since_snapshot: ops.len(),
+            snapshot_interval,
+        };
+        Ok((store, snapshot, ops))
+    }
+
+    /// append op to log
+    /// record is synced to disk before it returns, so an op is not lost on power failure
+    pub fn append(&mut self, op: &Op) -> Result<(), DbError> {
+        let record = LogRecord {
+            seq: self.seq + 1,
+            op: op.clone(),
+        };
+        let mut line = serde_json::to_vec(&record).expect("serialize log record fail");
+        line.push(b'\n');
+        self.log
+            .write_all(&line)
+            .and_then(|_| self.log.sync_data())
+            .map_err(|e| persist_error(&self.dir.join(LOG_FILE), e))?;
+        self.seq += 1;
+        self.ops_since_snapshot += 1;
+        Ok(())
+    }
+
+    /// whether enough ops are appended since the last snapshot
+    pub fn should_snapshot(&self) -> bool {
+        self.snapshot_interval > 0 && self.ops_since_snapshot >= self.snapshot_interval
+    }
+
+    /// write a snapshot of tables and truncate log
+    pub fn snapshot<'a>(&mut self, tables: impl Iterator<Item = &'a Table>) -> Result<(), DbError> {
+        let snapshot = Snapshot {
+            seq: self.seq,
+            tables: tables.map(TableSnapshot::from_table).collect(),
+        };
+        let tmp_path = self.dir.join(SNAPSHOT_TMP_FILE);
+        let snapshot_path = self.dir.join(SNAPSHOT_FILE);
+        File::create(&tmp_path)
+            .and_then(|mut file| {
+                file.write_all(&serde_json::to_vec(&snapshot).expect("serialize snapshot fail"))?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&tmp_path, &snapshot_path))
+            .and_then(|_| File::open(&self.dir)?.sync_all())
+            .map_err(|e| persist_error(&snapshot_path, e))?;
+
+        self.log
+            .set_len(0)
+            .and_then(|_| self.log.sync_all())
+            .map_err(|e| persist_error(&self.dir.join(LOG_FILE), e))?;
+        self.ops_since_snapshot = 0;
+        Ok(())
+    }
+}
+
+/// encode app name as a directory name
+/// characters other than ascii alphanumeric, '.', '_' and '-' are written as %XX
+pub fn encode_dir_name(app_name: &str) -> String {
+    app_name
+        .bytes()
+        .map(|b| match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'.' | b'_' | b'-' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// decode a directory name written by encode_dir_name
+/// return none if it is not an encoded name
+pub fn decode_dir_name(dir_name: &str) -> Option<String> {
+    let bytes = dir_name.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = dir_name.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn create_op() -> Op {
+        Op::Create {
+            table_name: "cars".to_string(),
+            primary_key: "id".to_string(),
+            header: vec!["id".to_string(), "speed".to_string()],
+            row_limit: 0,
+        }
+    }
+
+    fn insert_op(id: i32) -> Op {
+        Op::Inserts {
+            table_name: "cars".to_string(),
+            rows: vec![json!({"id": id, "speed": 10}).as_object().unwrap().clone()],
+        }
+    }
+
+    #[test]
+    fn test_torn_tail_and_snapshot() {
+        let dir = std::env::temp_dir().join(format!("db_store_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let (mut store, snapshot, ops) = Store::open(&dir, 0).unwrap();
+        assert!(snapshot.tables.is_empty() && ops.is_empty());
+        store.append(&create_op()).unwrap();
+        store.append(&insert_op(1)).unwrap();
+        drop(store);
+
+        // a crash while writing the third record
+        let log_path = dir.join(LOG_FILE);
+        let good_len = fs::metadata(&log_path).unwrap().len();
+        let mut log = OpenOptions::new().append(true).open(&log_path).unwrap();
+        log.write_all(br#"{"seq":3,"op":"inserts","table_na"#)
+            .unwrap();
+        drop(log);
+
+        let (mut store, _, ops) = Store::open(&dir, 0).unwrap();
+        assert_eq!(ops, vec![create_op(), insert_op(1)]);
+        assert_eq!(fs::metadata(&log_path).unwrap().len(), good_len);
+
+        let mut table = Table::new(
+            "cars".to_string(),
+            "id".to_string(),
+            vec!["id".to_string(), "speed".to_string()],
+            0,
+        )
+        .unwrap();
+        table
+            .insert(json!({"id": 1, "speed": 10}).as_object().unwrap().clone())
+            .unwrap();
+        store.snapshot([&table].into_iter()).unwrap();
+        store.append(&insert_op(2)).unwrap();
+        drop(store);
+
+        let (_, snapshot, ops) = Store::open(&dir, 0).unwrap();
+        assert_eq!(snapshot.seq, 2);
+        assert_eq!(snapshot.tables[0].rows.len(), 1);
+        assert_eq!(ops, vec![insert_op(2)]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_corrupted_log() {
+        let dir = std::env::temp_dir().join(format!("db_store_corrupt_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let (mut store, _, _) = Store::open(&dir, 0).unwrap();
+        store.append(&create_op()).unwrap();
+        drop(store);
+
+        // an invalid line with a valid record after it is not a torn tail
+        let log_path = dir.join(LOG_FILE);
+        let mut log = OpenOptions::new().append(true).open(&log_path).unwrap();
+        let record = LogRecord {
+            seq: 2,
+            op: insert_op(1),
+        };
+        log.write_all(b"not a record\n").unwrap();
+        log.write_all(&serde_json::to_vec(&record).unwrap())
+            .unwrap();
+        log.write_all(b"\n").unwrap();
+        drop(log);
+        let len = fs::metadata(&log_path).unwrap().len();
+
+        assert!(matches!(
+            Store::open(&dir, 0),
+            Err(DbError::PersistError(_))
+        ));
+        assert_eq!(fs::metadata(&log_path).unwrap().len(), len);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_dir_name() {
+        let app_name = "app::my_app/1";
+        let dir_name = encode_dir_name(app_name);
+        println!("{}", dir_name);
+        assert!(!dir_name.contains('/'));
+        assert_eq!(decode_dir_name(&dir_name).unwrap(), app_name);
+        assert_eq!(decode_dir_name("a%2"), None);
+    }
+}
@@ -1 +1,369 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
 
+use serde_json::{Map, Value};
+
+use crate::database::condition::{matches_all, Condition};
+use crate::database::database::DbError;
+use crate::database::order::{cmp_rows, Order};
+
+/// Row maps column to value, every column of header is present
+pub type Row = Map<String, Value>;
+
+/// Table is a table of app database.
+/// rows are kept in insertion order,
+/// if row_limit is not 0, the oldest row is evicted when table is full
+#[derive(Debug, Clone)]
+pub struct Table {
+    name: String,
+    primary_key: String,
+    header: Vec<String>,
+    row_limit: usize,
+    rows: BTreeMap<u64, Row>,
+    keys: HashMap<String, u64>,
+    next_seq: u64,
+}
+
+/// index key of a primary key value
+fn key_of(value: &Value) -> String {
+    value.to_string()
+}
+
+impl Table {
+    /// new
+    /// header must be unique and contain primary key
+    pub fn new(
+        name: String,
+        primary_key: String,
+        header: Vec<String>,
+        row_limit: usize,
+    ) -> Result<Self, DbError> {
+        let columns: HashSet<&String> = header.iter().collect();
+        if header.is_empty() || columns.len() != header.len() {
+            return Err(DbError::InvalidHeaderError(
+                name,
+                "header is empty or has duplicate columns".to_string(),
+            ));
+        }
+        if !columns.contains(&primary_key) {
+            return Err(DbError::InvalidHeaderError(
+                name,
+                format!("primary key {} is not in header", primary_key),
+            ));
+        }
+        Ok(Self {
+            name,
+            primary_key,
+            header,
+            row_limit,
+            rows: BTreeMap::new(),
+            keys: HashMap::new(),
+            next_seq: 0,
+        })
+    }
+
+    //getter
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_primary_key(&self) -> &str {
+        &self.primary_key
+    }
+
+    pub fn get_header(&self) -> &[String] {
+        &self.header
+    }
+
+    pub fn get_row_limit(&self) -> usize {
+        self.row_limit
+    }
+
+    /// number of rows
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// make sure column is in header
+    fn check_column(&self, column: &str) -> Result<(), DbError> {
+        if self.header.iter().any(|c| c == column) {
+            Ok(())
+        } else {
+            Err(DbError::UnknownColumnError(
+                self.name.clone(),
+                column.to_string(),
+            ))
+        }
+    }
+
+    fn check_conditions(&self, conditions: &[Condition]) -> Result<(), DbError> {
+        conditions
+            .iter()
+            .try_for_each(|condition| self.check_column(&condition.column))
+    }
+
+    /// complete a row to be inserted
+    /// missing columns are null, primary key must not be null
+    fn complete_row(&self, mut row: Row) -> Result<Row, DbError> {
+        for column in row.keys() {
+            self.check_column(column)?;
+        }
+        match row.get(&self.primary_key) {
+            Some(key) if !key.is_null() => {}
+            _ => {
+                return Err(DbError::MissingKeyError(
+                    self.name.clone(),
+                    self.primary_key.clone(),
+                ))
+            }
+        }
+        for column in &self.header {
+            row.entry(column.clone()).or_insert(Value::Null);
+        }
+        Ok(row)
+    }
+
+    fn push_row(&mut self, row: Row) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.keys.insert(key_of(&row[&self.primary_key]), seq);
+        self.rows.insert(seq, row);
+        while self.row_limit > 0 && self.rows.len() > self.row_limit {
+            let (_, evicted) = self.rows.pop_first().expect("pop oldest row fail");
+            self.keys.remove(&key_of(&evicted[&self.primary_key]));
+        }
+    }
+
+    /// insert a row
+    pub fn insert(&mut self, row: Row) -> Result<(), DbError> {
+        self.inserts(vec![row]).map(|_| ())
+    }
+
+    /// insert rows
+    /// nothing is inserted if any row is invalid
+    /// return the number of inserted rows
+    pub fn inserts(&mut self, rows: Vec<Row>) -> Result<usize, DbError> {
+        let mut batch_keys = HashSet::new();
+        let mut completed = Vec::with_capacity(rows.len());
+        for row in rows {
+            let row = self.complete_row(row)?;
+            let key = key_of(&row[&self.primary_key]);
+            if self.keys.contains_key(&key) || !batch_keys.insert(key.clone()) {
+                return Err(DbError::DuplicateKeyError(self.name.clone(), key));
+            }
+            completed.push(row);
+        }
+        let count = completed.len();
+        for row in completed {
+            self.push_row(row);
+        }
+        Ok(count)
+    }
+
+    /// update columns of rows satisfying conditions
+    /// primary key cannot be updated
+    /// return the number of updated rows
+    pub fn update(&mut self, values: Row, conditions: &[Condition]) -> Result<usize, DbError> {
+        self.check_conditions(conditions)?;
+        for column in values.keys() {
+            self.check_column(column)?;
+            if *column == self.primary_key {
+                return Err(DbError::UpdateKeyError(self.name.clone(), column.clone()));
+            }
+        }
+        let mut count = 0;
+        for row in self.rows.values_mut() {
+            if matches_all(conditions, row) {
+                for (column, value) in &values {
+                    row.insert(column.clone(), value.clone());
+                }
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// delete the row of primary key value
+    /// return whether the row existed
+    pub fn delete(&mut self, key: &Value) -> bool {
+        match self.keys.remove(&key_of(key)) {
+            Some(seq) => self.rows.remove(&seq).is_some(),
+            None => false,
+        }
+    }
+
+    /// delete rows satisfying conditions
+    /// return the number of deleted rows
+    pub fn deletes(&mut self, conditions: &[Condition]) -> Result<usize, DbError> {
+        self.check_conditions(conditions)?;
+        let seqs: Vec<u64> = self
+            .rows
+            .iter()
+            .filter(|(_, row)| matches_all(conditions, row))
+            .map(|(seq, _)| *seq)
+            .collect();
+        for seq in &seqs {
+            let row = self.rows.remove(seq).expect("remove row fail");
+            self.keys.remove(&key_of(&row[&self.primary_key]));
+        }
+        Ok(seqs.len())
+    }
+
+    /// get the row of primary key value
+    pub fn row(&self, key: &Value) -> Option<&Row> {
+        self.keys
+            .get(&key_of(key))
+            .and_then(|seq| self.rows.get(seq))
+    }
+
+    /// get rows satisfying conditions
+    /// rows are in insertion order unless order is given,
+    /// at most limit rows are returned if limit is given
+    pub fn rows(
+        &self,
+        conditions: &[Condition],
+        order: Option<&Order>,
+        limit: Option<usize>,
+    ) -> Result<Vec<Row>, DbError> {
+        self.check_conditions(conditions)?;
+        if let Some(order) = order {
+            self.check_column(&order.column)?;
+        }
+        let mut rows: Vec<&Row> = self
+            .rows
+            .values()
+            .filter(|row| matches_all(conditions, row))
+            .collect();
+        if let Some(order) = order {
+            rows.sort_by(|l, r| cmp_rows(order, l, r));
+        }
+        Ok(rows
+            .into_iter()
+            .take(limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect())
+    }
+
+    /// get values of a column of rows satisfying conditions
+    pub fn column(&self, column: &str, conditions: &[Condition]) -> Result<Vec<Value>, DbError> {
+        self.check_column(column)?;
+        Ok(self
+            .rows(conditions, None, None)?
+            .into_iter()
+            .map(|mut row| row.remove(column).unwrap_or(Value::Null))
+            .collect())
+    }
+
+    /// get some columns of rows satisfying conditions
+    pub fn columns(
+        &self,
+        columns: &[String],
+        conditions: &[Condition],
+    ) -> Result<Vec<Row>, DbError> {
+        columns
+            .iter()
+            .try_for_each(|column| self.check_column(column))?;
+        Ok(self
+            .rows(conditions, None, None)?
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .filter(|(column, _)| columns.contains(column))
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// get a cell of the row of primary key value
+    /// return none if the row does not exist
+    pub fn cell(&self, key: &Value, column: &str) -> Result<Option<Value>, DbError> {
+        self.check_column(column)?;
+        Ok(self.row(key).map(|row| row[column].clone()))
+    }
+
+    /// count rows satisfying conditions
+    pub fn count(&self, conditions: &[Condition]) -> Result<usize, DbError> {
+        self.check_conditions(conditions)?;
+        Ok(self
+            .rows
+            .values()
+            .filter(|row| matches_all(conditions, row))
+            .count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use common::structs::enumeration::compare_type::CompareType;
+    use common::structs::enumeration::order_type::OrderType;
+
+    use super::*;
+
+    fn row(value: Value) -> Row {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_table() {
+        assert!(Table::new("t".to_string(), "id".to_string(), vec!["x".to_string()], 0).is_err());
+        let header = vec!["id".to_string(), "speed".to_string(), "color".to_string()];
+        let mut table = Table::new("t".to_string(), "id".to_string(), header, 3).unwrap();
+
+        table.insert(row(json!({"id": 1, "speed": 10}))).unwrap();
+        assert!(table.insert(row(json!({"id": 1}))).is_err());
+        assert!(table.insert(row(json!({"speed": 1}))).is_err());
+        assert!(table.insert(row(json!({"id": 2, "size": 1}))).is_err());
+        assert_eq!(
+            table
+                .inserts(vec![
+                    row(json!({"id": 2, "speed": 30, "color": "red"})),
+                    row(json!({"id": 3, "speed": 20, "color": "green"})),
+                    row(json!({"id": 4, "speed": 40, "color": "red"})),
+                ])
+                .unwrap(),
+            3
+        );
+        // ring buffer evicts the oldest row
+        assert_eq!(table.len(), 3);
+        assert!(table.row(&json!(1)).is_none());
+        assert_eq!(table.cell(&json!(2), "color").unwrap(), Some(json!("red")));
+
+        let red = [Condition::new(
+            "color".to_string(),
+            CompareType::EQ,
+            json!("red"),
+        )];
+        assert_eq!(table.count(&red).unwrap(), 2);
+        assert_eq!(table.update(row(json!({"speed": 0})), &red).unwrap(), 2);
+        assert!(table.update(row(json!({"id": 0})), &red).is_err());
+        assert_eq!(
+            table.column("speed", &[]).unwrap(),
+            vec![json!(0), json!(20), json!(0)]
+        );
+
+        let order = Order::new("id".to_string(), OrderType::Desc);
+        let rows = table.rows(&[], Some(&order), Some(2)).unwrap();
+        println!("{:?}", rows);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["id"], json!(4));
+        let columns = table.columns(&["id".to_string()], &[]).unwrap();
+        assert_eq!(columns[0], row(json!({"id": 2})));
+
+        let in_ids = [Condition::new(
+            "id".to_string(),
+            CompareType::IN,
+            json!([2, 3]),
+        )];
+        assert_eq!(table.deletes(&in_ids).unwrap(), 2);
+        assert!(table.delete(&json!(4)));
+        assert!(table.is_empty());
+        assert!(table
+            .count(&[Condition::new("x".to_string(), CompareType::EQ, json!(1))])
+            .is_err());
+    }
+}
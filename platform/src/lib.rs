@@ -4,6 +4,12 @@
 pub mod app;
 pub mod config;
 pub mod database;
+/// platform_ctrl is used to start and stop the whole platform.
+/// it starts app listener, resource listener and service manager together
+/// and stops all of them when platform is shutdown.
+pub mod platform_ctrl;
 pub mod pubsub;
 pub mod resource;
 pub mod service;
+/// web serves a dashboard and json api of platform state.
+pub mod web;
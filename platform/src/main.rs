@@ -1,11 +1,70 @@
+use std::path::PathBuf;
+use std::sync::mpsc;
+
+use clap::Parser;
+use env_logger::Builder;
+use log::{error, info};
+
+use platform::config::configuration::config_analyze;
+use platform::platform_ctrl;
+
+/// platform server
+/// start app listener, resource listener and service manager together
+#[derive(Parser, Debug)]
+#[command(name = "platform", version, about)]
+struct Args {
+    /// path of platform config file
+    #[arg(short, long, default_value = "platform/configfile")]
+    config: PathBuf,
+
+    /// log level filter, e.g. error, warn, info, debug, trace or platform=debug
+    #[arg(short, long, default_value = "info")]
+    log_level: String,
+}
+
 fn main() {
-    todo!()
+    let args = Args::parse();
+    Builder::new().parse_filters(&args.log_level).init();
+
+    if !args.config.is_file() {
+        error!("config file: {:?} not exists", args.config);
+        std::process::exit(1);
+    }
+    config_analyze(&args.config);
+
+    // shutdown on SIGINT and SIGTERM
+    let (tx, rx) = mpsc::channel();
+    ctrlc::set_handler(move || {
+        let _ = tx.send(());
+    })
+    .expect("set signal handler fail");
+
+    let handles = platform_ctrl::start();
+
+    rx.recv().expect("recv shutdown signal fail");
+    info!("receive shutdown signal");
+    platform_ctrl::shutdown();
+
+    for handle in handles {
+        if handle.join().is_err() {
+            error!("manager thread panicked while shutdown");
+        }
+    }
+    info!("platform exit");
 }
 
-//TODO:
-//1. res_mgr_thread 写一个服务器，提供资源管理的接口
-//2. resoure_driver 服务线程，先没有abstract_subscriber，只有一个actor_mgr，一个sensor_mgr
-//3. actor_mgr 服务线程，管理actor的生命周期
-//4. sensor_mgr 服务线程，管理sensor的生命周期
-//5. 测试一下，看看能不能跑起来
-//6. 写app部分，如上
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args_parse() {
+        let args = Args::parse_from(["platform"]);
+        assert_eq!(args.config, PathBuf::from("platform/configfile"));
+        assert_eq!(args.log_level, "info");
+
+        let args = Args::parse_from(["platform", "-c", "./configfile", "--log-level", "trace"]);
+        assert_eq!(args.config, PathBuf::from("./configfile"));
+        assert_eq!(args.log_level, "trace");
+    }
+}
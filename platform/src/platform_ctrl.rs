@@ -0,0 +1,103 @@
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::thread::JoinHandle;
+
+use log::{info, trace};
+use once_cell::sync::Lazy;
+
+use crate::app::app_mgr_thread::APP_MGR_THREAD;
+use crate::database::database;
+use crate::resource::res_mgr_thread::RES_MGR_THREAD;
+use crate::service::ser_mgr_thread::SER_MGR_THREAD;
+use crate::web;
+
+/// number of managers: app_mgr_thread, res_mgr_thread and ser_mgr_thread
+pub const MGR_NUM: u32 = 3;
+
+/// MGR_START_FLAG counts how many managers have started
+static MGR_START_FLAG: Lazy<(Mutex<u32>, Condvar)> = Lazy::new(|| (Mutex::new(0), Condvar::new()));
+
+/// increase mgr start flag
+/// used by a manager when it starts running
+pub fn incr_mgr_start_flag() {
+    let (lock, cvar) = &*MGR_START_FLAG;
+    let mut flag = lock.lock().expect("lock mgr start flag fail");
+    *flag += 1;
+    cvar.notify_all();
+}
+
+/// lock until mgr start flag equal
+/// block current thread until `num` managers have started
+pub fn lock_until_mgr_start_flag_equal(num: u32) {
+    let (lock, cvar) = &*MGR_START_FLAG;
+    let mut flag = lock.lock().expect("lock mgr start flag fail");
+    while *flag < num {
+        flag = cvar.wait(flag).expect("wait mgr start flag fail");
+    }
+}
+
+/// start
+/// config should be analyzed before start
+/// bind listeners in current thread so that bind errors are reported at once
+/// app databases are restored before apps can connect
+/// web server is started after managers if it is on
+/// return join handles of all managers and web server after all managers are running
+pub fn start() -> Vec<JoinHandle<()>> {
+    let restored = database::restore_databases();
+    info!("{} app databases restored", restored);
+    Lazy::force(&RES_MGR_THREAD);
+    Lazy::force(&APP_MGR_THREAD);
+    Lazy::force(&SER_MGR_THREAD);
+
+    let mut handles = vec![
+        thread::Builder::new()
+            .name("res_mgr_thread".to_string())
+            .spawn(|| RES_MGR_THREAD.run())
+            .expect("spawn res mgr thread fail"),
+        thread::Builder::new()
+            .name("app_mgr_thread".to_string())
+            .spawn(|| APP_MGR_THREAD.run())
+            .expect("spawn app mgr thread fail"),
+        thread::Builder::new()
+            .name("ser_mgr_thread".to_string())
+            .spawn(|| SER_MGR_THREAD.run())
+            .expect("spawn ser mgr thread fail"),
+    ];
+
+    lock_until_mgr_start_flag_equal(MGR_NUM);
+    handles.extend(web::start());
+    info!("platform start success");
+    handles
+}
+
+/// shutdown
+/// stop accepting new resources and apps, then stop service manager
+/// and compact logs of app databases
+pub fn shutdown() {
+    info!("platform shutdown...");
+    web::stop();
+    trace!("web server stopped");
+    RES_MGR_THREAD.stop();
+    trace!("res mgr thread stopped");
+    APP_MGR_THREAD.stop();
+    trace!("app mgr thread stopped");
+    SER_MGR_THREAD.stop();
+    trace!("ser mgr thread stopped");
+    database::snapshot_databases();
+    trace!("app databases snapshot");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mgr_start_flag() {
+        let handle = thread::spawn(|| {
+            lock_until_mgr_start_flag_equal(2);
+        });
+        incr_mgr_start_flag();
+        incr_mgr_start_flag();
+        handle.join().unwrap();
+    }
+}
@@ -6,11 +6,22 @@
 /// ## channel
 /// channel is a struct that stores subscribers.
 /// it manage subscribers by group and priority.
+/// in a group only the highest priority not above the published priority receives a message,
+/// unless its subscribers are interceptors, which inspect, rewrite, pass on or drop it
+/// before it reaches the priority below.
+/// a channel name with wildcard `*` is a pattern, e.g. `*<Sensor>` or `Yellow*<Sensor>`.
+/// subscribers of a pattern receive messages of every matching channel, including channels created later.
 /// ## grp_prio_pair
 /// grp_prio_pair is a struct that stores group id and priority id.
 /// ## publisher
 /// publisher is a module that provides publish function.
 /// one can use it to publish message to subscribers through channel.
+/// a message can be retained in channel with a timestamp,
+/// subscribers opting in by subscribe_with_retained receive it at once when they subscribe.
+/// ## dispatcher
+/// dispatcher delivers published messages with a bounded pool of workers.
+/// each subscriber has a mailbox, its messages are handled in publish order.
+/// queue depth of mailboxes can be read to find slow subscribers.
 /// ## subscriber
 /// subscriber is a module that provides subscribe trait
 /// trait provides on_message, id, get_grp_prio_pair, subscribe, unsubscribe and get_name.
@@ -19,6 +30,7 @@
 /// it is an abstract struct that can be used to implement concrete subscriber.
 pub mod abstract_subscriber;
 pub mod channel;
+pub mod dispatcher;
 pub mod grp_prio_pair;
 pub mod publisher;
 pub mod subscriber;
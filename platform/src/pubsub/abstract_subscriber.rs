@@ -6,28 +6,118 @@ use std::fmt::Display;
 use std::sync::{Arc, RwLock};
 
 use dashmap::DashMap;
+use log::warn;
 use once_cell::sync::Lazy;
 
 use common::socket::cmd_message_grp_ids::GroupId;
 
-use crate::pubsub::channel;
 use crate::pubsub::channel::ChannelName;
 use crate::pubsub::grp_prio_pair::{GrpPrioPair, PrioId};
 use crate::pubsub::subscriber::Subscriber;
+use crate::pubsub::{channel, dispatcher};
 
-pub type SubscriberId = i32;
 pub type SharedSubscriber = Arc<dyn Subscriber>;
 
+/// SubscriberId identifies a subscriber in SUBSCRIBER_OBJS.
+/// index of a deregistered subscriber is reused with the next generation,
+/// so a stale id never reaches the subscriber that reuses its index
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriberId {
+    pub index: u32,
+    pub generation: u32,
+}
+
+impl SubscriberId {
+    pub fn new(index: u32, generation: u32) -> Self {
+        Self { index, generation }
+    }
+}
+
+impl Display for SubscriberId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}#{}", self.index, self.generation)
+    }
+}
+
+struct Slot {
+    generation: u32,
+    subscriber: Option<SharedSubscriber>,
+}
+
+/// SubscriberObjs stores subscribers by index,
+/// indexes of deregistered subscribers are reused, so it does not grow with churn
+#[derive(Default)]
+pub struct SubscriberObjs {
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+}
+
+impl SubscriberObjs {
+    /// allocate an id, reuse a free index if there is one
+    fn alloc(&mut self) -> SubscriberId {
+        match self.free.pop() {
+            Some(index) => SubscriberId::new(index, self.slots[index as usize].generation),
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    subscriber: None,
+                });
+                SubscriberId::new((self.slots.len() - 1) as u32, 0)
+            }
+        }
+    }
+
+    fn insert(&mut self, id: SubscriberId, subscriber: SharedSubscriber) {
+        self.slots[id.index as usize].subscriber = Some(subscriber);
+    }
+
+    /// remove subscriber of id and free its index
+    /// return none if id is stale
+    fn remove(&mut self, id: SubscriberId) -> Option<SharedSubscriber> {
+        let slot = self
+            .slots
+            .get_mut(id.index as usize)
+            .filter(|slot| slot.generation == id.generation)?;
+        let subscriber = slot.subscriber.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(id.index);
+        Some(subscriber)
+    }
+
+    /// get subscriber of id
+    /// return none if id is stale
+    fn get(&self, id: SubscriberId) -> Option<SharedSubscriber> {
+        self.slots
+            .get(id.index as usize)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.subscriber.clone())
+    }
+
+    /// number of registered subscribers
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// number of slots, registered or free
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+}
+
 /// SUBSCRIBER_OBJS is a static variable that stores all subscribers.
-/// It is a vector of Arc<dyn Subscriber>.
-/// outside can use index to get subscriber.
-static SUBSCRIBER_OBJS: Lazy<RwLock<Vec<SharedSubscriber>>> = Lazy::new(|| RwLock::new(Vec::new()));
+/// outside can use id to get subscriber.
+static SUBSCRIBER_OBJS: Lazy<RwLock<SubscriberObjs>> =
+    Lazy::new(|| RwLock::new(SubscriberObjs::default()));
 
 #[derive(Debug)]
 pub struct AbstractSubscriber {
     ///channels stores all channels that this subscriber subscribes to.
     channels: DashMap<ChannelName, GrpPrioPair>,
-    id: i32,
+    id: SubscriberId,
 }
 
 ///display for AbstractSubscriber
@@ -43,7 +133,7 @@ impl Subscriber for AbstractSubscriber {
         self
     }
 
-    fn id(&self) -> i32 {
+    fn id(&self) -> SubscriberId {
         self.id
     }
 
@@ -60,16 +150,67 @@ impl Subscriber for AbstractSubscriber {
         self.channels.insert(channel.to_string(), grp_prio_pair_now);
     }
 
+    /// retained messages go to mailbox before subscribing,
+    /// so that they are never handled after a newer message of the channel
+    /// retained messages are delivered whatever group they were published to
+    fn subscribe_with_retained(
+        &self,
+        channel: &str,
+        group_id: Option<GroupId>,
+        priority_id: Option<PrioId>,
+    ) {
+        // subscriber out of SUBSCRIBER_OBJS has no mailbox
+        if let Some(subscriber) = get_subscriber(self.id()) {
+            for (channel_name, retained) in channel::get_retained_messages(channel) {
+                dispatcher::dispatch(subscriber.clone(), Arc::new(channel_name), retained.msg);
+            }
+        }
+        self.subscribe(channel, group_id, priority_id);
+    }
+
+    fn subscribe_as_interceptor(
+        &self,
+        channel: &str,
+        group_id: Option<GroupId>,
+        priority_id: Option<PrioId>,
+    ) -> bool {
+        match channel::get_channel(channel).add_interceptor(self.id(), group_id, priority_id) {
+            Some(grp_prio_pair_now) => {
+                self.channels.insert(channel.to_string(), grp_prio_pair_now);
+                true
+            }
+            None => {
+                warn!(
+                    "{} can not intercept {}: another interceptor is at its priority",
+                    self.id(),
+                    channel
+                );
+                false
+            }
+        }
+    }
+
     fn unsubscribe(&self, channel: &str) {
         self.channels.remove(channel);
         channel::get_channel(channel).remove_subscriber(self.id());
     }
+
+    fn unsubscribe_all(&self) {
+        let channels: Vec<ChannelName> = self
+            .channels
+            .iter()
+            .map(|channel| channel.key().clone())
+            .collect();
+        for channel in channels {
+            self.unsubscribe(&channel);
+        }
+    }
 }
 
 ///impl AbstractSubscriber
 impl AbstractSubscriber {
     ///used for add subscriber
-    pub fn new(id: i32) -> Self {
+    pub fn new(id: SubscriberId) -> Self {
         Self {
             channels: DashMap::new(),
             id,
@@ -78,21 +219,56 @@ impl AbstractSubscriber {
 
     ///used outside
     pub fn add_to_subscriber_objs() -> Arc<Self> {
-        let mut subscriber_objs = SUBSCRIBER_OBJS.write().expect("get subscriber objs failed");
-        let subscriber = Arc::new(Self::new(subscriber_objs.len() as i32));
-        subscriber_objs.push(subscriber.clone());
-        subscriber
+        register(Self::new)
     }
 }
 
-///static function
-pub fn get_objs() -> &'static RwLock<Vec<SharedSubscriber>> {
+//static function
+
+/// register
+/// new a subscriber with a new id and store it in SUBSCRIBER_OBJS
+pub fn register<T, F>(new: F) -> Arc<T>
+where
+    T: Subscriber + 'static,
+    F: FnOnce(SubscriberId) -> T,
+{
+    let mut subscriber_objs = SUBSCRIBER_OBJS.write().expect("get subscriber objs failed");
+    let id = subscriber_objs.alloc();
+    let subscriber = Arc::new(new(id));
+    subscriber_objs.insert(id, subscriber.clone());
+    subscriber
+}
+
+/// deregister
+/// remove subscriber from SUBSCRIBER_OBJS, every channel it joined and dispatcher
+/// return false if id is stale
+pub fn deregister(id: SubscriberId) -> bool {
+    let subscriber = SUBSCRIBER_OBJS
+        .write()
+        .expect("get subscriber objs failed")
+        .remove(id);
+    match subscriber {
+        Some(subscriber) => {
+            subscriber.unsubscribe_all();
+            dispatcher::remove(id);
+            true
+        }
+        None => false,
+    }
+}
+
+/// get subscriber objs, e.g. to read how many subscribers are registered
+pub fn get_objs() -> &'static RwLock<SubscriberObjs> {
     &SUBSCRIBER_OBJS
 }
 
-pub fn get_subscriber(id: i32) -> Option<SharedSubscriber> {
-    let subscriber_objs = SUBSCRIBER_OBJS.read().expect("get subscriber objs failed");
-    subscriber_objs.get(id as usize).map(|x| x.clone())
+/// get subscriber
+/// return none if id is stale
+pub fn get_subscriber(id: SubscriberId) -> Option<SharedSubscriber> {
+    SUBSCRIBER_OBJS
+        .read()
+        .expect("get subscriber objs failed")
+        .get(id)
 }
 
 #[cfg(test)]
@@ -101,8 +277,8 @@ mod tests {
 
     #[test]
     fn test_abstract_subscriber() {
-        let subscriber = AbstractSubscriber::new(0);
-        assert_eq!(subscriber.id(), 0);
+        let subscriber = AbstractSubscriber::new(SubscriberId::new(0, 0));
+        assert_eq!(subscriber.id(), SubscriberId::new(0, 0));
         //assert_eq!(subscriber.to_string(), "pubsub::abstract_subscriber::AbstractSubscriber");
         assert_eq!(subscriber.get_grp_prio_pair("test"), None);
         subscriber.subscribe("test", None, None);
@@ -113,4 +289,51 @@ mod tests {
         subscriber.unsubscribe("test");
         assert_eq!(subscriber.get_grp_prio_pair("test"), None);
     }
+
+    #[test]
+    fn test_unsubscribe_all() {
+        let subscriber = AbstractSubscriber::new(SubscriberId::new(0, 0));
+        subscriber.subscribe("test_unsubscribe_all_1", Some(1), None);
+        subscriber.subscribe("test_unsubscribe_all_2", Some(1), None);
+        subscriber.unsubscribe_all();
+        for channel in ["test_unsubscribe_all_1", "test_unsubscribe_all_2"] {
+            println!("{}", channel);
+            assert_eq!(subscriber.get_grp_prio_pair(channel), None);
+            assert_eq!(channel::get_grp_prio_pair(channel, subscriber.id()), None);
+        }
+    }
+
+    #[test]
+    fn test_subscriber_objs() {
+        let mut subscriber_objs = SubscriberObjs::default();
+        for _ in 0..100 {
+            let id = subscriber_objs.alloc();
+            let subscriber: SharedSubscriber = Arc::new(AbstractSubscriber::new(id));
+            subscriber_objs.insert(id, subscriber);
+            assert!(subscriber_objs.get(id).is_some());
+            assert!(subscriber_objs.remove(id).is_some());
+            // stale id is rejected
+            assert!(subscriber_objs.get(id).is_none());
+            assert!(subscriber_objs.remove(id).is_none());
+        }
+        println!("capacity: {}", subscriber_objs.capacity());
+        assert_eq!(subscriber_objs.capacity(), 1);
+        assert!(subscriber_objs.is_empty());
+    }
+
+    #[test]
+    fn test_register_and_deregister() {
+        let subscriber = register(AbstractSubscriber::new);
+        let id = subscriber.id();
+        println!("{}", id);
+        subscriber.subscribe("test_register_and_deregister", None, None);
+        assert!(get_subscriber(id).is_some());
+        assert!(deregister(id));
+        assert!(get_subscriber(id).is_none());
+        assert!(!deregister(id));
+        assert_eq!(
+            channel::get_grp_prio_pair("test_register_and_deregister", id),
+            None
+        );
+    }
 }
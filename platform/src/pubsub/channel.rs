@@ -3,12 +3,15 @@
 // so inside we do not modify it until we have a better idea
 
 use std::fmt::Display;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, SystemTime};
 
 use dashmap::mapref::one::Ref;
 use dashmap::{DashMap, DashSet};
 use once_cell::sync::Lazy;
 
 use common::socket::cmd_message_grp_ids::GroupId;
+use common::SyncString;
 
 use crate::pubsub::abstract_subscriber::SubscriberId;
 use crate::pubsub::grp_prio_pair::{GrpPrioPair, PrioId};
@@ -24,34 +27,106 @@ use crate::pubsub::grp_prio_pair::{GrpPrioPair, PrioId};
 pub type SubscriberSet = DashSet<SubscriberId>;
 pub type ChannelName = String;
 
+/// RetainedMessage is the last message retained by a channel and when it was retained
+#[derive(Debug, Clone)]
+pub struct RetainedMessage {
+    pub msg: SyncString,
+    pub timestamp: SystemTime,
+}
+
+impl RetainedMessage {
+    pub fn new(msg: SyncString) -> Self {
+        Self {
+            msg,
+            timestamp: SystemTime::now(),
+        }
+    }
+
+    /// age of message, zero if clock goes back
+    pub fn get_age(&self) -> Duration {
+        self.timestamp.elapsed().unwrap_or(Duration::ZERO)
+    }
+}
+
 pub struct Channel {
     subscribers: DashMap<GroupId, DashMap<PrioId, SubscriberSet>>,
     channel_base_name: ChannelName,
+    /// patterns matching this channel, always empty for a pattern
+    patterns: DashSet<ChannelName>,
+    /// subscribers that intercept messages before lower priorities of their group
+    interceptors: DashSet<SubscriberId>,
+    /// interceptors are added one at a time, so that a priority never holds two of them
+    interceptor_lock: Mutex<()>,
+    /// last message retained by publisher, none if nothing is retained
+    retained: RwLock<Option<RetainedMessage>>,
 }
 
 /// static
 static CHANNEL_OBJS: Lazy<DashMap<ChannelName, Channel>> = Lazy::new(|| DashMap::new());
+/// channels of patterns, kept apart so that exact channels are found by one lookup
+static PATTERN_OBJS: Lazy<DashMap<ChannelName, Channel>> = Lazy::new(DashMap::new);
 
 pub static DEFAULT_GRP_ID: i32 = 0;
 pub static DEFAULT_PRIO_ID: i32 = 0;
+/// group of monitors, e.g. sensor streams of web server
+/// sensor data published to any group is published to it too,
+/// group ids of apps start from 1 so it never meets an app
+pub static MONITOR_GRP_ID: i32 = -1;
 
 /// const
 pub const SENSOR_SUFFIX: &str = "<Sensor>";
 pub const ACTOR_SUFFIX: &str = "<Actor>";
 pub const SENSOR_REQUEST_SUFFIX: &str = "<Sensor_Request>";
 pub const ACTOR_REQUEST_SUFFIX: &str = "<Actor_Request>";
+/// wildcard of pattern, matches any characters, e.g. *<Sensor> or Yellow*<Sensor>
+pub const WILDCARD: char = '*';
 
 impl Channel {
     /// public function
     /// should not be used out of pubsub module
 
+    /// a channel with a wildcard is a pattern,
+    /// its subscribers receive messages of every channel it matches, created before or after it
     pub fn new(channel_base_name: ChannelName) -> Option<Ref<'static, ChannelName, Channel>> {
-        let channel = Self {
-            subscribers: DashMap::new(),
-            channel_base_name: channel_base_name.clone(),
+        let is_pattern = is_pattern(&channel_base_name);
+        let objs = if is_pattern {
+            &PATTERN_OBJS
+        } else {
+            &CHANNEL_OBJS
         };
-        CHANNEL_OBJS.insert(channel_base_name.clone(), channel);
-        CHANNEL_OBJS.get(&channel_base_name)
+        // a channel created by another thread meanwhile is kept
+        objs.entry(channel_base_name.clone())
+            .or_insert_with(|| Self {
+                subscribers: DashMap::new(),
+                channel_base_name: channel_base_name.clone(),
+                patterns: DashSet::new(),
+                interceptors: DashSet::new(),
+                interceptor_lock: Mutex::new(()),
+                retained: RwLock::new(None),
+            });
+
+        // both sides link after they are inserted, so that a channel and a pattern
+        // created at the same time still find each other.
+        // no ref of one map is held while the other is read, to avoid deadlock
+        if is_pattern {
+            for channel in CHANNEL_OBJS.iter() {
+                if matches_pattern(&channel_base_name, channel.key()) {
+                    channel.patterns.insert(channel_base_name.clone());
+                }
+            }
+        } else {
+            let patterns: Vec<ChannelName> = PATTERN_OBJS
+                .iter()
+                .filter(|pattern| matches_pattern(pattern.key(), &channel_base_name))
+                .map(|pattern| pattern.key().clone())
+                .collect();
+            if let Some(channel) = CHANNEL_OBJS.get(&channel_base_name) {
+                for pattern in patterns {
+                    channel.patterns.insert(pattern);
+                }
+            }
+        }
+        objs.get(&channel_base_name)
     }
 
     ///getter
@@ -63,6 +138,34 @@ impl Channel {
         &self.subscribers
     }
 
+    /// get patterns matching this channel
+    pub fn get_patterns(&self) -> Vec<ChannelName> {
+        self.patterns
+            .iter()
+            .map(|pattern| pattern.key().clone())
+            .collect()
+    }
+
+    /// whether any pattern matches this channel
+    pub fn has_patterns(&self) -> bool {
+        !self.patterns.is_empty()
+    }
+
+    /// retain message, it replaces the message retained before
+    pub fn retain(&self, msg: SyncString) {
+        *self.retained.write().expect("write retained fail") = Some(RetainedMessage::new(msg));
+    }
+
+    /// get retained message
+    /// if max_age is specified, message older than it is not returned
+    pub fn get_retained(&self, max_age: Option<Duration>) -> Option<RetainedMessage> {
+        self.retained
+            .read()
+            .expect("read retained fail")
+            .clone()
+            .filter(|retained| max_age.is_none_or(|max_age| retained.get_age() <= max_age))
+    }
+
     pub fn get_group_subscribers(
         &self,
         group_id: i32,
@@ -82,8 +185,25 @@ impl Channel {
         None
     }
 
+    /// the highest priority below prio_id in group that has subscribers, none if there is none
+    pub fn get_prio_below(&self, group_id: GroupId, prio_id: PrioId) -> Option<PrioId> {
+        let grp = self.subscribers.get(&group_id)?;
+        let prio_below = grp
+            .iter()
+            .filter(|prio| *prio.key() < prio_id && !prio.value().is_empty())
+            .map(|prio| *prio.key())
+            .max();
+        prio_below
+    }
+
+    /// whether subscriber intercepts messages of this channel
+    pub fn is_interceptor(&self, subscriber: SubscriberId) -> bool {
+        self.interceptors.contains(&subscriber)
+    }
+
     /// remove subscriber
     pub fn remove_subscriber(&self, subscriber: SubscriberId) {
+        self.interceptors.remove(&subscriber);
         let grp_prio_pair_now = self.get_grp_prio_pair(subscriber);
         if let Some(grp_prio_pair_now) = grp_prio_pair_now {
             let group_id = grp_prio_pair_now.grp_id;
@@ -146,10 +266,51 @@ impl Channel {
         }
         let prio = group.get(&prio_id).expect("get prio failed");
         prio.insert(subscriber);
+        // subscribing again leaves interception mode
+        self.interceptors.remove(&subscriber);
 
         GrpPrioPair::new(group_id, prio_id)
     }
 
+    /// interceptor at priority of group, none if there is none
+    pub fn get_interceptor(&self, group_id: GroupId, prio_id: PrioId) -> Option<SubscriberId> {
+        let grp = self.subscribers.get(&group_id)?;
+        let prio = grp.get(&prio_id)?;
+        let interceptor = prio
+            .iter()
+            .map(|subscriber| *subscriber)
+            .find(|subscriber| self.interceptors.contains(subscriber));
+        interceptor
+    }
+
+    /// add interceptor
+    /// interceptor is added like a subscriber, but messages it receives are passed on
+    /// to lower priorities of its group after it intercepts them, see Subscriber::intercept
+    /// a priority holds one interceptor at most, so that interceptors of a group run in a fixed order
+    /// and lower priorities receive a message once
+    /// return none if another interceptor is at the priority
+    pub fn add_interceptor(
+        &self,
+        subscriber: SubscriberId,
+        group_id: Option<GroupId>,
+        prio_id: Option<PrioId>,
+    ) -> Option<GrpPrioPair> {
+        let _guard = self.interceptor_lock.lock().expect("lock interceptor fail");
+        // a new group has no interceptor yet
+        if let Some(group_id) = group_id {
+            let prio_id = prio_id.unwrap_or(DEFAULT_PRIO_ID);
+            if self
+                .get_interceptor(group_id, prio_id)
+                .is_some_and(|interceptor| interceptor != subscriber)
+            {
+                return None;
+            }
+        }
+        let grp_prio_pair = self.add_subscriber(subscriber, group_id, prio_id);
+        self.interceptors.insert(subscriber);
+        Some(grp_prio_pair)
+    }
+
     /// private function
 
     /// generate new group id
@@ -184,14 +345,52 @@ impl Display for Channel {
 // so its efficiency is not important
 
 /// get channel by channel base name
+/// a name with a wildcard gets a pattern
 /// if channel not exist, create it
 pub fn get_channel(channel_base_name: &str) -> Ref<'static, String, Channel> {
-    match CHANNEL_OBJS.get(channel_base_name) {
+    let objs = if is_pattern(channel_base_name) {
+        &PATTERN_OBJS
+    } else {
+        &CHANNEL_OBJS
+    };
+    match objs.get(channel_base_name) {
         Some(channel) => channel,
         None => Channel::new(channel_base_name.to_string()).expect("create channel failed"),
     }
 }
 
+/// whether channel name is a pattern
+pub fn is_pattern(channel_name: &str) -> bool {
+    channel_name.contains(WILDCARD)
+}
+
+/// match channel name with pattern
+/// wildcard matches any characters, including none
+pub fn matches_pattern(pattern: &str, channel_name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = channel_name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // position of last wildcard in pattern and of name it matches up to
+    let mut backtrack: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && pattern[p] == WILDCARD {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == name[n] {
+            p += 1;
+            n += 1;
+        } else if let Some((wildcard, matched)) = backtrack {
+            // let the last wildcard match one more character
+            p = wildcard + 1;
+            n = matched + 1;
+            backtrack = Some((wildcard, n));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == WILDCARD)
+}
+
 /// get channel name with suffix
 fn get_channel_name_with_suffix(base_name: &str, suffix: &str) -> ChannelName {
     let channel_name = format!("{}{}", base_name, suffix);
@@ -223,6 +422,30 @@ pub fn get_objs() -> &'static DashMap<ChannelName, Channel> {
     &CHANNEL_OBJS
 }
 
+/// get retained messages
+/// a channel gives its own retained message, a pattern gives that of every channel it matches.
+/// a channel is not created if it does not exist
+pub fn get_retained_messages(channel: &str) -> Vec<(ChannelName, RetainedMessage)> {
+    let retained = |channel: &Channel| {
+        channel
+            .get_retained(None)
+            .map(|retained| (channel.get_name().to_string(), retained))
+    };
+    if is_pattern(channel) {
+        CHANNEL_OBJS
+            .iter()
+            .filter(|matched| matches_pattern(channel, matched.key()))
+            .filter_map(|matched| retained(&matched))
+            .collect()
+    } else {
+        CHANNEL_OBJS
+            .get(channel)
+            .and_then(|channel| retained(&channel))
+            .into_iter()
+            .collect()
+    }
+}
+
 ///get grp id and prio id
 pub fn get_grp_prio_pair(channel: &str, subscriber: SubscriberId) -> Option<GrpPrioPair> {
     get_channel(channel).get_grp_prio_pair(subscriber)
@@ -237,11 +460,65 @@ pub fn get_grp_prio_pair_with_channel(
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::pubsub::abstract_subscriber::SubscriberId;
     use crate::pubsub::channel::{
         get_actor, get_actor_request, get_channel, get_grp_prio_pair,
-        get_grp_prio_pair_with_channel, get_sensor, get_sensor_request,
+        get_grp_prio_pair_with_channel, get_objs, get_retained_messages, get_sensor,
+        get_sensor_request, matches_pattern,
     };
 
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("*<Sensor>", "GreenCar<Sensor>"));
+        assert!(matches_pattern("Yellow*<Sensor>", "YellowCar<Sensor>"));
+        assert!(matches_pattern("Yellow*", "Yellow"));
+        assert!(matches_pattern("*Car*<Sensor>", "YellowCar<Sensor>"));
+        assert!(!matches_pattern("*<Sensor>", "YellowCar<Sensor_Request>"));
+        assert!(!matches_pattern("Yellow*<Sensor>", "GreenCar<Sensor>"));
+        assert!(!matches_pattern("Yellow", "YellowCar"));
+    }
+
+    #[test]
+    fn test_pattern() {
+        let before = get_sensor("test_pattern_car_before");
+        let pattern = get_channel("test_pattern_car_*<Sensor>")
+            .get_name()
+            .to_string();
+        let after = get_sensor("test_pattern_car_after");
+        let other = get_actor("test_pattern_car_other");
+        for channel in [&before, &after] {
+            println!("{}: {:?}", channel, get_channel(channel).get_patterns());
+            assert_eq!(get_channel(channel).get_patterns(), vec![pattern.clone()]);
+        }
+        assert!(!get_channel(&other).has_patterns());
+        assert!(get_objs().get(&pattern).is_none());
+    }
+
+    #[test]
+    fn test_retained() {
+        let channel = get_sensor("test_retained_car");
+        assert!(get_retained_messages(&channel).is_empty());
+        get_channel(&channel).retain(Arc::new("1".to_string()));
+        get_channel(&channel).retain(Arc::new("2".to_string()));
+        let retained = get_channel(&channel).get_retained(None).unwrap();
+        println!("{:?}, age {:?}", retained, retained.get_age());
+        assert_eq!(retained.msg.as_str(), "2");
+        assert!(get_channel(&channel)
+            .get_retained(Some(Duration::from_secs(60)))
+            .is_some());
+        thread::sleep(Duration::from_millis(20));
+        assert!(get_channel(&channel)
+            .get_retained(Some(Duration::from_millis(10)))
+            .is_none());
+        let retained = get_retained_messages("test_retained_*<Sensor>");
+        assert_eq!(retained.len(), 1);
+        assert_eq!(retained[0].0, channel);
+    }
+
     #[test]
     fn test_channel() {
         let channel = get_channel("test_channel");
@@ -275,8 +552,9 @@ mod tests {
     #[test]
     fn test_get_grp_prio_pair() {
         let channel = get_channel("test_get_grp_prio_pair");
-        let grp_prio_pair = channel.add_subscriber(1, None, None);
-        let grp_prio_pair_now = get_grp_prio_pair("test_get_grp_prio_pair", 1).unwrap();
+        let subscriber = SubscriberId::new(1, 0);
+        let grp_prio_pair = channel.add_subscriber(subscriber, None, None);
+        let grp_prio_pair_now = get_grp_prio_pair("test_get_grp_prio_pair", subscriber).unwrap();
         println!("{}", grp_prio_pair);
         assert_eq!(grp_prio_pair, grp_prio_pair_now);
     }
@@ -284,9 +562,34 @@ mod tests {
     #[test]
     fn test_get_grp_prio_pair_with_channel() {
         let channel = get_channel("test_get_grp_prio_pair_with_channel");
-        let grp_prio_pair = channel.add_subscriber(1, None, None);
-        let grp_prio_pair_now = get_grp_prio_pair_with_channel(&channel, 1).unwrap();
+        let subscriber = SubscriberId::new(1, 0);
+        let grp_prio_pair = channel.add_subscriber(subscriber, None, None);
+        let grp_prio_pair_now = get_grp_prio_pair_with_channel(&channel, subscriber).unwrap();
         println!("{}", grp_prio_pair);
         assert_eq!(grp_prio_pair, grp_prio_pair_now);
     }
+
+    #[test]
+    fn test_add_interceptor() {
+        let channel = get_channel("test_add_interceptor");
+        let interceptor = SubscriberId::new(1, 0);
+        let grp_prio_pair = channel.add_interceptor(interceptor, None, None).unwrap();
+        let (grp_id, priority_id) = (grp_prio_pair.grp_id, grp_prio_pair.priority_id);
+        assert_eq!(
+            channel.get_interceptor(grp_id, priority_id),
+            Some(interceptor)
+        );
+        // a priority holds one interceptor, subscribing again is allowed
+        let other = SubscriberId::new(2, 0);
+        assert!(channel
+            .add_interceptor(other, Some(grp_id), Some(priority_id))
+            .is_none());
+        assert!(channel.get_grp_prio_pair(other).is_none());
+        assert!(channel
+            .add_interceptor(interceptor, Some(grp_id), Some(priority_id))
+            .is_some());
+        assert!(channel
+            .add_interceptor(other, Some(grp_id), Some(priority_id + 1))
+            .is_some());
+    }
 }
@@ -0,0 +1,466 @@
+use std::collections::VecDeque;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+use dashmap::DashMap;
+use log::{error, trace, warn};
+use once_cell::sync::Lazy;
+
+use common::SyncString;
+
+use crate::pubsub::abstract_subscriber::{SharedSubscriber, SubscriberId};
+use crate::pubsub::grp_prio_pair::GrpPrioPair;
+use crate::pubsub::publisher;
+use crate::pubsub::subscriber::Interception;
+
+/// messages a mailbox keeps at most, the oldest message is dropped when it is full
+/// a subscriber waiting on io, e.g. a resource driver, should hand messages off instead of blocking
+pub const MAILBOX_CAPACITY: usize = 1024;
+/// workers of dispatcher at least
+pub const MIN_WORKERS: usize = 4;
+
+pub type SyncDispatcher = Arc<Dispatcher>;
+/// channel, message and where subscriber intercepts it, none if it is not an interceptor
+type Message = (SyncString, SyncString, Option<GrpPrioPair>);
+
+/// static
+static DISPATCHER: Lazy<SyncDispatcher> = Lazy::new(|| {
+    let workers = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(MIN_WORKERS)
+        .max(MIN_WORKERS);
+    Dispatcher::new_with_workers(workers, MAILBOX_CAPACITY)
+});
+
+struct MailboxQueue {
+    messages: VecDeque<Message>,
+    /// mailbox is in ready queue or handled by a worker
+    scheduled: bool,
+}
+
+/// Mailbox keeps messages of one subscriber in publish order
+struct Mailbox {
+    subscriber: SharedSubscriber,
+    queue: Mutex<MailboxQueue>,
+    dropped: AtomicU64,
+}
+
+impl Mailbox {
+    fn new(subscriber: SharedSubscriber) -> Self {
+        Self {
+            subscriber,
+            queue: Mutex::new(MailboxQueue {
+                messages: VecDeque::new(),
+                scheduled: false,
+            }),
+            dropped: AtomicU64::new(0),
+        }
+    }
+}
+
+/// Dispatcher delivers published messages with a bounded pool of workers
+/// a mailbox is handled by one worker at a time, so messages of a subscriber are handled in order,
+/// and a slow subscriber holds one worker at most
+pub struct Dispatcher {
+    mailboxes: DashMap<SubscriberId, Arc<Mailbox>>,
+    ready: Mutex<VecDeque<Arc<Mailbox>>>,
+    condvar: Condvar,
+    capacity: usize,
+}
+
+impl Dispatcher {
+    /// new a dispatcher and start its workers
+    pub fn new_with_workers(workers: usize, capacity: usize) -> SyncDispatcher {
+        let dispatcher = Arc::new(Self {
+            mailboxes: DashMap::new(),
+            ready: Mutex::new(VecDeque::new()),
+            condvar: Condvar::new(),
+            capacity,
+        });
+        for i in 0..workers {
+            let dispatcher = dispatcher.clone();
+            thread::Builder::new()
+                .name(format!("pubsub worker {}", i))
+                .spawn(move || dispatcher.work())
+                .expect("spawn pubsub worker fail");
+        }
+        dispatcher
+    }
+
+    /// dispatch a message to mailbox of subscriber
+    pub fn dispatch(&self, subscriber: SharedSubscriber, channel: SyncString, msg: SyncString) {
+        self.push(subscriber, (channel, msg, None));
+    }
+
+    /// dispatch a message to mailbox of interceptor,
+    /// what it passes on is published below grp_prio_pair after it is handled
+    pub fn intercept(
+        &self,
+        subscriber: SharedSubscriber,
+        channel: SyncString,
+        msg: SyncString,
+        grp_prio_pair: GrpPrioPair,
+    ) {
+        self.push(subscriber, (channel, msg, Some(grp_prio_pair)));
+    }
+
+    fn push(&self, subscriber: SharedSubscriber, message: Message) {
+        let mailbox = self
+            .mailboxes
+            .entry(subscriber.id())
+            .or_insert_with(|| Arc::new(Mailbox::new(subscriber)))
+            .clone();
+        let schedule = {
+            let mut queue = mailbox.queue.lock().expect("lock mailbox fail");
+            if queue.messages.len() >= self.capacity {
+                if let Some((channel, msg, _)) = queue.messages.pop_front() {
+                    let dropped = mailbox.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                    // every drop is counted, log is thinned out to 1, 2, 4, 8... drops
+                    if dropped.is_power_of_two() {
+                        warn!(
+                            "mailbox of {} is full, drop message {} of {} ({} dropped)",
+                            mailbox.subscriber.get_name(),
+                            msg,
+                            channel,
+                            dropped
+                        );
+                    } else {
+                        trace!(
+                            "mailbox of {} is full, drop message {} of {}",
+                            mailbox.subscriber.get_name(),
+                            msg,
+                            channel
+                        );
+                    }
+                }
+            }
+            queue.messages.push_back(message);
+            !std::mem::replace(&mut queue.scheduled, true)
+        };
+        if schedule {
+            self.schedule(mailbox);
+        }
+    }
+
+    /// messages waiting in mailbox of subscriber
+    pub fn queue_depth(&self, subscriber: SubscriberId) -> usize {
+        self.mailboxes
+            .get(&subscriber)
+            .map(|mailbox| {
+                mailbox
+                    .queue
+                    .lock()
+                    .expect("lock mailbox fail")
+                    .messages
+                    .len()
+            })
+            .unwrap_or(0)
+    }
+
+    /// messages waiting in all mailboxes, keyed by subscriber
+    pub fn queue_depths(&self) -> Vec<(SubscriberId, usize)> {
+        self.mailboxes
+            .iter()
+            .map(|mailbox| {
+                let depth = mailbox
+                    .queue
+                    .lock()
+                    .expect("lock mailbox fail")
+                    .messages
+                    .len();
+                (*mailbox.key(), depth)
+            })
+            .collect()
+    }
+
+    /// messages of subscriber dropped since its mailbox was full
+    pub fn dropped(&self, subscriber: SubscriberId) -> u64 {
+        self.mailboxes
+            .get(&subscriber)
+            .map(|mailbox| mailbox.dropped.load(Ordering::Relaxed))
+            .unwrap_or(0)
+    }
+
+    /// remove mailbox of subscriber, messages waiting in it are dropped
+    pub fn remove(&self, subscriber: SubscriberId) {
+        if let Some((_, mailbox)) = self.mailboxes.remove(&subscriber) {
+            mailbox
+                .queue
+                .lock()
+                .expect("lock mailbox fail")
+                .messages
+                .clear();
+        }
+    }
+
+    /// a panic of subscriber must not kill worker,
+    /// a panicking interceptor drops message, so that a message it fails to check is not passed on
+    fn handle(subscriber: &SharedSubscriber, (channel, msg, grp_prio_pair): Message) {
+        let grp_prio_pair = match grp_prio_pair {
+            Some(grp_prio_pair) => grp_prio_pair,
+            None => {
+                let on_message = AssertUnwindSafe(|| subscriber.on_message(channel, msg));
+                if catch_unwind(on_message).is_err() {
+                    error!("{} panics on message", subscriber.get_name());
+                }
+                return;
+            }
+        };
+        let intercept = AssertUnwindSafe(|| subscriber.intercept(channel.clone(), msg.clone()));
+        let interception = catch_unwind(intercept).unwrap_or_else(|_| {
+            error!(
+                "{} panics on intercepting message, drop message {} of {}",
+                subscriber.get_name(),
+                msg,
+                channel
+            );
+            Interception::Drop
+        });
+        match interception {
+            Interception::Pass => publisher::pass_on(&channel, grp_prio_pair, msg),
+            Interception::Rewrite(msg) => publisher::pass_on(&channel, grp_prio_pair, msg),
+            Interception::Drop => trace!(
+                "{} drops message of {} at {}",
+                subscriber.get_name(),
+                channel,
+                grp_prio_pair
+            ),
+        }
+    }
+
+    fn schedule(&self, mailbox: Arc<Mailbox>) {
+        self.ready
+            .lock()
+            .expect("lock ready queue fail")
+            .push_back(mailbox);
+        self.condvar.notify_one();
+    }
+
+    /// worker handles one message of a mailbox each turn,
+    /// then mailbox goes to the back of ready queue if it is not empty
+    fn work(&self) {
+        loop {
+            let mailbox = {
+                let mut ready = self.ready.lock().expect("lock ready queue fail");
+                loop {
+                    match ready.pop_front() {
+                        Some(mailbox) => break mailbox,
+                        None => ready = self.condvar.wait(ready).expect("wait ready queue fail"),
+                    }
+                }
+            };
+            let message = mailbox
+                .queue
+                .lock()
+                .expect("lock mailbox fail")
+                .messages
+                .pop_front();
+            if let Some(message) = message {
+                Self::handle(&mailbox.subscriber, message);
+            }
+            let reschedule = {
+                let mut queue = mailbox.queue.lock().expect("lock mailbox fail");
+                queue.scheduled = !queue.messages.is_empty();
+                queue.scheduled
+            };
+            if reschedule {
+                self.schedule(mailbox);
+            }
+        }
+    }
+}
+
+//below is static function
+
+/// dispatch a message by static dispatcher
+pub fn dispatch(subscriber: SharedSubscriber, channel: SyncString, msg: SyncString) {
+    DISPATCHER.dispatch(subscriber, channel, msg);
+}
+
+/// dispatch a message to interceptor by static dispatcher
+pub fn intercept(
+    subscriber: SharedSubscriber,
+    channel: SyncString,
+    msg: SyncString,
+    grp_prio_pair: GrpPrioPair,
+) {
+    DISPATCHER.intercept(subscriber, channel, msg, grp_prio_pair);
+}
+
+/// remove mailbox of subscriber in static dispatcher
+pub fn remove(subscriber: SubscriberId) {
+    DISPATCHER.remove(subscriber);
+}
+
+/// queue depth of subscriber in static dispatcher
+pub fn queue_depth(subscriber: SubscriberId) -> usize {
+    DISPATCHER.queue_depth(subscriber)
+}
+
+/// queue depths of all subscribers in static dispatcher
+pub fn queue_depths() -> Vec<(SubscriberId, usize)> {
+    DISPATCHER.queue_depths()
+}
+
+/// dropped messages of subscriber in static dispatcher
+pub fn dropped(subscriber: SubscriberId) -> u64 {
+    DISPATCHER.dropped(subscriber)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Display;
+    use std::time::{Duration, Instant};
+
+    use crate::pubsub::abstract_subscriber::{self, AbstractSubscriber};
+    use crate::pubsub::channel;
+    use crate::pubsub::subscriber::Subscriber;
+
+    use super::*;
+
+    struct RecordSubscriber {
+        abstract_subscriber: AbstractSubscriber,
+        delay: Duration,
+        received: Mutex<Vec<String>>,
+    }
+
+    impl RecordSubscriber {
+        fn new(id: SubscriberId, delay: Duration) -> Arc<Self> {
+            Arc::new(Self {
+                abstract_subscriber: AbstractSubscriber::new(id),
+                delay,
+                received: Mutex::new(Vec::new()),
+            })
+        }
+
+        fn received(&self) -> Vec<String> {
+            self.received.lock().unwrap().clone()
+        }
+    }
+
+    impl Display for RecordSubscriber {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "record subscriber {}", self.id())
+        }
+    }
+
+    impl Subscriber for RecordSubscriber {
+        fn super_reference(&self) -> &AbstractSubscriber {
+            &self.abstract_subscriber
+        }
+
+        fn on_message(&self, _channel: SyncString, msg: SyncString) {
+            thread::sleep(self.delay);
+            self.received.lock().unwrap().push(msg.to_string());
+        }
+    }
+
+    /// panics on message "panic", passes on others
+    struct PanicInterceptor {
+        abstract_subscriber: AbstractSubscriber,
+    }
+
+    impl Display for PanicInterceptor {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "panic interceptor {}", self.id())
+        }
+    }
+
+    impl Subscriber for PanicInterceptor {
+        fn super_reference(&self) -> &AbstractSubscriber {
+            &self.abstract_subscriber
+        }
+
+        fn intercept(&self, _channel: SyncString, msg: SyncString) -> Interception {
+            assert_ne!(msg.as_str(), "panic");
+            Interception::Pass
+        }
+    }
+
+    fn wait_until(condition: impl Fn() -> bool) {
+        let start = Instant::now();
+        while !condition() {
+            assert!(start.elapsed() < Duration::from_secs(5), "wait timeout");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_dispatch_in_order() {
+        let dispatcher = Dispatcher::new_with_workers(2, MAILBOX_CAPACITY);
+        let subscribers = [
+            RecordSubscriber::new(SubscriberId::new(0, 0), Duration::ZERO),
+            RecordSubscriber::new(SubscriberId::new(1, 0), Duration::ZERO),
+        ];
+        let channel = Arc::new("test_dispatch_in_order".to_string());
+        for i in 0..100 {
+            for subscriber in subscribers.iter() {
+                dispatcher.dispatch(subscriber.clone(), channel.clone(), Arc::new(i.to_string()));
+            }
+        }
+        let expected: Vec<String> = (0..100).map(|i| i.to_string()).collect();
+        for subscriber in subscribers.iter() {
+            wait_until(|| subscriber.received().len() == 100);
+            assert_eq!(subscriber.received(), expected);
+            assert_eq!(dispatcher.queue_depth(subscriber.id()), 0);
+        }
+    }
+
+    #[test]
+    fn test_slow_subscriber() {
+        let dispatcher = Dispatcher::new_with_workers(2, 4);
+        let slow = RecordSubscriber::new(SubscriberId::new(0, 0), Duration::from_millis(200));
+        let fast = RecordSubscriber::new(SubscriberId::new(1, 0), Duration::ZERO);
+        let channel = Arc::new("test_slow_subscriber".to_string());
+        for i in 0..10 {
+            dispatcher.dispatch(slow.clone(), channel.clone(), Arc::new(i.to_string()));
+            dispatcher.dispatch(fast.clone(), channel.clone(), Arc::new(i.to_string()));
+        }
+        // fast subscriber is not starved by slow one
+        wait_until(|| fast.received().len() >= 4);
+        println!(
+            "slow: received {:?}, depth {}, dropped {}",
+            slow.received(),
+            dispatcher.queue_depth(slow.id()),
+            dispatcher.dropped(slow.id())
+        );
+        assert!(dispatcher.queue_depth(slow.id()) > 0);
+        assert!(dispatcher.dropped(slow.id()) > 0);
+        // the newest messages are kept
+        wait_until(|| slow.received().last().map(String::as_str) == Some("9"));
+        assert_eq!(dispatcher.queue_depth(slow.id()), 0);
+    }
+
+    #[test]
+    fn test_panicking_interceptor() {
+        let dispatcher = Dispatcher::new_with_workers(2, MAILBOX_CAPACITY);
+        let channel_name = "test_panicking_interceptor";
+        let recorder = abstract_subscriber::register(|id| RecordSubscriber {
+            abstract_subscriber: AbstractSubscriber::new(id),
+            delay: Duration::ZERO,
+            received: Mutex::new(Vec::new()),
+        });
+        recorder.subscribe(channel_name, None, None);
+        let pair = channel::get_grp_prio_pair(channel_name, recorder.id())
+            .expect("get grp prio pair failed");
+        let interceptor: SharedSubscriber = Arc::new(PanicInterceptor {
+            abstract_subscriber: AbstractSubscriber::new(SubscriberId::new(u32::MAX, 0)),
+        });
+        let grp_prio_pair = GrpPrioPair::new(pair.grp_id, pair.priority_id + 1);
+        let channel = Arc::new(channel_name.to_string());
+        for msg in ["panic", "hello"] {
+            dispatcher.intercept(
+                interceptor.clone(),
+                channel.clone(),
+                Arc::new(msg.to_string()),
+                grp_prio_pair,
+            );
+        }
+
+        // message that interceptor panics on is not passed on, worker goes on
+        wait_until(|| !recorder.received().is_empty());
+        assert_eq!(recorder.received(), vec!["hello"]);
+    }
+}
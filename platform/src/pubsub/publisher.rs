@@ -1,73 +1,142 @@
+use std::collections::HashSet;
 use std::sync::Arc;
-use std::thread;
 
-use dashmap::DashMap;
+use log::{trace, warn};
 
 use common::socket::cmd_message_grp_ids::GroupId;
 use common::SyncString;
 
-use crate::pubsub::channel::SubscriberSet;
-use crate::pubsub::grp_prio_pair::PrioId;
-use crate::pubsub::{abstract_subscriber, channel};
+use crate::pubsub::abstract_subscriber::SubscriberId;
+use crate::pubsub::channel::Channel;
+use crate::pubsub::grp_prio_pair::{GrpPrioPair, PrioId};
+use crate::pubsub::{abstract_subscriber, channel, dispatcher};
+
+/// subscriber to deliver to, with where it intercepts if it is an interceptor
+type Delivery = (SubscriberId, Option<GrpPrioPair>);
 
 /// publish
 /// publish message to channel
 /// if group_id is specified, publish to this group
 /// if group_id is not specified, publish to all groups
-/// if prio_id is specified, publish to this priority and below
-/// if prio_id is not specified, publish to all priorities
+/// if prio_id is specified, publish to the highest priority not above it in each group
+/// if prio_id is not specified, publish to the highest priority in each group
+/// interceptors at that priority pass message on to the priority below, see Subscriber::intercept
+/// subscribers of patterns matching channel receive message too, at most once per subscriber
 pub fn publish(channel: &str, group_id: Option<GroupId>, prio_id: Option<PrioId>, msg: SyncString) {
+    if channel::is_pattern(channel) {
+        warn!("can not publish to pattern {}", channel);
+        return;
+    }
     let prio_id = prio_id.unwrap_or(PrioId::MAX);
     let channel_name = Arc::new(channel.to_string());
-    let channel = channel::get_channel(channel);
-    let subscribers = channel.get_subscribers();
+    let mut deliveries = Vec::new();
+    let patterns = {
+        let channel = channel::get_channel(channel);
+        collect_subscribers(group_id, prio_id, &channel, &mut deliveries);
+        channel.get_patterns()
+    };
+
+    // exact channel is released before patterns are read, so publish costs nothing more without patterns
+    if !patterns.is_empty() {
+        for pattern in patterns.iter() {
+            let pattern = channel::get_channel(pattern);
+            collect_subscribers(group_id, prio_id, &pattern, &mut deliveries);
+        }
+        let mut seen = HashSet::new();
+        deliveries.retain(|(subscriber_id, _)| seen.insert(*subscriber_id));
+    }
+
+    for (subscriber_id, interception) in deliveries {
+        // subscriber may be deregistered while it is still in channel
+        let subscriber = match abstract_subscriber::get_subscriber(subscriber_id) {
+            Some(subscriber) => subscriber,
+            None => continue,
+        };
+        // message is handed to mailbox of subscriber, see dispatcher
+        match interception {
+            Some(grp_prio_pair) => {
+                dispatcher::intercept(subscriber, channel_name.clone(), msg.clone(), grp_prio_pair)
+            }
+            None => dispatcher::dispatch(subscriber, channel_name.clone(), msg.clone()),
+        }
+    }
+}
+
+/// retain message in channel, replacing the message retained before
+/// subscribers opting in receive it when they subscribe, see Subscriber::subscribe_with_retained
+pub fn retain(channel: &str, msg: SyncString) {
+    if channel::is_pattern(channel) {
+        warn!("can not retain message in pattern {}", channel);
+        return;
+    }
+    channel::get_channel(channel).retain(msg);
+}
+
+/// pass intercepted message on to the next priority below interceptor in its group
+/// that has subscribers, of channel or of patterns matching it
+/// message stops at interceptor if there is none
+pub fn pass_on(channel: &str, grp_prio_pair: GrpPrioPair, msg: SyncString) {
+    let (grp_id, prio_id) = (grp_prio_pair.grp_id, grp_prio_pair.priority_id);
+    let (mut prio_below, patterns) = {
+        let channel = channel::get_channel(channel);
+        (
+            channel.get_prio_below(grp_id, prio_id),
+            channel.get_patterns(),
+        )
+    };
+    for pattern in patterns.iter() {
+        let pattern_prio_below = channel::get_channel(pattern).get_prio_below(grp_id, prio_id);
+        prio_below = prio_below.max(pattern_prio_below);
+    }
+    match prio_below {
+        Some(prio_id) => publish(channel, Some(grp_id), Some(prio_id), msg),
+        None => trace!("nothing below {} of {}", grp_prio_pair, channel),
+    }
+}
 
+/// collect subscribers of specified group, or of all groups if group_id is not specified
+fn collect_subscribers(
+    group_id: Option<GroupId>,
+    prio_id: PrioId,
+    channel: &Channel,
+    deliveries: &mut Vec<Delivery>,
+) {
     if let Some(group_id) = group_id {
         //if group_id is specified, publish to this group
-        publish_to_group(channel_name, group_id, prio_id, msg, subscribers);
+        collect_group_subscribers(group_id, prio_id, channel, deliveries);
     } else {
         //if group_id is not specified, publish to all groups
-        for group in subscribers.iter() {
-            publish_to_group(
-                channel_name.clone(),
-                *group.key(),
-                prio_id,
-                msg.clone(),
-                subscribers,
-            );
+        for group in channel.get_subscribers().iter() {
+            collect_group_subscribers(*group.key(), prio_id, channel, deliveries);
         }
     }
 }
 
-/// publish to specified group and priority
-fn publish_to_group(
-    channel_name: SyncString,
+/// collect subscribers of specified group and priority
+fn collect_group_subscribers(
     group_id: GroupId,
     prio_id: PrioId,
-    msg: SyncString,
-    subscribers: &DashMap<GroupId, DashMap<PrioId, SubscriberSet>>,
+    channel: &Channel,
+    deliveries: &mut Vec<Delivery>,
 ) {
-    let grp = subscribers.get(&group_id);
+    let grp = channel.get_subscribers().get(&group_id);
     if let Some(grp) = grp {
-        let mut max_prio = PrioId::MIN;
-        for prio in grp.iter() {
-            if prio.key() > &prio_id {
-                continue;
-            }
-            max_prio = max_prio.max(*prio.key());
-        }
+        let max_prio = grp
+            .iter()
+            .map(|prio| *prio.key())
+            .filter(|prio| *prio <= prio_id)
+            .max();
 
-        if max_prio > PrioId::MIN {
+        //the lowest priority is a priority as well
+        if let Some(max_prio) = max_prio {
             let prio = grp.get(&max_prio).expect("get priority failed");
-            for subscriber_id in prio.iter() {
-                let subscriber = abstract_subscriber::get_subscriber(*subscriber_id)
-                    .expect("get subscriber failed");
-                let channel_name_in = channel_name.clone();
-                let msg_in = msg.clone();
-                thread::spawn(move || {
-                    subscriber.on_message(channel_name_in, msg_in);
-                });
-            }
+            let grp_prio_pair = GrpPrioPair::new(group_id, max_prio);
+            deliveries.extend(prio.iter().map(|subscriber_id| {
+                let interception = channel
+                    .is_interceptor(*subscriber_id)
+                    .then_some(grp_prio_pair);
+                (*subscriber_id, interception)
+            }));
         }
     }
 }
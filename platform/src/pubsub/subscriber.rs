@@ -3,9 +3,21 @@ use std::fmt::Display;
 use common::socket::cmd_message_grp_ids::GroupId;
 use common::SyncString;
 
-use crate::pubsub::abstract_subscriber::AbstractSubscriber;
+use crate::pubsub::abstract_subscriber::{AbstractSubscriber, SubscriberId};
 use crate::pubsub::grp_prio_pair::{GrpPrioPair, PrioId};
 
+/// Interception is what an interceptor does with a message,
+/// see Subscriber::subscribe_as_interceptor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Interception {
+    /// pass message on to lower priorities
+    Pass,
+    /// pass a rewritten message on to lower priorities instead
+    Rewrite(SyncString),
+    /// lower priorities never receive message
+    Drop,
+}
+
 pub trait Subscriber: Send + Sync + Display {
     fn super_reference(&self) -> &AbstractSubscriber;
 
@@ -20,8 +32,16 @@ pub trait Subscriber: Send + Sync + Display {
         );
     }
 
+    ///intercept message before lower priorities of the group receive it
+    /// it is called instead of on_message when subscribed as interceptor
+    /// default handles message by on_message and passes it on, e.g. for a logger
+    fn intercept(&self, channel: SyncString, msg: SyncString) -> Interception {
+        self.on_message(channel, msg);
+        Interception::Pass
+    }
+
     /// id is used to identify subscriber
-    fn id(&self) -> i32 {
+    fn id(&self) -> SubscriberId {
         self.super_reference().id()
     }
 
@@ -38,11 +58,43 @@ pub trait Subscriber: Send + Sync + Display {
             .subscribe(channel, group_id, priority_id);
     }
 
+    ///subscribe to channel and receive its retained message at once
+    /// a pattern receives retained messages of all channels it matches
+    fn subscribe_with_retained(
+        &self,
+        channel: &str,
+        group_id: Option<GroupId>,
+        priority_id: Option<PrioId>,
+    ) {
+        self.super_reference()
+            .subscribe_with_retained(channel, group_id, priority_id);
+    }
+
+    ///subscribe to channel as interceptor
+    /// messages published to the group at its priority or above are intercepted by it first,
+    /// then what it passes on is published to the group below its priority
+    /// an interceptor in a new group has nothing below it, so group id should be given
+    /// return false if another interceptor is at that priority of the group
+    fn subscribe_as_interceptor(
+        &self,
+        channel: &str,
+        group_id: Option<GroupId>,
+        priority_id: Option<PrioId>,
+    ) -> bool {
+        self.super_reference()
+            .subscribe_as_interceptor(channel, group_id, priority_id)
+    }
+
     ///unsubscribe from channel
     fn unsubscribe(&self, channel: &str) {
         self.super_reference().unsubscribe(channel);
     }
 
+    ///unsubscribe from all channels subscribed
+    fn unsubscribe_all(&self) {
+        self.super_reference().unsubscribe_all();
+    }
+
     /// get name
     fn get_name(&self) -> String {
         self.to_string()
@@ -1,14 +1,17 @@
 use std::fmt::Display;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::{Arc, RwLock, Weak};
 
 use dashmap::DashSet;
 use serde::{Deserialize, Serialize};
 
 use common::structs::actor_info::ActorInfo;
+use common::structs::request_policy::RequestPolicy;
 use common::structs::state::State;
 use common::structs::value_type::ValueType;
 
 use crate::app::app_mgr::{SyncAppMgr, SyncAppName, SyncAppNameSet};
+use crate::pubsub::abstract_subscriber::SubscriberId;
 use crate::resource::sensor_mgr::SensorMgr;
 use crate::resource::RwlockAlive;
 
@@ -30,6 +33,14 @@ pub struct ActorMgr {
     actor_type: ValueType,
     #[serde(default = "default_is_alive")]
     is_alive: RwlockAlive,
+    #[serde(default)]
+    request_policy: RequestPolicy,
+    /// requests timed out in a row
+    #[serde(skip)]
+    timeout_num: AtomicU32,
+    /// resource driver registered it last
+    #[serde(skip)]
+    owner: RwLock<Option<SubscriberId>>,
     #[serde(skip)]
     apps: SyncAppNameSet,
 }
@@ -53,27 +64,42 @@ impl ActorMgr {
         self.actor_type
     }
 
-    /// #relate to apps
+    // #relate to apps
 
     /// add app
-    pub fn add_app(&self, app: SyncAppMgr) {
-        todo!()
+    pub fn add_app(&self, app_mgr: &SyncAppMgr) {
+        self.apps
+            .insert(app_mgr.get_app_name_clone(), Arc::downgrade(app_mgr));
     }
 
     /// remove app
-    pub fn remove_app(&self, app: SyncAppMgr) {
-        todo!()
+    /// return false if app is not registered
+    pub fn remove_app(&self, app_name: &SyncAppName) -> bool {
+        self.apps.remove(app_name).is_some()
     }
 
     /// get apps
     /// may leak information
     pub fn get_apps(&self) -> &SyncAppNameSet {
-        todo!()
+        &self.apps
     }
 
     /// get app names
     pub fn get_app_names_vec(&self) -> Vec<SyncAppName> {
-        todo!()
+        self.apps.iter().map(|app| app.key().clone()).collect()
+    }
+
+    /// generate actor information
+    pub fn create_actor_info(&self) -> ActorInfo {
+        ActorInfo::new(
+            Some(self.actor_name.clone()),
+            self.actor_type,
+            match self.is_alive() {
+                true => State::On,
+                false => State::Off,
+            },
+            self.get_app_names_vec(),
+        )
     }
 
     /// is_alive function
@@ -86,25 +112,50 @@ impl ActorMgr {
     /// set the actor alive or not
     /// used when register or error
     pub fn set_alive(&self, alive: bool) {
+        if alive {
+            self.timeout_num.store(0, Ordering::SeqCst);
+        }
         *self.is_alive.write().expect("write is alive fail") = alive;
     }
+
+    /// get request policy
+    pub fn get_request_policy(&self) -> RequestPolicy {
+        self.request_policy
+    }
+
+    /// set resource driver serving it, see ResourceDriver::run
+    pub fn set_owner(&self, owner: SubscriberId) {
+        self.owner.write().expect("write owner fail").replace(owner);
+    }
+
+    /// whether it is served by resource driver,
+    /// a driver of a broken connection does not own it after the wrapper reconnects
+    pub fn is_owned_by(&self, driver: SubscriberId) -> bool {
+        *self.owner.read().expect("read owner fail") == Some(driver)
+    }
+
+    /// record whether a request times out after all attempts
+    /// return true if max_timeout_num requests time out in a row, then it is set not alive
+    pub fn record_request(&self, timeout: bool) -> bool {
+        if !timeout {
+            self.timeout_num.store(0, Ordering::SeqCst);
+            return false;
+        }
+        let timeout_num = self.timeout_num.fetch_add(1, Ordering::SeqCst) + 1;
+        if timeout_num >= self.request_policy.max_timeout_num {
+            self.set_alive(false);
+            return true;
+        }
+        false
+    }
 }
 
 impl Display for ActorMgr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let actor_info = ActorInfo::new(
-            Some(self.actor_name.clone()),
-            self.actor_type.clone(),
-            match self.is_alive() {
-                true => State::On,
-                false => State::Off,
-            },
-            self.get_app_names_vec(),
-        );
         write!(
             f,
             "{}",
-            serde_json::to_string(&actor_info).expect("actor info to string fail")
+            serde_json::to_string(&self.create_actor_info()).expect("actor info to string fail")
         )
     }
 }
@@ -1,16 +1,20 @@
 use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
 
 use dashmap::DashMap;
-use log::trace;
+use log::{info, trace};
 use once_cell::sync::Lazy;
+use serde_json::{json, Map, Value};
 
 use common::SyncString;
 
-use crate::config;
+use crate::pubsub::abstract_subscriber;
+use crate::pubsub::subscriber::Subscriber;
 use crate::resource::actor_mgr::{SyncActorMgr, SyncActorName};
 use crate::resource::resource_driver::ResourceDriver;
 use crate::resource::sensor_mgr::{SyncSensorMgr, SyncSensorName};
+use crate::{config, platform_ctrl};
 
 /// res_mgr_thread is a webserver that provides a management interface for tcp connections.
 
@@ -18,6 +22,7 @@ pub struct ResMgrThread {
     sensor_mgrs: DashMap<SyncSensorName, SyncSensorMgr>,
     actor_mgrs: DashMap<SyncActorName, SyncActorMgr>,
     listener: TcpListener,
+    running: AtomicBool,
 }
 
 impl ResMgrThread {
@@ -33,6 +38,7 @@ impl ResMgrThread {
                     .get_resource_listen_port()
             ))
             .expect("bind tcp port fail"),
+            running: AtomicBool::new(true),
         }
     }
 
@@ -41,17 +47,23 @@ impl ResMgrThread {
         //create a resource driver and add it to subscriber objs
         let resource_driver = ResourceDriver::add_to_subscriber_objs(stream);
         //run resource driver
+        let id = resource_driver.id();
         ResourceDriver::run(resource_driver);
+        //resource driver leaves subscriber objs, a reconnected wrapper gets a new one
+        abstract_subscriber::deregister(id);
     }
 
     pub fn run(&self) {
-        // 增加管理器启动标志...
-        // Platform::incr_mgr_start_flag();
-
-        // 锁定直到管理器启动标志达到特定值...
-        // Platform::lock_until_mgr_start_flag_equal(3);
+        info!(
+            "res mgr thread listen on {}",
+            self.listener.local_addr().expect("get local addr fail")
+        );
+        platform_ctrl::incr_mgr_start_flag();
 
         for stream in self.listener.incoming() {
+            if !self.running.load(Ordering::SeqCst) {
+                break;
+            }
             let stream = stream.unwrap();
             trace!("New resource connection: {}", stream.peer_addr().unwrap());
 
@@ -61,6 +73,19 @@ impl ResMgrThread {
         }
     }
 
+    /// stop
+    /// stop accepting new resource connections and let run return
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+        // wake up the blocking accept in run
+        let port = self
+            .listener
+            .local_addr()
+            .expect("get local addr fail")
+            .port();
+        let _ = TcpStream::connect(("127.0.0.1", port));
+    }
+
     pub fn get_sensor_mgrs(&self) -> &DashMap<SyncString, SyncSensorMgr> {
         &self.sensor_mgrs
     }
@@ -68,6 +93,45 @@ impl ResMgrThread {
     pub fn get_actor_mgrs(&self) -> &DashMap<SyncString, SyncActorMgr> {
         &self.actor_mgrs
     }
+
+    /// generate information of all sensors, keyed by sensor name
+    pub fn create_all_sensor_info(&self) -> Map<String, Value> {
+        self.sensor_mgrs
+            .iter()
+            .map(|sensor_mgr| {
+                (
+                    sensor_mgr.key().to_string(),
+                    json!(sensor_mgr.create_sensor_info()),
+                )
+            })
+            .collect()
+    }
+
+    /// generate information with the latest value of all sensors, keyed by sensor name
+    pub fn create_all_sensor_info_and_data(&self) -> Map<String, Value> {
+        self.sensor_mgrs
+            .iter()
+            .map(|sensor_mgr| {
+                (
+                    sensor_mgr.key().to_string(),
+                    sensor_mgr.create_sensor_info_and_data(),
+                )
+            })
+            .collect()
+    }
+
+    /// generate information of all actors, keyed by actor name
+    pub fn create_all_actor_info(&self) -> Map<String, Value> {
+        self.actor_mgrs
+            .iter()
+            .map(|actor_mgr| {
+                (
+                    actor_mgr.key().to_string(),
+                    json!(actor_mgr.create_actor_info()),
+                )
+            })
+            .collect()
+    }
 }
 
 // create instance of res_mgr_thread
@@ -85,7 +149,7 @@ mod tests {
 
     #[test]
     fn test_res_mgr_thread() {
-        Builder::new().parse_filters("trace").init();
+        let _ = Builder::new().parse_filters("trace").try_init();
         let config_file = "./configfile".to_string();
         config_analyze(config_file.as_ref());
 
@@ -96,9 +160,10 @@ mod tests {
         //link to server
         thread::sleep(std::time::Duration::from_secs(1));
 
-        let mut stream = TcpStream::connect("127.0.0.1:9091").unwrap();
+        let _stream = TcpStream::connect("127.0.0.1:9091").unwrap();
 
         thread::sleep(std::time::Duration::from_secs(1));
-        aa.join();
+        RES_MGR_THREAD.stop();
+        aa.join().unwrap();
     }
 }
@@ -1,36 +1,50 @@
 use std::any::type_name;
 use std::fmt::{Display, Formatter};
 use std::net::TcpStream;
+use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::string::ToString;
+use std::sync::mpsc::RecvTimeoutError;
 use std::sync::{Arc, RwLock, Weak};
 use std::thread;
 
-use log::{info, trace};
+use log::{error, info, trace, warn};
 
 use common::socket::cmd_message::CmdMessage;
 use common::socket::cmd_message_grp_ids::CmdMessageGrpIds;
+use common::socket::framing;
+use common::socket::framing::Framing;
 use common::socket::tcp::TCP;
 use common::structs::enumeration::resource_type::{ResourceType, RwLockOptionResourceType};
+use common::structs::request_policy::RequestPolicy;
 use common::structs::resource_config::ResourceConfig;
+use common::structs::sensor_data::SensorData;
 use common::SyncString;
 
 use crate::pubsub::abstract_subscriber::SubscriberId;
-use crate::pubsub::channel::{get_actor_request, get_sensor_request};
+use crate::pubsub::channel::{
+    get_actor, get_actor_request, get_sensor, get_sensor_request, MONITOR_GRP_ID,
+    SENSOR_REQUEST_SUFFIX,
+};
 use crate::pubsub::subscriber::Subscriber;
 use crate::pubsub::{abstract_subscriber, publisher};
 use crate::resource::actor_mgr::{ActorMgr, RwLockOptionSyncActorMgr};
 use crate::resource::res_mgr_thread::RES_MGR_THREAD;
 use crate::resource::resource_driver::device_driver_tcp::DeviceDriverTCP;
+use crate::resource::resource_driver::request_queue::RequestQueue;
 use crate::resource::sensor_mgr::{RwLockOptionSyncSensorMgr, SensorMgr};
 
 pub mod device_driver_tcp;
+pub mod request_queue;
 
 pub type SyncResourceDriver = Arc<ResourceDriver>;
 pub type WeakResourceDriver = Weak<ResourceDriver>;
 pub type SyncResourceName = Arc<String>;
 pub type RwLockOptionSyncResourceName = RwLock<Option<SyncResourceName>>;
 
-const DEFAULT_NONE_STR: &str = "@#$%";
+/// workers of a resource driver, so that requests of several apps can be in flight
+pub const REQUEST_WORKERS: usize = 4;
+/// requests a resource driver keeps at most, the oldest request is dropped when it is full
+pub const REQUEST_QUEUE_CAPACITY: usize = 64;
 
 /// resource_driver is a job thread that res_mgr_thread can use to perform resource operations.
 //todo: add lock inside instead of outside to avoid field config
@@ -43,6 +57,8 @@ pub struct ResourceDriver {
     actor_mgr: RwLockOptionSyncActorMgr,
     resource_type: RwLockOptionResourceType,
     device_name: RwLockOptionSyncResourceName,
+    /// requests handed off by on_message, whether it is sensory and its message
+    requests: RequestQueue<(bool, SyncString)>,
 }
 
 impl ResourceDriver {
@@ -57,16 +73,13 @@ impl ResourceDriver {
             actor_mgr: RwLock::new(None),
             resource_type: RwLock::new(None),
             device_name: RwLock::new(None),
+            requests: RequestQueue::new(REQUEST_QUEUE_CAPACITY),
         }
     }
 
     ///add to static set Abstract Subscriber objs
     pub fn add_to_subscriber_objs(stream: TcpStream) -> SyncResourceDriver {
-        let mut subscriber_objs = abstract_subscriber::get_objs()
-            .write()
-            .expect("get subscriber objs failed");
-        let resource_driver = Arc::new(Self::new(stream, subscriber_objs.len() as i32));
-        subscriber_objs.push(resource_driver.clone());
+        let resource_driver = abstract_subscriber::register(|id| Self::new(stream, id));
 
         resource_driver
             .tcp
@@ -90,6 +103,7 @@ impl ResourceDriver {
             info!("[wrapper -> platform]: {}", cmd_message);
 
             let joo = serde_json::to_string(&cmd_message.message).expect("parse cmd message fail");
+            let frame_version = framing::negotiate(cmd_message.framing.map(u64::from));
 
             //handle register
             if cmd_message
@@ -97,8 +111,34 @@ impl ResourceDriver {
                 .expect("cmd is none")
                 .eq_ignore_ascii_case("register")
             {
-                let resource_config: ResourceConfig =
-                    serde_json::from_str(&joo).expect("parse resource config fail");
+                //an invalid config is refused with its problems instead of panic
+                let source = format!(
+                    "register of {}",
+                    driver
+                        .tcp
+                        .get_socket()
+                        .peer_addr()
+                        .map(|addr| addr.to_string())
+                        .unwrap_or_default()
+                );
+                let resource_config = match ResourceConfig::from_value_validated(
+                    &source,
+                    cmd_message.message.clone().unwrap_or_default(),
+                ) {
+                    Ok(resource_config) => resource_config,
+                    Err(e) => {
+                        warn!("{}", e);
+                        let return_msg = CmdMessage::new_with_error(
+                            Some("register_back".to_string()),
+                            Some(serde_json::json!("false")),
+                            e.to_string(),
+                        );
+                        driver.tcp.send(&return_msg.to_string());
+                        //close of abstract tcp logs instead of panic if wrapper has closed
+                        driver.tcp.super_reference().close();
+                        return;
+                    }
+                };
                 let resource_type = resource_config.resource_type;
                 let device_name = Arc::new(resource_config.name.expect("device name is none"));
 
@@ -114,25 +154,40 @@ impl ResourceDriver {
                     .expect("write device name fail")
                     .replace(device_name.clone());
 
-                match resource_type {
+                let registered = match resource_type {
                     ResourceType::Sensor => {
-                        Self::register_sensor(driver.clone(), device_name, &joo);
+                        Self::register_sensor(driver.clone(), device_name.clone(), &joo)
                     }
                     ResourceType::Actor => {
-                        Self::register_actor(driver.clone(), device_name, &joo);
+                        Self::register_actor(driver.clone(), device_name.clone(), &joo)
                     }
                     ResourceType::Hybrid => {
-                        Self::register_hybrid(driver.clone(), device_name, &joo);
+                        Self::register_hybrid(driver.clone(), device_name.clone(), &joo)
                     }
+                };
+                //config that passes validation may still not fit a mgr, it is refused as well
+                if let Err(e) = registered {
+                    warn!("{}: parse resource mgr fail: {}", device_name, e);
+                    let return_msg = CmdMessage::new_with_error(
+                        Some("register_back".to_string()),
+                        Some(serde_json::json!("false")),
+                        format!("parse resource mgr fail: {}", e),
+                    );
+                    driver.tcp.send(&return_msg.to_string());
+                    driver.tcp.super_reference().close();
+                    return;
                 }
             }
 
             //register back
-            let return_msg = CmdMessage::new(
+            let return_msg = CmdMessage::new_with_framing(
                 Some("register_back".to_string()),
                 Some(serde_json::json!("true")),
+                frame_version,
             );
             driver.tcp.send(&return_msg.to_string());
+            // register back is still a line, frames start after it
+            driver.tcp.set_framing(Framing::from_version(frame_version));
             info!(
                 "[platform -> {}]: {}",
                 driver
@@ -144,71 +199,48 @@ impl ResourceDriver {
                 return_msg
             );
 
-            //set tcp lock_flag
-            driver.tcp.set_lock_flag(true);
+            //replies are read by one thread, so requests of several apps can be in flight
+            {
+                let driver = driver.clone();
+                thread::spawn(move || driver.tcp.recv_loop());
+            }
+            //a wrapper waits on io, so requests are handled by workers of this driver
+            //instead of pubsub workers
+            let workers: Vec<_> = (0..REQUEST_WORKERS)
+                .map(|_| {
+                    let driver = driver.clone();
+                    thread::spawn(move || driver.work())
+                })
+                .collect();
 
             //alive request loop
             loop {
                 thread::sleep(std::time::Duration::from_secs(1));
 
-                match driver
-                    .resource_type
-                    .read()
-                    .expect("read resource type fail")
-                    .as_ref()
-                    .expect("resource type is none")
-                {
-                    ResourceType::Sensor => {
-                        if driver
-                            .sensor_mgr
-                            .read()
-                            .expect("read sensor mgr fail")
-                            .as_ref()
-                            .expect("get sensor mgr fail")
-                            .is_alive()
-                            == false
-                        {
-                            break;
-                        }
-                    }
-                    ResourceType::Actor => {
-                        if driver
-                            .actor_mgr
-                            .read()
-                            .expect("read actor mgr fail")
-                            .as_ref()
-                            .expect("get actor mgr fail")
-                            .is_alive()
-                            == false
-                        {
-                            break;
-                        }
-                    }
-                    ResourceType::Hybrid => {
-                        if driver
-                            .sensor_mgr
+                //resource may be registered again by another connection
+                if driver.tcp.is_broken() {
+                    break;
+                }
+
+                //a hybrid resource is served while either of its roles is alive
+                //otherwise connection is closed, so that recv loop stops and wrapper reconnects
+                if !driver.is_sensor_alive() && !driver.is_actor_alive() {
+                    warn!(
+                        "{}: not alive, close connection",
+                        driver
+                            .device_name
                             .read()
-                            .expect("read sensor mgr fail")
+                            .expect("read device name fail")
                             .as_ref()
-                            .expect("get sensor mgr fail")
-                            .is_alive()
-                            == false
-                            || driver
-                                .actor_mgr
-                                .read()
-                                .expect("read actor mgr fail")
-                                .as_ref()
-                                .expect("get actor mgr fail")
-                                .is_alive()
-                                == false
-                        {
-                            break;
-                        }
-                    }
+                            .expect("device name is none")
+                    );
+                    driver.tcp.super_reference().close();
+                    break;
                 }
 
                 let alive_request = CmdMessage::new(Some("alive_request".to_string()), None);
 
+                //alive request has no reply
                 if driver.tcp.send(&alive_request.to_string()) == false {
                     break;
                 }
@@ -222,13 +254,33 @@ impl ResourceDriver {
                         .expect("device name is none"),
                     alive_request
                 );
+            }
+
+            driver.requests.close();
+            for worker in workers {
+                let _ = worker.join();
+            }
+        }
+    }
 
-                driver.tcp.unlock();
+    /// worker takes requests in order until driver stops
+    /// a panic on a request must not kill worker
+    fn work(&self) {
+        while let Some((is_sensory, msg)) = self.requests.take() {
+            let handle = AssertUnwindSafe(|| self.on_message_handle(is_sensory, msg));
+            if catch_unwind(handle).is_err() {
+                error!("{} panics on request", self);
             }
         }
     }
 
-    fn register_sensor(driver: SyncResourceDriver, device_name: SyncResourceName, joo: &str) {
+    /// register sensor role of driver
+    /// return error if a new sensor mgr can not be parsed from joo, nothing is registered then
+    fn register_sensor(
+        driver: SyncResourceDriver,
+        device_name: SyncResourceName,
+        joo: &str,
+    ) -> Result<(), serde_json::Error> {
         if RES_MGR_THREAD.get_sensor_mgrs().contains_key(&device_name) {
             let sensor_mgr = RES_MGR_THREAD
                 .get_sensor_mgrs()
@@ -240,18 +292,10 @@ impl ResourceDriver {
                 .write()
                 .expect("get write sensor mgr fail")
                 .replace(sensor_mgr);
-            driver
-                .sensor_mgr
-                .read()
-                .expect("get read sensor mgr fail")
-                .as_ref()
-                .expect("sensor mgr is none")
-                .set_alive(true);
 
             trace!("Sensor: {} get from sensor mgrs success", device_name);
         } else {
-            let sensor_mgr: Arc<SensorMgr> =
-                Arc::new(serde_json::from_str(joo).expect("parse sensor mgr fail"));
+            let sensor_mgr: Arc<SensorMgr> = Arc::new(serde_json::from_str(joo)?);
             driver
                 .sensor_mgr
                 .write()
@@ -262,12 +306,29 @@ impl ResourceDriver {
                 .insert(device_name.clone(), sensor_mgr);
             trace!("New sensor: {} add to sensor mgrs success", device_name);
         }
+        //owner is set before alive, so that a broken connection of the last driver does not reset it
+        if let Some(sensor_mgr) = driver
+            .sensor_mgr
+            .read()
+            .expect("read sensor mgr fail")
+            .as_ref()
+        {
+            sensor_mgr.set_owner(driver.id());
+            sensor_mgr.set_alive(true);
+        }
         driver.subscribe(&get_sensor_request(&device_name), None, None);
 
         trace!("New sensor: {} register to platform success", device_name);
+        Ok(())
     }
 
-    fn register_actor(driver: SyncResourceDriver, device_name: SyncResourceName, joo: &str) {
+    /// register actor role of driver
+    /// return error if a new actor mgr can not be parsed from joo, nothing is registered then
+    fn register_actor(
+        driver: SyncResourceDriver,
+        device_name: SyncResourceName,
+        joo: &str,
+    ) -> Result<(), serde_json::Error> {
         if RES_MGR_THREAD.get_actor_mgrs().contains_key(&device_name) {
             let actor_mgr = RES_MGR_THREAD
                 .get_actor_mgrs()
@@ -279,18 +340,10 @@ impl ResourceDriver {
                 .write()
                 .expect("get write actor mgr fail")
                 .replace(actor_mgr);
-            driver
-                .actor_mgr
-                .read()
-                .expect("get read actor mgr fail")
-                .as_ref()
-                .expect("actor mgr is none")
-                .set_alive(true);
 
             trace!("Actor: {} get from actor mgrs success", device_name);
         } else {
-            let actor_mgr: Arc<ActorMgr> =
-                Arc::new(serde_json::from_str(joo).expect("parse actor mgr fail"));
+            let actor_mgr: Arc<ActorMgr> = Arc::new(serde_json::from_str(joo)?);
             driver
                 .actor_mgr
                 .write()
@@ -301,71 +354,186 @@ impl ResourceDriver {
                 .insert(device_name.clone(), actor_mgr);
             trace!("New actor: {} add to actor mgrs success", device_name);
         }
+        //owner is set before alive, so that a broken connection of the last driver does not reset it
+        if let Some(actor_mgr) = driver
+            .actor_mgr
+            .read()
+            .expect("read actor mgr fail")
+            .as_ref()
+        {
+            actor_mgr.set_owner(driver.id());
+            actor_mgr.set_alive(true);
+        }
         driver.subscribe(&get_actor_request(&device_name), None, None);
 
         trace!("New actor: {} register to platform success", device_name);
+        Ok(())
+    }
+
+    /// register both roles of driver
+    /// actor mgr is parsed first, so that a hybrid is not left registered as a sensor only
+    fn register_hybrid(
+        driver: SyncResourceDriver,
+        device_name: SyncResourceName,
+        joo: &str,
+    ) -> Result<(), serde_json::Error> {
+        serde_json::from_str::<ActorMgr>(joo)?;
+        Self::register_sensor(driver.clone(), device_name.clone(), joo)?;
+        Self::register_actor(driver.clone(), device_name.clone(), joo)
     }
 
-    fn register_hybrid(driver: SyncResourceDriver, device_name: SyncResourceName, joo: &str) {
-        Self::register_sensor(driver.clone(), device_name.clone(), joo);
-        Self::register_actor(driver.clone(), device_name.clone(), joo);
+    /// whether sensor role is registered and alive
+    fn is_sensor_alive(&self) -> bool {
+        self.sensor_mgr
+            .read()
+            .expect("read sensor mgr fail")
+            .as_ref()
+            .is_some_and(|sensor_mgr| sensor_mgr.is_alive())
+    }
+
+    /// whether actor role is registered and alive
+    fn is_actor_alive(&self) -> bool {
+        self.actor_mgr
+            .read()
+            .expect("read actor mgr fail")
+            .as_ref()
+            .is_some_and(|actor_mgr| actor_mgr.is_alive())
     }
 
-    fn on_message_handle(&self, _channel: SyncString, msg: SyncString) {
-        let resource_name_and_type = format!(
-            "{}<{}>",
-            self.device_name
+    /// request policy of sensor for sensory request, or of actor for action request
+    fn get_request_policy(&self, is_sensory: bool) -> RequestPolicy {
+        if is_sensory {
+            self.sensor_mgr
                 .read()
-                .expect("read device name fail")
+                .expect("read sensor mgr fail")
                 .as_ref()
-                .expect("device name is none"),
-            self.resource_type
+                .map(|sensor_mgr| sensor_mgr.get_request_policy())
+        } else {
+            self.actor_mgr
                 .read()
-                .expect("read resource type fail")
+                .expect("read actor mgr fail")
                 .as_ref()
-                .expect("resource type is none")
-        );
+                .map(|actor_mgr| actor_mgr.get_request_policy())
+        }
+        .unwrap_or_default()
+    }
+
+    /// record whether a request times out to sensor or actor
+    /// requests of a driver which no longer owns the resource are not recorded
+    /// return true if it is set not alive
+    fn record_request(&self, is_sensory: bool, timeout: bool) -> bool {
+        if is_sensory {
+            self.sensor_mgr
+                .read()
+                .expect("read sensor mgr fail")
+                .as_ref()
+                .filter(|sensor_mgr| sensor_mgr.is_owned_by(self.id()))
+                .is_some_and(|sensor_mgr| sensor_mgr.record_request(timeout))
+        } else {
+            self.actor_mgr
+                .read()
+                .expect("read actor mgr fail")
+                .as_ref()
+                .filter(|actor_mgr| actor_mgr.is_owned_by(self.id()))
+                .is_some_and(|actor_mgr| actor_mgr.record_request(timeout))
+        }
+    }
+
+    /// send request and wait for its reply
+    /// it is sent again after a backoff if it times out, at most retry_num times
+    /// return number of attempts if there is no reply
+    fn request_with_retry(
+        &self,
+        send: &CmdMessage,
+        policy: &RequestPolicy,
+    ) -> Result<CmdMessage, u32> {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            match self.tcp.request(send, policy.get_timeout()) {
+                Ok(recv) => return Ok(recv),
+                // connection is closed, retry is useless
+                Err(RecvTimeoutError::Disconnected) => return Err(attempts),
+                Err(RecvTimeoutError::Timeout) if attempts > policy.retry_num => {
+                    return Err(attempts)
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    trace!("request {} timeout, attempt {}", send, attempts);
+                    thread::sleep(policy.get_backoff(attempts));
+                }
+            }
+        }
+    }
+
+    /// send request to wrapper and publish its reply
+    /// reply of sensory request is published to sensor channel, of action request to actor channel,
+    /// so that a hybrid resource serves apps under both roles
+    fn on_message_handle(&self, is_sensory: bool, msg: SyncString) {
+        let device_name = self
+            .device_name
+            .read()
+            .expect("read device name fail")
+            .as_ref()
+            .expect("device name is none")
+            .clone();
+        //channel name is also used to name resource in log, e.g. YellowCar<Sensor>
+        let resource_name_and_type = if is_sensory {
+            get_sensor(&device_name)
+        } else {
+            get_actor(&device_name)
+        };
 
         let cmd_message_grp_ids: CmdMessageGrpIds =
             serde_json::from_str(&msg).expect("parse cmd message grp ids fail");
         let send = cmd_message_grp_ids.get_cmd_message();
-        self.tcp.send(&send.to_string());
+        let policy = self.get_request_policy(is_sensory);
         info!("[platform -> {}]: {}", resource_name_and_type, send);
 
-        let recv: CmdMessage = match self.tcp.recv() {
-            Some(ret) => serde_json::from_str(&ret).expect("parse cmd message fail"),
-            None => {
-                if send
-                    .cmd
-                    .expect("cmd is none")
-                    .eq_ignore_ascii_case("sensory_request")
-                {
-                    CmdMessage::new(
-                        Some("sensory_back".to_string()),
-                        Some(
-                            serde_json::to_value(DEFAULT_NONE_STR)
-                                .expect("parse default none str fail"),
-                        ),
-                    )
-                } else {
-                    CmdMessage::new(
-                        Some("action_back".to_string()),
-                        Some(
-                            serde_json::to_value(DEFAULT_NONE_STR)
-                                .expect("parse default none str fail"),
-                        ),
-                    )
+        let msg = match self.request_with_retry(&send, &policy) {
+            Ok(recv) => {
+                info!("[{} -> platform]: {}", resource_name_and_type, recv);
+                self.record_request(is_sensory, false);
+                let msg = recv.message.as_ref().expect("message is none").to_string();
+                if is_sensory {
+                    if let Some(sensor_mgr) = self
+                        .sensor_mgr
+                        .read()
+                        .expect("read sensor mgr fail")
+                        .as_ref()
+                    {
+                        sensor_mgr.set_value(msg.clone());
+                    }
+                    // a failed request is not retained, the last value stays
+                    publisher::retain(&resource_name_and_type, Arc::new(msg.clone()));
+                }
+                msg
+            }
+            Err(attempts) => {
+                warn!(
+                    "[{} -> platform]: no reply of {} after {} attempts",
+                    resource_name_and_type, send, attempts
+                );
+                if self.record_request(is_sensory, true) {
+                    warn!(
+                        "{} is not alive after {} requests time out in a row",
+                        resource_name_and_type, policy.max_timeout_num
+                    );
                 }
+                serde_json::to_string(&SensorData::new_timeout(attempts, policy.timeout_ms))
+                    .expect("serialize timeout fail")
             }
         };
-        info!("[{} -> platform]: {}", resource_name_and_type, recv);
 
-        for grp_id in cmd_message_grp_ids.grp_ids.expect("grp ids is none") {
+        let mut grp_ids = cmd_message_grp_ids.grp_ids.expect("grp ids is none");
+        if !grp_ids.contains(&MONITOR_GRP_ID) {
+            grp_ids.push(MONITOR_GRP_ID);
+        }
+        for grp_id in grp_ids {
             publisher::publish(
                 &resource_name_and_type,
                 Some(grp_id),
                 None,
-                Arc::new(recv.message.as_ref().expect("message is none").to_string()),
+                Arc::new(msg.clone()),
             );
         }
     }
@@ -385,57 +553,310 @@ impl Subscriber for ResourceDriver {
         &self.abstract_subscriber
     }
 
+    /// request of sensor and actor of a hybrid resource are handled separately,
+    /// each only if its role is alive
+    /// a request is handed off to request queue of driver, so that dispatcher is not blocked
+    /// until wrapper replies, the oldest request is dropped when the queue is full
     fn on_message(&self, channel: SyncString, msg: SyncString) {
-        match self
-            .resource_type
-            .read()
-            .expect("read resource type fail")
-            .as_ref()
-            .expect("resource type is none")
-        {
-            ResourceType::Sensor => {
-                if self
-                    .sensor_mgr
-                    .read()
-                    .expect("read sensor mgr fail")
-                    .as_ref()
-                    .expect("sensor mgr is none")
-                    .is_alive()
-                {
-                    self.on_message_handle(channel, msg);
-                }
-            }
-            ResourceType::Actor => {
-                if self
-                    .actor_mgr
-                    .read()
-                    .expect("read actor mgr fail")
-                    .as_ref()
-                    .expect("actor mgr is none")
-                    .is_alive()
-                {
-                    self.on_message_handle(channel, msg);
-                }
+        let is_sensory = channel.ends_with(SENSOR_REQUEST_SUFFIX);
+        let alive = if is_sensory {
+            self.is_sensor_alive()
+        } else {
+            self.is_actor_alive()
+        };
+        if !alive {
+            return;
+        }
+        if let Some((_, msg)) = self.requests.push((is_sensory, msg)) {
+            let dropped = self.requests.dropped();
+            // every drop is counted, log is thinned out to 1, 2, 4, 8... drops
+            if dropped.is_power_of_two() {
+                warn!(
+                    "{}: request queue is full, drop request {} ({} dropped)",
+                    channel, msg, dropped
+                );
+            } else {
+                trace!("{}: request queue is full, drop request {}", channel, msg);
             }
-            ResourceType::Hybrid => {
-                if self
-                    .sensor_mgr
-                    .read()
-                    .expect("read sensor mgr fail")
-                    .as_ref()
-                    .expect("sensor mgr is none")
-                    .is_alive()
-                    && self
-                        .actor_mgr
-                        .read()
-                        .expect("read actor mgr fail")
-                        .as_ref()
-                        .expect("actor mgr is none")
-                        .is_alive()
-                {
-                    self.on_message_handle(channel, msg);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    use serde_json::{json, Value};
+
+    use common::socket::cmd_message_grp_ids::GroupId;
+
+    use crate::config::configuration::config_analyze;
+    use crate::pubsub::abstract_subscriber::AbstractSubscriber;
+    use crate::pubsub::channel;
+
+    use super::*;
+
+    /// app side, records replies published to a channel
+    struct TestRecorder {
+        abstract_subscriber: AbstractSubscriber,
+        received: Mutex<Vec<String>>,
+    }
+
+    impl TestRecorder {
+        fn new() -> Arc<Self> {
+            abstract_subscriber::register(|id| Self {
+                abstract_subscriber: AbstractSubscriber::new(id),
+                received: Mutex::new(Vec::new()),
+            })
+        }
+
+        fn received(&self) -> Vec<String> {
+            self.received.lock().unwrap().clone()
+        }
+    }
+
+    impl Display for TestRecorder {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", type_name::<Self>())
+        }
+    }
+
+    impl Subscriber for TestRecorder {
+        fn super_reference(&self) -> &AbstractSubscriber {
+            &self.abstract_subscriber
+        }
+
+        fn on_message(&self, _channel: SyncString, msg: SyncString) {
+            self.received.lock().unwrap().push(msg.to_string());
+        }
+    }
+
+    /// request published by an app of group grp_id
+    fn publish_request(channel: &str, cmd: &str, message: Option<Value>, grp_id: GroupId) {
+        let request = CmdMessageGrpIds::new(Some(cmd.to_string()), message, Some(vec![grp_id]));
+        publisher::publish(
+            channel,
+            None,
+            None,
+            Arc::new(serde_json::to_string(&request).unwrap()),
+        );
+    }
+
+    /// wrapper side of a connection to a resource driver
+    struct TestWrapper {
+        stream: TcpStream,
+        reader: BufReader<TcpStream>,
+    }
+
+    impl TestWrapper {
+        fn send(&mut self, msg: &CmdMessage) {
+            self.stream
+                .write_all((msg.to_string() + "\n").as_bytes())
+                .unwrap();
+        }
+
+        /// next request of platform, alive requests are skipped
+        fn recv(&mut self) -> CmdMessage {
+            loop {
+                let mut line = String::new();
+                assert!(self.reader.read_line(&mut line).unwrap() > 0);
+                let msg: CmdMessage = serde_json::from_str(line.trim_end()).unwrap();
+                if msg.cmd.as_deref() != Some("alive_request") {
+                    return msg;
                 }
             }
         }
     }
+
+    /// connect a wrapper to a new resource driver and register it
+    fn register(config: &ResourceConfig) -> (TestWrapper, SyncResourceDriver) {
+        config_analyze("./configfile".as_ref());
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let driver = ResourceDriver::add_to_subscriber_objs(listener.accept().unwrap().0);
+        {
+            let driver = driver.clone();
+            thread::spawn(move || ResourceDriver::run(driver));
+        }
+        let mut wrapper = TestWrapper {
+            reader: BufReader::new(stream.try_clone().unwrap()),
+            stream,
+        };
+        let message = serde_json::to_value(config).unwrap();
+        wrapper.send(&CmdMessage::new(
+            Some("register".to_string()),
+            Some(message),
+        ));
+        let register_back = wrapper.recv();
+        assert_eq!(register_back.message, Some(serde_json::json!("true")));
+        (wrapper, driver)
+    }
+
+    fn wait_until(condition: impl Fn() -> bool) {
+        let start = Instant::now();
+        while !condition() {
+            assert!(start.elapsed() < Duration::from_secs(5), "wait timeout");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_stale_callback() {
+        let config = ResourceConfig::new(
+            Some("test_stale_callback_car".to_string()),
+            ResourceType::Hybrid,
+            Some(vec!["speed".to_string()]),
+        );
+        let (old_wrapper, old_driver) = register(&config);
+        // wrapper reconnects before the old connection is found broken
+        let (_new_wrapper, new_driver) = register(&config);
+        old_wrapper
+            .stream
+            .shutdown(std::net::Shutdown::Both)
+            .unwrap();
+        wait_until(|| old_driver.tcp.is_broken());
+
+        assert!(new_driver.is_sensor_alive());
+        assert!(new_driver.is_actor_alive());
+        for request in [
+            get_sensor_request("test_stale_callback_car"),
+            get_actor_request("test_stale_callback_car"),
+        ] {
+            assert!(channel::get_grp_prio_pair(&request, new_driver.id()).is_some());
+            assert!(channel::get_grp_prio_pair(&request, old_driver.id()).is_none());
+        }
+    }
+
+    #[test]
+    fn test_hybrid() {
+        let name = "test_hybrid_lamp";
+        let config = ResourceConfig::new(
+            Some(name.to_string()),
+            ResourceType::Hybrid,
+            Some(vec!["light".to_string()]),
+        );
+        let (mut wrapper, _driver) = register(&config);
+        let grp_id = 5;
+        let sensor_recorder = TestRecorder::new();
+        sensor_recorder.subscribe(&get_sensor(name), Some(grp_id), None);
+        let actor_recorder = TestRecorder::new();
+        actor_recorder.subscribe(&get_actor(name), Some(grp_id), None);
+
+        // read and action are served on one connection
+        publish_request(&get_sensor_request(name), "sensory_request", None, grp_id);
+        let request = wrapper.recv();
+        assert_eq!(request.cmd.as_deref(), Some("sensory_request"));
+        wrapper.send(&CmdMessage::new_with_id(
+            Some("sensory_back".to_string()),
+            Some(json!({"light": 1})),
+            request.id,
+        ));
+
+        publish_request(
+            &get_actor_request(name),
+            "action_request",
+            Some(json!("on")),
+            grp_id,
+        );
+        let request = wrapper.recv();
+        assert_eq!(request.cmd.as_deref(), Some("action_request"));
+        assert_eq!(request.message, Some(json!("on")));
+        wrapper.send(&CmdMessage::new_with_id(
+            Some("action_back".to_string()),
+            Some(json!("true")),
+            request.id,
+        ));
+
+        // each reply goes to the channel of its role
+        wait_until(|| !sensor_recorder.received().is_empty());
+        wait_until(|| !actor_recorder.received().is_empty());
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(
+            sensor_recorder.received(),
+            vec![json!({"light": 1}).to_string()]
+        );
+        assert_eq!(actor_recorder.received(), vec![json!("true").to_string()]);
+    }
+
+    #[test]
+    fn test_register_with_invalid_mgr() {
+        config_analyze("./configfile".as_ref());
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let _stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let driver = ResourceDriver::add_to_subscriber_objs(listener.accept().unwrap().0);
+        let name = Arc::new("test_register_with_invalid_mgr".to_string());
+
+        // name of mgr should be a string
+        let joo = json!({"name": 1}).to_string();
+        assert!(ResourceDriver::register_hybrid(driver.clone(), name.clone(), &joo).is_err());
+        assert!(!RES_MGR_THREAD.get_sensor_mgrs().contains_key(&name));
+        assert!(!RES_MGR_THREAD.get_actor_mgrs().contains_key(&name));
+        assert!(channel::get_grp_prio_pair(&get_sensor_request(&name), driver.id()).is_none());
+    }
+
+    #[test]
+    fn test_flood() {
+        let name = "test_flood_car";
+        // wrapper replies late, so requests are not timed out meanwhile
+        let config = ResourceConfig::new_with_request_policy(
+            Some(name.to_string()),
+            ResourceType::Sensor,
+            Some(vec!["speed".to_string()]),
+            RequestPolicy::new(10_000, 0, 0, 3),
+        );
+        let (mut wrapper, driver) = register(&config);
+        let request = |seq: usize| {
+            publish_request(
+                &get_sensor_request(name),
+                "sensory_request",
+                Some(json!(seq)),
+                0,
+            )
+        };
+        let recv = |wrapper: &mut TestWrapper| {
+            let request = wrapper.recv();
+            let seq = request.message.as_ref().unwrap().as_u64().unwrap() as usize;
+            (seq, request.id)
+        };
+
+        // every worker waits for a reply
+        (0..REQUEST_WORKERS).for_each(request);
+        let mut in_flight: VecDeque<_> = (0..REQUEST_WORKERS).map(|_| recv(&mut wrapper)).collect();
+        let mut seqs: Vec<_> = in_flight.iter().map(|(seq, _)| *seq).collect();
+        seqs.sort();
+        assert_eq!(seqs, (0..REQUEST_WORKERS).collect::<Vec<_>>());
+
+        // queue keeps the newest requests
+        let extra = 10;
+        let total = REQUEST_WORKERS + REQUEST_QUEUE_CAPACITY + extra;
+        (REQUEST_WORKERS..total).for_each(request);
+        wait_until(|| driver.requests.dropped() == extra as u64);
+        assert_eq!(driver.requests.len(), REQUEST_QUEUE_CAPACITY);
+
+        // a worker freed by a reply takes the oldest request left
+        for expected in REQUEST_WORKERS + extra..total {
+            let (_, id) = in_flight.pop_front().unwrap();
+            wrapper.send(&CmdMessage::new_with_id(
+                Some("sensory_back".to_string()),
+                Some(json!(1)),
+                id,
+            ));
+            let (seq, id) = recv(&mut wrapper);
+            assert_eq!(seq, expected);
+            in_flight.push_back((seq, id));
+        }
+        assert!(driver.requests.is_empty());
+        assert_eq!(driver.requests.dropped(), extra as u64);
+        for (_, id) in in_flight {
+            wrapper.send(&CmdMessage::new_with_id(
+                Some("sensory_back".to_string()),
+                Some(json!(1)),
+                id,
+            ));
+        }
+    }
 }
@@ -1,12 +1,18 @@
 use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
-use log::{error, trace};
+use log::{error, trace, warn};
 
 use common::socket::abstract_tcp::AbstractTCP;
+use common::socket::cmd_message::CmdMessage;
+use common::socket::demux::Demux;
 use common::socket::tcp::TCP;
-use common::structs::enumeration::resource_type::ResourceType;
 
+use crate::pubsub::channel::{get_actor_request, get_sensor_request};
+use crate::pubsub::subscriber::Subscriber;
 use crate::resource::resource_driver::{SyncResourceDriver, WeakResourceDriver};
 
 //todo: resource_driver和device_driver_tcp的依赖关系对吗？
@@ -18,6 +24,9 @@ use crate::resource::resource_driver::{SyncResourceDriver, WeakResourceDriver};
 pub struct DeviceDriverTCP {
     abstract_tcp: AbstractTCP,
     resource_driver_weak: RwLock<Option<WeakResourceDriver>>,
+    /// replies of wrapper to requests in flight
+    demux: Demux,
+    broken: AtomicBool,
 }
 
 impl DeviceDriverTCP {
@@ -25,9 +34,68 @@ impl DeviceDriverTCP {
         Self {
             abstract_tcp: AbstractTCP::new(socket, lock_flag),
             resource_driver_weak: RwLock::new(None),
+            demux: Demux::new(),
+            broken: AtomicBool::new(false),
         }
     }
 
+    /// request
+    /// send request with a new id and wait for its reply until timeout,
+    /// other requests can be sent meanwhile, replies are read by recv_loop
+    /// return Disconnected if request is not sent or connection is closed before reply,
+    /// a late reply after Timeout is dropped
+    pub fn request(
+        &self,
+        request: &CmdMessage,
+        timeout: Duration,
+    ) -> Result<CmdMessage, RecvTimeoutError> {
+        let (id, receiver) = self.demux.register();
+        let request =
+            CmdMessage::new_with_id(request.cmd.clone(), request.message.clone(), Some(id));
+        if !self.send(&request.to_string()) {
+            self.demux.cancel(id);
+            return Err(RecvTimeoutError::Disconnected);
+        }
+        let reply = receiver.recv_timeout(timeout);
+        if reply.is_err() {
+            self.demux.cancel(id);
+        }
+        reply
+    }
+
+    /// recv loop
+    /// read replies and pass them to their requests until connection is closed
+    pub fn recv_loop(&self) {
+        while let Some(recv) = self.recv() {
+            match serde_json::from_str::<CmdMessage>(&recv) {
+                Ok(reply) => {
+                    if !self.demux.dispatch(reply) {
+                        warn!("drop reply without request: {}", recv);
+                    }
+                }
+                Err(e) => warn!("drop invalid reply {}: {}", recv, e),
+            }
+        }
+        self.demux.close();
+        // peer closing connection is not an error of recv, so callback is not invoked yet
+        self.callback();
+        trace!("resource tcp connection: recv loop stop");
+    }
+
+    /// whether connection is broken and callback has run
+    pub fn is_broken(&self) -> bool {
+        self.broken.load(Ordering::SeqCst)
+    }
+
+    /// get resource driver, none if it has been dropped
+    pub fn get_resource_driver(&self) -> Option<SyncResourceDriver> {
+        self.resource_driver_weak
+            .read()
+            .expect("read resource driver weak fail")
+            .as_ref()
+            .and_then(|resource_driver_weak| resource_driver_weak.upgrade())
+    }
+
     pub fn set_resource_driver_weak(&self, resource_driver: &SyncResourceDriver) {
         self.resource_driver_weak
             .write()
@@ -59,77 +127,55 @@ impl TCP for DeviceDriverTCP {
     }
 
     //TODO: HOW TO INVOKE IT JUST LIKE JAVA INTERFACE CALLBACK
+    /// set resource not alive and stop requests to it
+    /// it runs once, and a resource registered again by a reconnected wrapper is owned by
+    /// the new driver, so a late callback of the broken connection leaves it alive
     fn callback(&self) {
+        if self.broken.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let resource_driver = match self.get_resource_driver() {
+            Some(resource_driver) => resource_driver,
+            None => return,
+        };
+        let device_name = match resource_driver
+            .device_name
+            .read()
+            .expect("read device name fail")
+            .clone()
+        {
+            Some(device_name) => device_name,
+            // connection is broken before register
+            None => return,
+        };
         error!(
             "{}: TCP connection is broken. Set the status to off",
-            self.resource_driver_weak
-                .read()
-                .expect("read resource driver weak fail")
-                .as_ref()
-                .expect("resource driver weak is none")
-                .upgrade()
-                .expect("get resource driver fail")
-                .device_name
-                .read()
-                .expect("read device name fail")
-                .as_ref()
-                .expect("device name is none")
+            device_name
         );
 
-        let resource_driver = self
-            .resource_driver_weak
-            .read()
-            .expect("read resource driver weak fail")
-            .as_ref()
-            .expect("resource driver weak is none")
-            .upgrade()
-            .expect("get resource driver fail");
-
-        //notion: should not return resource_driver out, so should add ;
-        match resource_driver
-            .resource_type
+        let sensor_mgr = resource_driver
+            .sensor_mgr
             .read()
-            .expect("read resource type fail")
-            .as_ref()
-            .expect("resource type is none")
-        {
-            ResourceType::Sensor => {
-                resource_driver
-                    .sensor_mgr
-                    .read()
-                    .expect("read sensor mgr fail")
-                    .as_ref()
-                    .expect("get sensor mgr fail")
-                    .set_alive(false);
+            .expect("read sensor mgr fail")
+            .clone();
+        // unsubscribe only removes this driver, the new driver keeps its subscription
+        if let Some(sensor_mgr) = sensor_mgr {
+            if sensor_mgr.is_owned_by(resource_driver.id()) {
+                sensor_mgr.set_alive(false);
             }
-            ResourceType::Actor => {
-                resource_driver
-                    .actor_mgr
-                    .read()
-                    .expect("read actor mgr fail")
-                    .as_ref()
-                    .expect("get actor mgr fail")
-                    .set_alive(false);
-            }
-            ResourceType::Hybrid => {
-                resource_driver
-                    .sensor_mgr
-                    .read()
-                    .expect("read sensor mgr fail")
-                    .as_ref()
-                    .expect("get sensor mgr fail")
-                    .set_alive(false);
-                resource_driver
-                    .actor_mgr
-                    .read()
-                    .expect("read actor mgr fail")
-                    .as_ref()
-                    .expect("get actor mgr fail")
-                    .set_alive(false);
+            resource_driver.unsubscribe(&get_sensor_request(&device_name));
+        }
+        let actor_mgr = resource_driver
+            .actor_mgr
+            .read()
+            .expect("read actor mgr fail")
+            .clone();
+        if let Some(actor_mgr) = actor_mgr {
+            if actor_mgr.is_owned_by(resource_driver.id()) {
+                actor_mgr.set_alive(false);
             }
-        };
-
-        //todo: unsubscribe the channel request
+            resource_driver.unsubscribe(&get_actor_request(&device_name));
+        }
     }
 
     /*fn get_socket(&self) -> &TcpStream {
@@ -0,0 +1,123 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Condvar, Mutex};
+
+struct RequestQueueState<T> {
+    requests: VecDeque<T>,
+    closed: bool,
+}
+
+/// RequestQueue keeps requests of a resource driver until one of its workers takes them
+/// it is bounded as a mailbox of dispatcher, the oldest request is dropped when it is full
+pub struct RequestQueue<T> {
+    state: Mutex<RequestQueueState<T>>,
+    condvar: Condvar,
+    capacity: usize,
+    dropped: AtomicU64,
+}
+
+impl<T> RequestQueue<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            state: Mutex::new(RequestQueueState {
+                requests: VecDeque::new(),
+                closed: false,
+            }),
+            condvar: Condvar::new(),
+            capacity,
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// push a request to the back
+    /// return the oldest request if it is dropped for it, a closed queue drops request itself
+    pub fn push(&self, request: T) -> Option<T> {
+        let mut state = self.state.lock().expect("lock request queue fail");
+        if state.closed {
+            return Some(request);
+        }
+        let dropped = if state.requests.len() >= self.capacity {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            state.requests.pop_front()
+        } else {
+            None
+        };
+        state.requests.push_back(request);
+        self.condvar.notify_one();
+        dropped
+    }
+
+    /// take the oldest request, wait until there is one
+    /// return none if queue is closed
+    pub fn take(&self) -> Option<T> {
+        let mut state = self.state.lock().expect("lock request queue fail");
+        loop {
+            if state.closed {
+                return None;
+            }
+            if let Some(request) = state.requests.pop_front() {
+                return Some(request);
+            }
+            state = self.condvar.wait(state).expect("wait request queue fail");
+        }
+    }
+
+    /// close queue, requests left are discarded and waiting workers return
+    pub fn close(&self) {
+        let mut state = self.state.lock().expect("lock request queue fail");
+        state.closed = true;
+        state.requests.clear();
+        self.condvar.notify_all();
+    }
+
+    /// requests waiting in queue
+    pub fn len(&self) -> usize {
+        self.state
+            .lock()
+            .expect("lock request queue fail")
+            .requests
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// requests dropped since queue was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_request_queue() {
+        let queue = RequestQueue::new(3);
+        for i in 0..5 {
+            let dropped = queue.push(i);
+            assert_eq!(dropped, if i < 3 { None } else { Some(i - 3) });
+        }
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.dropped(), 2);
+        assert_eq!(queue.take(), Some(2));
+        assert_eq!(queue.take(), Some(3));
+        assert_eq!(queue.take(), Some(4));
+        assert!(queue.is_empty());
+
+        // a waiting worker returns after close
+        let queue = Arc::new(queue);
+        let worker = {
+            let queue = queue.clone();
+            thread::spawn(move || queue.take())
+        };
+        queue.close();
+        assert_eq!(worker.join().unwrap(), None);
+        assert_eq!(queue.push(5), Some(5));
+    }
+}
@@ -1,16 +1,22 @@
 use std::fmt::Display;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::{Arc, RwLock, Weak};
 
 use dashmap::DashSet;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 
+use common::socket::cmd_message_grp_ids::GroupId;
+use common::structs::request_policy::RequestPolicy;
+use common::structs::resource_config::{DEFAULT_MAX_VALUE_FREQ, DEFAULT_MIN_VALUE_FREQ};
 use common::structs::sensor_info::SensorInfo;
 use common::structs::state::State;
-use common::structs::time_line::SyncCondTimeLine;
+use common::structs::time_line::{FrequencyType, SyncCondTimeLine};
 use common::structs::value_type::ValueType;
 
-use crate::app::app_mgr::{SyncAppName, SyncAppNameSet};
-use crate::resource::sensor_mgr::value_thread::RwLockOptionValueThread;
+use crate::app::app_mgr::{SyncAppMgr, SyncAppName, SyncAppNameSet};
+use crate::pubsub::abstract_subscriber::SubscriberId;
+use crate::resource::sensor_mgr::value_thread::{RwLockOptionValueThread, ValueThread};
 use crate::resource::RwlockAlive;
 
 pub mod value_thread;
@@ -18,16 +24,12 @@ pub mod value_thread;
 ///sensor_mgr is a struct that manages the lifecycle of sensors.
 /// should be protected by a RwLock.
 /// resoure_driver and app_driver both can own a write lock of sensor_mgr temporarily.
-
 pub type SyncSensorMgr = Arc<SensorMgr>;
 pub type SyncSensorName = Arc<String>;
 pub type WeakSensorMgr = Weak<SensorMgr>;
 pub type RwLockOptionSyncSensorMgr = RwLock<Option<SyncSensorMgr>>;
 pub type SyncSensorNameSet = DashSet<SyncSensorName>;
 
-//todo: should add a Mutex and condvar to timeline
-// it can be a bottleneck although it only has a thread read and many thread rare write
-
 type SyncFieldNames = Arc<Vec<String>>;
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -45,6 +47,14 @@ pub struct SensorMgr {
     min_value_freq: u64,
     #[serde(default = "default_max_value_freq")]
     max_value_freq: u64,
+    #[serde(default)]
+    request_policy: RequestPolicy,
+    /// requests timed out in a row
+    #[serde(skip)]
+    timeout_num: AtomicU32,
+    /// resource driver registered it last
+    #[serde(skip)]
+    owner: RwLock<Option<SubscriberId>>,
     #[serde(skip)]
     get_value_thread: RwLockOptionValueThread,
     #[serde(skip)]
@@ -52,6 +62,9 @@ pub struct SensorMgr {
     /// share data with value thread
     #[serde(skip)]
     time_line: SyncCondTimeLine,
+    /// the latest value sent by wrapper
+    #[serde(skip)]
+    value: RwLock<Option<String>>,
 }
 
 fn default_sensor_type() -> ValueType {
@@ -67,11 +80,11 @@ fn default_is_alive() -> RwlockAlive {
 }
 
 fn default_min_value_freq() -> u64 {
-    1
+    DEFAULT_MIN_VALUE_FREQ
 }
 
 fn default_max_value_freq() -> u64 {
-    1000
+    DEFAULT_MAX_VALUE_FREQ
 }
 
 impl SensorMgr {
@@ -110,9 +123,54 @@ impl SensorMgr {
     /// set the sensor alive or not
     /// used when register or error
     pub fn set_alive(&self, alive: bool) {
+        if alive {
+            self.timeout_num.store(0, Ordering::SeqCst);
+        }
         *self.is_alive.write().expect("write is alive fail") = alive;
     }
 
+    /// get request policy
+    pub fn get_request_policy(&self) -> RequestPolicy {
+        self.request_policy
+    }
+
+    /// set resource driver serving it, see ResourceDriver::run
+    pub fn set_owner(&self, owner: SubscriberId) {
+        self.owner.write().expect("write owner fail").replace(owner);
+    }
+
+    /// whether it is served by resource driver,
+    /// a driver of a broken connection does not own it after the wrapper reconnects
+    pub fn is_owned_by(&self, driver: SubscriberId) -> bool {
+        *self.owner.read().expect("read owner fail") == Some(driver)
+    }
+
+    /// record whether a request times out after all attempts
+    /// return true if max_timeout_num requests time out in a row, then it is set not alive
+    pub fn record_request(&self, timeout: bool) -> bool {
+        if !timeout {
+            self.timeout_num.store(0, Ordering::SeqCst);
+            return false;
+        }
+        let timeout_num = self.timeout_num.fetch_add(1, Ordering::SeqCst) + 1;
+        if timeout_num >= self.request_policy.max_timeout_num {
+            self.set_alive(false);
+            return true;
+        }
+        false
+    }
+
+    /// set the latest value
+    pub fn set_value(&self, value: String) {
+        self.value.write().expect("write value fail").replace(value);
+    }
+
+    /// get the latest value
+    /// return none if no value has been sent by wrapper
+    pub fn get_value_clone(&self) -> Option<String> {
+        self.value.read().expect("read value fail").clone()
+    }
+
     /// is get value running
     pub fn is_get_value_running(&self) -> bool {
         self.get_value_thread
@@ -121,26 +179,107 @@ impl SensorMgr {
             .is_some()
     }
 
-    // todo: should with sync
     /// start get value
-    pub fn start_get_value(&self) {
-        todo!()
+    /// value thread is started once, it is kept running while any app registers
+    pub fn start_get_value(self: &Arc<Self>) {
+        let mut get_value_thread = self
+            .get_value_thread
+            .write()
+            .expect("write get value thread fail");
+        if get_value_thread.is_none() {
+            get_value_thread.replace(ValueThread::new(self));
+        }
     }
 
     /// stop get value
+    /// wait until value thread stops
     pub fn stop_get_value(&self) {
-        todo!()
+        let value_thread = self
+            .get_value_thread
+            .write()
+            .expect("write get value thread fail")
+            .take();
+        if let Some(mut value_thread) = value_thread {
+            value_thread.stop_thread();
+        }
+    }
+
+    /// add app
+    /// an app of passive mode is requested freq times per second, freq of a registered app is changed,
+    /// an app of active mode has no freq and requests by itself
+    /// value thread is started by the first app with freq
+    /// return false if freq is out of range
+    pub fn add_app(self: &Arc<Self>, app_mgr: &SyncAppMgr, freq: Option<FrequencyType>) -> bool {
+        if freq.is_some_and(|freq| !self.check_value_freq(freq as u64)) {
+            return false;
+        }
+        let app_name = app_mgr.get_app_name_clone();
+        self.apps.insert(app_name.clone(), Arc::downgrade(app_mgr));
+        if let Some(freq) = freq {
+            {
+                let (time_line, condvar) = &*self.time_line;
+                time_line
+                    .lock()
+                    .expect("lock time line fail")
+                    .insert_with_freq(app_name, freq);
+                condvar.notify_all();
+            }
+            self.start_get_value();
+        }
+        true
+    }
+
+    /// remove app
+    /// value thread is stopped when no app has freq
+    /// return false if app is not registered
+    pub fn remove_app(&self, app_name: &SyncAppName) -> bool {
+        if self.apps.remove(app_name).is_none() {
+            return false;
+        }
+        let is_time_line_empty = {
+            let (time_line, condvar) = &*self.time_line;
+            let mut time_line = time_line.lock().expect("lock time line fail");
+            if let Some(freq) = time_line.get_app_name_to_freq().get(app_name).copied() {
+                time_line.delete_with_freq(app_name.clone(), freq);
+                condvar.notify_all();
+            }
+            time_line.size() == 0
+        };
+        if is_time_line_empty {
+            self.stop_get_value();
+        }
+        true
+    }
+
+    /// get freq of app
+    pub fn get_app_freq(&self, app_name: &SyncAppName) -> Option<FrequencyType> {
+        self.time_line
+            .0
+            .lock()
+            .expect("lock time line fail")
+            .get_app_name_to_freq()
+            .get(app_name)
+            .copied()
+    }
+
+    /// get grp id of app
+    /// return none if app is not registered or has been dropped
+    pub fn get_grp_id(&self, app_name: &SyncAppName) -> Option<GroupId> {
+        self.apps
+            .get(app_name)
+            .and_then(|app_mgr| app_mgr.upgrade())
+            .map(|app_mgr| app_mgr.get_grp_id_clone())
     }
 
     /// get apps
     /// todo: may add more other function instead of use it
     pub fn get_apps(&self) -> &SyncAppNameSet {
-        todo!()
+        &self.apps
     }
 
     /// get apps names
     pub fn get_app_names_vec(&self) -> Vec<SyncAppName> {
-        todo!()
+        self.apps.iter().map(|app| app.key().clone()).collect()
     }
 
     /// generate sensor information
@@ -157,8 +296,16 @@ impl SensorMgr {
         )
     }
 
-    //todo: how to implement thread function
-    // does it need to use Weak, or just shared data ?
+    /// generate sensor information with the latest value
+    /// value is null if no value has been sent by wrapper
+    pub fn create_sensor_info_and_data(&self) -> Value {
+        let mut jo = json!(self.create_sensor_info());
+        jo["value"] = self
+            .get_value_clone()
+            .map(|value| serde_json::from_str(&value).unwrap_or(Value::String(value)))
+            .unwrap_or(Value::Null);
+        jo
+    }
 }
 
 impl Display for SensorMgr {
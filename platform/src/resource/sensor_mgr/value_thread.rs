@@ -1,50 +1,284 @@
-use std::sync::{Arc, Mutex, RwLock};
+use std::ops::Bound::{Excluded, Unbounded};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
 use std::thread;
+use std::time::{Duration, Instant};
 
-use crate::resource::sensor_mgr::SyncSensorMgr;
+use log::{info, trace};
+
+use common::socket::cmd_message_grp_ids::{CmdMessageGrpIds, GroupId};
+use common::structs::time_line::SyncCondTimeLine;
+
+use crate::pubsub::channel::get_sensor_request;
+use crate::pubsub::publisher;
+use crate::resource::sensor_mgr::{SyncSensorMgr, WeakSensorMgr};
 
 pub type RwLockOptionValueThread = RwLock<Option<ValueThread>>;
 
-pub type SyncMutexStop = Arc<Mutex<bool>>;
+pub type SyncStop = Arc<AtomicBool>;
+
+/// length of a cycle of time line in ms
+pub const CYCLE_MS: u64 = 1000;
 
 /// notion: value thread is a thread that get value from sensor
 /// its mode can be seen as a producer
+/// in every cycle it walks the time line and publishes sensory_request
+/// on sensor request channel with grp ids of apps due at that time
 /// struct type:
 /// {
-///   shared_data: Arc<???>
+///   shared_data: time line with condvar, notified when freq changes or thread stops
 ///   thread: Option<thread::JoinHandle<()>>
 /// }
 #[derive(Debug)]
 pub struct ValueThread {
-    should_stop: SyncMutexStop,
-    stopped: SyncMutexStop,
-    //sensor_mgr_weak: WeakSensorMgr,
+    should_stop: SyncStop,
+    stopped: SyncStop,
+    time_line: SyncCondTimeLine,
     thread: Option<thread::JoinHandle<()>>,
 }
 
 impl ValueThread {
     /// new a value thread
     /// it also run the thread
-    pub fn new(sensor_mgr: SyncSensorMgr) -> Self {
-        let should_stop = Arc::new(Mutex::new(false));
-        let stopped = Arc::new(Mutex::new(true));
+    /// thread holds a weak sensor mgr, so sensor mgr can be dropped while thread is running
+    pub fn new(sensor_mgr: &SyncSensorMgr) -> Self {
+        let should_stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::new(AtomicBool::new(false));
+        let time_line = sensor_mgr.get_time_line_clone();
 
-        let join_handle = thread::spawn(move || todo!());
+        let join_handle = {
+            let should_stop = should_stop.clone();
+            let stopped = stopped.clone();
+            let sensor_mgr_weak = Arc::downgrade(sensor_mgr);
+            thread::Builder::new()
+                .name(format!("value thread {}", sensor_mgr.get_sensor_name()))
+                .spawn(move || Self::run(should_stop, stopped, sensor_mgr_weak))
+                .expect("spawn value thread fail")
+        };
 
         Self {
             should_stop,
             stopped,
-            //      sensor_mgr_weak: Arc::downgrade(&sensor_mgr),
+            time_line,
             thread: Some(join_handle),
         }
     }
 
     /// how thread run
-    fn run(should_stop: SyncMutexStop, stopped: SyncMutexStop, sensor_mgr: SyncSensorMgr) {
-        todo!()
+    /// time nodes of a cycle are visited in order of time,
+    /// the thread sleeps on condvar until the next node is due,
+    /// so a change of time line is seen at once and stop does not wait for a whole cycle
+    /// it stops by itself when sensor mgr is dropped
+    fn run(should_stop: SyncStop, stopped: SyncStop, sensor_mgr_weak: WeakSensorMgr) {
+        let (sensor_name, time_line) = match sensor_mgr_weak.upgrade() {
+            Some(sensor_mgr) => (
+                sensor_mgr.get_sensor_name().clone(),
+                sensor_mgr.get_time_line_clone(),
+            ),
+            None => {
+                stopped.store(true, Ordering::SeqCst);
+                return;
+            }
+        };
+        info!("value thread of sensor {} start", sensor_name);
+        let channel = get_sensor_request(&sensor_name);
+        let (lock, condvar) = &*time_line;
+        let mut cycle_start = Instant::now();
+        // time of the last visited node in this cycle
+        let mut last_time = 0;
+        let mut guard = lock.lock().expect("lock time line fail");
+        while !should_stop.load(Ordering::SeqCst) {
+            let elapsed = cycle_start.elapsed().as_millis() as u64;
+            let next = guard
+                .get_nodes()
+                .range((Excluded(last_time), Unbounded))
+                .next()
+                .map(|(time, node)| (*time, node.app_names.clone()));
+            // every node of this cycle, including the one at CYCLE_MS, is published before the next cycle
+            if elapsed >= CYCLE_MS && !matches!(next, Some((time, _)) if time <= CYCLE_MS) {
+                cycle_start += Duration::from_millis(CYCLE_MS);
+                last_time = 0;
+                continue;
+            }
+            match next {
+                Some((time, app_names)) if time <= elapsed => {
+                    last_time = time;
+                    let grp_ids: Vec<GroupId> = match sensor_mgr_weak.upgrade() {
+                        Some(sensor_mgr) => app_names
+                            .iter()
+                            .filter_map(|app_name| sensor_mgr.get_grp_id(app_name))
+                            .collect(),
+                        None => break,
+                    };
+                    if grp_ids.is_empty() {
+                        continue;
+                    }
+                    let msg = CmdMessageGrpIds::new(
+                        Some("sensory_request".to_string()),
+                        None,
+                        Some(grp_ids),
+                    );
+                    trace!(
+                        "value thread of sensor {} at {}ms: {:?}",
+                        sensor_name,
+                        time,
+                        msg
+                    );
+                    // publish without lock of time line, so that apps are not blocked by subscribers
+                    drop(guard);
+                    publisher::publish(
+                        &channel,
+                        None,
+                        None,
+                        Arc::new(serde_json::to_string(&msg).expect("serialize msg fail")),
+                    );
+                    guard = lock.lock().expect("lock time line fail");
+                }
+                Some((time, _)) => {
+                    guard = condvar
+                        .wait_timeout(guard, Duration::from_millis(time - elapsed))
+                        .expect("wait time line fail")
+                        .0;
+                }
+                None if guard.size() == 0 => {
+                    // nothing to request, wait until an app registers
+                    guard = condvar.wait(guard).expect("wait time line fail");
+                    cycle_start = Instant::now();
+                    last_time = 0;
+                }
+                None => {
+                    guard = condvar
+                        .wait_timeout(guard, Duration::from_millis(CYCLE_MS - elapsed))
+                        .expect("wait time line fail")
+                        .0;
+                }
+            }
+        }
+        stopped.store(true, Ordering::SeqCst);
+        info!("value thread of sensor {} stop", sensor_name);
+    }
+
+    /// is thread stopped
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+
+    /// stop thread and wait for it
+    pub fn stop_thread(&mut self) {
+        {
+            // set flag with lock of time line so that the notification is not lost
+            let _guard = self.time_line.0.lock().expect("lock time line fail");
+            self.should_stop.store(true, Ordering::SeqCst);
+            self.time_line.1.notify_all();
+        }
+        // sensor mgr may be dropped by value thread itself, which cannot join itself
+        if let Some(thread) = self
+            .thread
+            .take()
+            .filter(|thread| thread.thread().id() != thread::current().id())
+        {
+            thread.join().expect("join value thread fail");
+        }
+    }
+}
+
+impl Drop for ValueThread {
+    fn drop(&mut self) {
+        if self.thread.is_some() {
+            self.stop_thread();
+        }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Display;
+    use std::sync::Mutex;
+
+    use common::SyncString;
+
+    use crate::app::app_mgr::{AppMgr, SyncAppMgr};
+    use crate::pubsub::abstract_subscriber::{self, AbstractSubscriber};
+    use crate::pubsub::subscriber::Subscriber;
+
+    use super::*;
+
+    struct RequestCounter {
+        abstract_subscriber: AbstractSubscriber,
+        grp_ids: Mutex<Vec<GroupId>>,
+    }
+
+    impl Display for RequestCounter {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "RequestCounter")
+        }
+    }
+
+    impl Subscriber for RequestCounter {
+        fn super_reference(&self) -> &AbstractSubscriber {
+            &self.abstract_subscriber
+        }
+
+        fn on_message(&self, _channel: SyncString, msg: SyncString) {
+            let msg: CmdMessageGrpIds = serde_json::from_str(&msg).unwrap();
+            self.grp_ids
+                .lock()
+                .unwrap()
+                .extend(msg.grp_ids.unwrap_or_default());
+        }
+    }
+
+    #[test]
+    fn test_value_thread_requests_per_cycle() {
+        let sensor_mgr: SyncSensorMgr =
+            Arc::new(serde_json::from_str(r#"{"name": "test_value_thread_cycle"}"#).unwrap());
+        let counter = abstract_subscriber::register(|id| RequestCounter {
+            abstract_subscriber: AbstractSubscriber::new(id),
+            grp_ids: Mutex::new(Vec::new()),
+        });
+        counter.subscribe(
+            &get_sensor_request(sensor_mgr.get_sensor_name()),
+            None,
+            None,
+        );
+
+        let app_1hz: SyncAppMgr = Arc::new(AppMgr::new(Arc::new("test_app_1hz".to_string())));
+        app_1hz.set_grp_id(1);
+        let app_4hz: SyncAppMgr = Arc::new(AppMgr::new(Arc::new("test_app_4hz".to_string())));
+        app_4hz.set_grp_id(4);
+        assert!(sensor_mgr.add_app(&app_1hz, Some(1)));
+        assert!(sensor_mgr.add_app(&app_4hz, Some(4)));
+
+        // two whole cycles, half way to the next node
+        thread::sleep(Duration::from_millis(2 * CYCLE_MS + 125));
+        sensor_mgr.stop_get_value();
+        thread::sleep(Duration::from_millis(100));
+
+        let grp_ids = counter.grp_ids.lock().unwrap().clone();
+        assert_eq!(grp_ids.iter().filter(|grp_id| **grp_id == 1).count(), 2);
+        assert_eq!(grp_ids.iter().filter(|grp_id| **grp_id == 4).count(), 8);
+        abstract_subscriber::deregister(counter.id());
+    }
+
+    #[test]
+    fn test_value_thread_stop() {
+        let sensor_mgr: SyncSensorMgr =
+            Arc::new(serde_json::from_str(r#"{"name": "test_value_thread_sensor"}"#).unwrap());
+        let mut value_thread = ValueThread::new(&sensor_mgr);
+        println!("{:?}", value_thread);
+        let time_line = sensor_mgr.get_time_line_clone();
+        time_line
+            .0
+            .lock()
+            .unwrap()
+            .insert_with_freq(Arc::new("test_value_thread_app".to_string()), 10);
+        time_line.1.notify_all();
+        thread::sleep(Duration::from_millis(150));
+        assert!(!value_thread.is_stopped());
 
-    pub fn stop_thread(&self, sensor_mgr: SyncSensorMgr) {
-        todo!()
+        let start = Instant::now();
+        value_thread.stop_thread();
+        assert!(value_thread.is_stopped());
+        assert!(start.elapsed() < Duration::from_millis(CYCLE_MS));
     }
 }
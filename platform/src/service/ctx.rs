@@ -1 +1,456 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::sync::{Arc, RwLock};
 
+use log::info;
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+use common::structs::ctx_service_config::CtxServiceConfig;
+use common::structs::ctx_service_result::CtxServiceResult;
+use common::structs::enumeration::ctx_validator::CtxValidator;
+use common::structs::sensor_data::SensorData;
+
+use crate::config::configuration::CTX_SERVER_CONFIG;
+use crate::pubsub::grp_prio_pair::PrioId;
+use crate::service::ctx::checker::{Checker, ConcChecker, EccChecker, PccChecker};
+use crate::service::ctx::context::{Context, CtxId};
+use crate::service::ctx::expr::FuncDef;
+use crate::service::ctx::pattern::{ContextPools, Pattern};
+use crate::service::ctx::rule::{link_key, CheckEnv, LinkKey, Rule};
+use crate::service::ctx::scheduler::{GeasScheduler, ImdScheduler, Scheduler};
+
+pub mod checker;
+pub mod context;
+pub mod ctx_server;
+pub mod expr;
+pub mod pattern;
+pub mod rule;
+pub mod scheduler;
+
+/// priority of ctx server on sensor channels.
+/// it is higher than DEFAULT_PRIO_ID of app driver,
+/// so sensor data goes through ctx server before it reaches app
+pub const CTX_PRIO_ID: PrioId = 1;
+
+pub type SyncCtxResource = Arc<CtxResource>;
+
+/// CTX_BASE_RESOURCE is loaded from base files of ctx server config
+static CTX_BASE_RESOURCE: Lazy<RwLock<Option<SyncCtxResource>>> = Lazy::new(|| RwLock::new(None));
+
+#[derive(Error, Debug)]
+pub enum CtxError {
+    #[error("ctx server is off")]
+    ServerOffError,
+    #[error("ctx validator {0} is not supported")]
+    UnsupportedValidatorError(String),
+    #[error("read ctx file {0} err: {1}")]
+    ReadFileError(String, std::io::Error),
+    #[error("parse ctx file {0} err: {1}")]
+    ParseFileError(String, serde_json::Error),
+    #[error("invalid ctx file {0}: {1}")]
+    InvalidFileError(String, String),
+    #[error("ctx eval err: {0}")]
+    EvalError(String),
+}
+
+/// CtxResource holds rules, bfuncs, patterns and mfuncs loaded from files.
+/// all files are JSON:
+/// rule file is an array of rules, pattern file is an array of patterns,
+/// bfunc file and mfunc file are objects mapping function name to function
+pub struct CtxResource {
+    rules: Vec<Rule>,
+    bfuncs: HashMap<String, FuncDef>,
+    patterns: Vec<Pattern>,
+    mfuncs: HashMap<String, FuncDef>,
+}
+
+fn read_json<T: DeserializeOwned>(file: &str) -> Result<T, CtxError> {
+    let content =
+        fs::read_to_string(file).map_err(|e| CtxError::ReadFileError(file.to_string(), e))?;
+    serde_json::from_str(&content).map_err(|e| CtxError::ParseFileError(file.to_string(), e))
+}
+
+impl CtxResource {
+    /// load
+    /// read all files and validate them against each other
+    pub fn load(
+        rule_file: &str,
+        bfunc_file: &str,
+        pattern_file: &str,
+        mfunc_file: &str,
+    ) -> Result<Self, CtxError> {
+        let invalid = |file: &str, msg: String| CtxError::InvalidFileError(file.to_string(), msg);
+
+        let bfuncs: HashMap<String, FuncDef> = read_json(bfunc_file)?;
+        for (name, bfunc) in &bfuncs {
+            bfunc
+                .body
+                .check_refs(&bfunc.params, &[])
+                .map_err(|e| invalid(bfunc_file, format!("bfunc '{}': {}", name, e)))?;
+        }
+
+        let mfuncs: HashMap<String, FuncDef> = read_json(mfunc_file)?;
+        for (name, mfunc) in &mfuncs {
+            mfunc
+                .body
+                .check_refs(&[String::new()], &mfunc.args)
+                .map_err(|e| invalid(mfunc_file, format!("mfunc '{}': {}", name, e)))?;
+        }
+
+        let patterns: Vec<Pattern> = read_json(pattern_file)?;
+        let mut pattern_ids = HashSet::new();
+        for pattern in &patterns {
+            if !pattern_ids.insert(pattern.id.clone()) {
+                return Err(invalid(
+                    pattern_file,
+                    format!("duplicate pattern '{}'", pattern.id),
+                ));
+            }
+            if pattern.freshness == pattern::Freshness::Number(0) {
+                return Err(invalid(
+                    pattern_file,
+                    format!("pattern '{}': freshness is 0", pattern.id),
+                ));
+            }
+            if let Some(matcher) = &pattern.matcher {
+                let mfunc = mfuncs.get(&matcher.mfunc).ok_or_else(|| {
+                    invalid(
+                        pattern_file,
+                        format!(
+                            "pattern '{}': mfunc '{}' not found",
+                            pattern.id, matcher.mfunc
+                        ),
+                    )
+                })?;
+                if let Some(arg) = mfunc
+                    .args
+                    .iter()
+                    .find(|arg| !matcher.args.contains_key(*arg))
+                {
+                    return Err(invalid(
+                        pattern_file,
+                        format!(
+                            "pattern '{}': arg '{}' of mfunc '{}' is not given",
+                            pattern.id, arg, matcher.mfunc
+                        ),
+                    ));
+                }
+            }
+        }
+
+        let rules: Vec<Rule> = read_json(rule_file)?;
+        let mut rule_ids = HashSet::new();
+        for rule in &rules {
+            if !rule_ids.insert(rule.id.clone()) {
+                return Err(invalid(rule_file, format!("duplicate rule '{}'", rule.id)));
+            }
+            rule.validate(&pattern_ids, &bfuncs)
+                .map_err(|e| invalid(rule_file, format!("rule '{}': {}", rule.id, e)))?;
+        }
+
+        Ok(Self {
+            rules,
+            bfuncs,
+            patterns,
+            mfuncs,
+        })
+    }
+
+    /// getter
+    pub fn get_rules(&self) -> &Vec<Rule> {
+        &self.rules
+    }
+
+    pub fn get_patterns(&self) -> &Vec<Pattern> {
+        &self.patterns
+    }
+}
+
+/// CtxService checks contexts of one app
+pub struct CtxService {
+    resource: SyncCtxResource,
+    validator: CtxValidator,
+    pools: ContextPools,
+    scheduler: Box<dyn Scheduler>,
+    checker: Box<dyn Checker>,
+    /// links reported last time for each rule, only new links are reported
+    reported: Vec<HashSet<LinkKey>>,
+    next_ctx_id: CtxId,
+}
+
+impl CtxService {
+    /// new
+    /// Infuse is not supported
+    pub fn new(resource: SyncCtxResource, validator: CtxValidator) -> Result<Self, CtxError> {
+        let rules = resource.get_rules();
+        let checker: Box<dyn Checker> = match validator {
+            CtxValidator::EccImd | CtxValidator::EccGeas => Box::new(EccChecker),
+            CtxValidator::PccImd | CtxValidator::PccGeas => Box::new(PccChecker::new(rules)),
+            CtxValidator::ConCImd | CtxValidator::ConCGeas => Box::new(ConcChecker::new(rules)),
+            CtxValidator::Infuse => {
+                return Err(CtxError::UnsupportedValidatorError(validator.to_string()))
+            }
+        };
+        let scheduler: Box<dyn Scheduler> = match validator {
+            CtxValidator::EccImd | CtxValidator::PccImd | CtxValidator::ConCImd => {
+                Box::new(ImdScheduler::default())
+            }
+            _ => Box::new(GeasScheduler::default()),
+        };
+        Ok(Self {
+            reported: vec![HashSet::new(); rules.len()],
+            resource,
+            validator,
+            pools: ContextPools::new(),
+            scheduler,
+            checker,
+            next_ctx_id: 0,
+        })
+    }
+
+    /// get ctx validator
+    pub fn get_ctx_validator(&self) -> CtxValidator {
+        self.validator
+    }
+
+    /// on sensor data
+    /// build a context and return inconsistencies found
+    pub fn on_sensor_data(
+        &mut self,
+        sensor_name: &str,
+        sensor_data: &SensorData,
+    ) -> Vec<CtxServiceResult> {
+        let ctx = Arc::new(Context::new(self.next_ctx_id, sensor_name, sensor_data));
+        self.next_ctx_id += 1;
+        self.on_context(ctx)
+    }
+
+    fn on_context(&mut self, ctx: context::SyncContext) -> Vec<CtxServiceResult> {
+        let resource = self.resource.clone();
+        let changes = pattern::gen_changes(&resource.patterns, &resource.mfuncs, &self.pools, &ctx);
+
+        let mut incs = Vec::new();
+        for change in changes {
+            if self.scheduler.check_before(&change) {
+                incs.extend(self.check());
+            }
+            change.apply(&mut self.pools);
+            if self.scheduler.push(change) {
+                incs.extend(self.check());
+            }
+        }
+        incs
+    }
+
+    /// check pending changes, return links not reported before
+    fn check(&mut self) -> Vec<CtxServiceResult> {
+        let changes = self.scheduler.take();
+        if changes.is_empty() {
+            return Vec::new();
+        }
+        let env = CheckEnv {
+            pools: &self.pools,
+            bfuncs: &self.resource.bfuncs,
+        };
+        let results = self.checker.check(&self.resource.rules, &env, &changes);
+
+        let mut incs = Vec::new();
+        for (index, result) in results {
+            let links = if result.value {
+                Vec::new()
+            } else {
+                result.links
+            };
+            let mut keys = HashSet::new();
+            for link in links {
+                let key = link_key(&link);
+                if !self.reported[index].contains(&key) {
+                    incs.push(CtxServiceResult::new(
+                        self.resource.rules[index].id.clone(),
+                        link.iter()
+                            .map(|(var, ctx)| ctx.to_link_item(var))
+                            .collect(),
+                    ));
+                }
+                keys.insert(key);
+            }
+            self.reported[index] = keys;
+        }
+        incs
+    }
+}
+
+/// load base resource
+/// called by ser_mgr_thread when ctx server is on
+pub fn load_base_resource() -> Result<(), CtxError> {
+    let resource = {
+        let config = CTX_SERVER_CONFIG
+            .lock()
+            .expect("get ctx server config fail");
+        CtxResource::load(
+            config.get_base_rule_file(),
+            config.get_base_bfunc_file(),
+            config.get_base_pattern_file(),
+            config.get_base_mfunc_file(),
+        )?
+    };
+    info!(
+        "ctx base resource load success: {} rules, {} patterns",
+        resource.rules.len(),
+        resource.patterns.len()
+    );
+    *CTX_BASE_RESOURCE
+        .write()
+        .expect("write ctx base resource fail") = Some(Arc::new(resource));
+    Ok(())
+}
+
+/// new ctx service
+/// a file not given by app falls back to the base file of ctx server
+pub fn new_ctx_service(ctx_service_config: &CtxServiceConfig) -> Result<CtxService, CtxError> {
+    let config = CTX_SERVER_CONFIG
+        .lock()
+        .expect("get ctx server config fail");
+    if !config.is_server_on() {
+        return Err(CtxError::ServerOffError);
+    }
+    let validator = match ctx_service_config.get_ctx_validator() {
+        Some(validator) => validator,
+        None => config.get_ctx_validator_type().ok_or_else(|| {
+            CtxError::UnsupportedValidatorError(config.get_ctx_validator().to_string())
+        })?,
+    };
+
+    let use_base = ctx_service_config.get_rule_file().is_none()
+        && ctx_service_config.get_bfunc_file().is_none()
+        && ctx_service_config.get_pattern_file().is_none()
+        && ctx_service_config.get_mfunc_file().is_none();
+    let base = CTX_BASE_RESOURCE
+        .read()
+        .expect("read ctx base resource fail")
+        .clone();
+    let resource = match base {
+        Some(base) if use_base => base,
+        _ => Arc::new(CtxResource::load(
+            ctx_service_config
+                .get_rule_file()
+                .unwrap_or(config.get_base_rule_file()),
+            ctx_service_config
+                .get_bfunc_file()
+                .unwrap_or(config.get_base_bfunc_file()),
+            ctx_service_config
+                .get_pattern_file()
+                .unwrap_or(config.get_base_pattern_file()),
+            ctx_service_config
+                .get_mfunc_file()
+                .unwrap_or(config.get_base_mfunc_file()),
+        )?),
+    };
+    CtxService::new(resource, validator)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use serde_json::json;
+
+    use super::*;
+
+    fn test_resource() -> SyncCtxResource {
+        let dir = std::env::temp_dir().join(format!("ctx_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, value: serde_json::Value| -> PathBuf {
+            let path = dir.join(name);
+            fs::write(&path, value.to_string()).unwrap();
+            path
+        };
+        let bfunc = write(
+            "bfunc.json",
+            json!({
+                "close": {"params": ["a", "b"], "body": {"cmp": {"op": "<", "left": {"abs": {"sub": [
+                    {"field": {"param": "a", "name": "speed"}},
+                    {"field": {"param": "b", "name": "speed"}}]}}, "right": {"const": 20}}}}
+            }),
+        );
+        let mfunc = write(
+            "mfunc.json",
+            json!({
+                "fast": {"args": ["min"], "body": {"cmp": {"op": ">=", "left": {"field": {"name": "speed"}}, "right": {"arg": "min"}}}}
+            }),
+        );
+        let pattern = write(
+            "pattern.json",
+            json!([
+                {"id": "pat_car", "sensor": "car", "freshness": {"type": "number", "value": 3}},
+                {"id": "pat_fast", "sensor": "car", "freshness": {"type": "number", "value": 3},
+                 "matcher": {"mfunc": "fast", "args": {"min": 100}}}
+            ]),
+        );
+        let rule = write(
+            "rule.json",
+            json!([
+                {"id": "rule_no_jump", "formula": {"forall": {"var": "v1", "in": "pat_fast", "formula":
+                    {"forall": {"var": "v2", "in": "pat_car", "formula":
+                        {"bfunc": {"name": "close", "params": {"a": "v1", "b": "v2"}}}}}}}},
+                {"id": "rule_not_fast", "formula": {"forall": {"var": "v1", "in": "pat_car", "formula":
+                    {"not": {"exists": {"var": "v2", "in": "pat_fast", "formula":
+                        {"bfunc": {"name": "close", "params": {"a": "v1", "b": "v2"}}}}}}}}}
+            ]),
+        );
+        let path = |p: &PathBuf| p.to_str().unwrap().to_string();
+        Arc::new(
+            CtxResource::load(&path(&rule), &path(&bfunc), &path(&pattern), &path(&mfunc)).unwrap(),
+        )
+    }
+
+    fn run(validator: CtxValidator, resource: SyncCtxResource) -> Vec<(String, Vec<CtxId>)> {
+        let mut service = CtxService::new(resource, validator).unwrap();
+        let mut incs = Vec::new();
+        for speed in [10, 30, 120, 40, 130, 20, 10, 5] {
+            let data =
+                SensorData::new_with_one_field_with_default_type("speed".to_string(), json!(speed));
+            for inc in service.on_sensor_data("car", &data) {
+                incs.push((
+                    inc.rule_id,
+                    inc.link.iter().map(|item| item.ctx_id).collect(),
+                ));
+            }
+        }
+        incs.sort();
+        incs
+    }
+
+    #[test]
+    fn test_load_sample_resource() {
+        let dir = "../resources/config/ctx";
+        let resource = CtxResource::load(
+            &format!("{}/rules.json", dir),
+            &format!("{}/bfunc.json", dir),
+            &format!("{}/patterns.json", dir),
+            &format!("{}/mfunc.json", dir),
+        )
+        .unwrap();
+        assert_eq!(resource.get_rules().len(), 2);
+        assert_eq!(resource.get_patterns().len(), 2);
+    }
+
+    #[test]
+    fn test_checkers() {
+        let resource = test_resource();
+        let ecc = run(CtxValidator::EccImd, resource.clone());
+        println!("{:?}", ecc);
+        assert!(!ecc.is_empty());
+        assert_eq!(run(CtxValidator::PccImd, resource.clone()), ecc);
+        assert_eq!(run(CtxValidator::ConCImd, resource.clone()), ecc);
+
+        let geas = run(CtxValidator::EccGeas, resource.clone());
+        println!("{:?}", geas);
+        assert_eq!(run(CtxValidator::PccGeas, resource.clone()), geas);
+        assert_eq!(run(CtxValidator::ConCGeas, resource.clone()), geas);
+
+        assert!(CtxService::new(resource, CtxValidator::Infuse).is_err());
+    }
+}
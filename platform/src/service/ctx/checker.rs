@@ -0,0 +1,302 @@
+use std::collections::{BTreeMap, HashSet};
+use std::thread;
+
+use log::error;
+
+use crate::service::ctx::context::{CtxId, SyncContext};
+use crate::service::ctx::pattern::{ChangeType, ContextChange, PatternId};
+use crate::service::ctx::rule::{
+    combine_exists, combine_forall, CheckEnv, EvalResult, Formula, Link, Rule,
+};
+use crate::service::ctx::CtxError;
+
+/// RuleResult is the result of rule rules[index]
+pub type RuleResult = (usize, EvalResult);
+
+/// Checker evaluates rules after context changes are applied to pools
+pub trait Checker: Send {
+    /// check
+    /// return results of rules evaluated in this check,
+    /// a rule missing in results is unchanged since the last check
+    fn check(
+        &mut self,
+        rules: &[Rule],
+        env: &CheckEnv,
+        changes: &[ContextChange],
+    ) -> Vec<RuleResult>;
+}
+
+/// top quantifier of a rule, which is split into branches by PCC and ConC
+struct TopQuantifier {
+    var: String,
+    pattern: PatternId,
+    formula: Formula,
+    is_forall: bool,
+    inner_patterns: HashSet<PatternId>,
+}
+
+struct RuleInfo {
+    patterns: HashSet<PatternId>,
+    top: Option<TopQuantifier>,
+}
+
+impl RuleInfo {
+    fn new(rule: &Rule) -> Self {
+        let top = match &rule.formula {
+            Formula::Forall {
+                var,
+                pattern,
+                formula,
+            }
+            | Formula::Exists {
+                var,
+                pattern,
+                formula,
+            } => {
+                let mut inner_patterns = HashSet::new();
+                formula.patterns(&mut inner_patterns);
+                Some(TopQuantifier {
+                    var: var.clone(),
+                    pattern: pattern.clone(),
+                    formula: (**formula).clone(),
+                    is_forall: matches!(rule.formula, Formula::Forall { .. }),
+                    inner_patterns,
+                })
+            }
+            _ => None,
+        };
+        Self {
+            patterns: rule.patterns(),
+            top,
+        }
+    }
+
+    fn is_affected(&self, changed: &HashSet<&PatternId>) -> bool {
+        self.patterns
+            .iter()
+            .any(|pattern| changed.contains(pattern))
+    }
+}
+
+fn changed_patterns(changes: &[ContextChange]) -> HashSet<&PatternId> {
+    changes.iter().map(|change| &change.pattern_id).collect()
+}
+
+impl TopQuantifier {
+    fn eval_branch(&self, env: &CheckEnv, ctx: &SyncContext) -> Result<EvalResult, CtxError> {
+        self.formula
+            .eval(env, &Link::from([(self.var.clone(), ctx.clone())]), false)
+    }
+
+    fn combine<'a>(
+        &self,
+        branches: impl IntoIterator<Item = (&'a SyncContext, &'a EvalResult)>,
+    ) -> EvalResult {
+        if self.is_forall {
+            combine_forall(&self.var, branches, false)
+        } else {
+            combine_exists(&self.var, branches, false)
+        }
+    }
+
+    fn pool<'a>(&self, env: &'a CheckEnv) -> &'a [SyncContext] {
+        env.pools
+            .get(&self.pattern)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+}
+
+/// EccChecker re-evaluates every rule entirely in each check
+pub struct EccChecker;
+
+impl Checker for EccChecker {
+    fn check(
+        &mut self,
+        rules: &[Rule],
+        env: &CheckEnv,
+        _changes: &[ContextChange],
+    ) -> Vec<RuleResult> {
+        rules
+            .iter()
+            .enumerate()
+            .filter_map(|(index, rule)| match rule.eval(env) {
+                Ok(result) => Some((index, result)),
+                Err(e) => {
+                    error!("ecc check rule {} fail: {}", rule.id, e);
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// PccChecker only evaluates rules affected by changes.
+/// results of every context of the top quantifier are cached,
+/// so a change of the top pattern only evaluates the added contexts
+pub struct PccChecker {
+    infos: Vec<RuleInfo>,
+    caches: Vec<Option<BTreeMap<CtxId, (SyncContext, EvalResult)>>>,
+}
+
+impl PccChecker {
+    pub fn new(rules: &[Rule]) -> Self {
+        Self {
+            infos: rules.iter().map(RuleInfo::new).collect(),
+            caches: rules.iter().map(|_| None).collect(),
+        }
+    }
+
+    fn check_rule(
+        &mut self,
+        index: usize,
+        rule: &Rule,
+        env: &CheckEnv,
+        changes: &[ContextChange],
+        changed: &HashSet<&PatternId>,
+    ) -> Result<EvalResult, CtxError> {
+        let info = &self.infos[index];
+        let top = match &info.top {
+            Some(top) => top,
+            None => return rule.eval(env),
+        };
+
+        let cache = &mut self.caches[index];
+        match cache {
+            Some(branches) if !top.inner_patterns.iter().any(|p| changed.contains(p)) => {
+                let pool = top.pool(env);
+                for change in changes.iter().filter(|c| c.pattern_id == top.pattern) {
+                    let id = change.ctx.get_id();
+                    match change.change_type {
+                        ChangeType::Add if pool.iter().any(|ctx| ctx.get_id() == id) => {
+                            let result = top.eval_branch(env, &change.ctx)?;
+                            branches.insert(id, (change.ctx.clone(), result));
+                        }
+                        ChangeType::Add => {}
+                        ChangeType::Del => {
+                            branches.remove(&id);
+                        }
+                    }
+                }
+            }
+            _ => {
+                let mut branches = BTreeMap::new();
+                for ctx in top.pool(env) {
+                    branches.insert(ctx.get_id(), (ctx.clone(), top.eval_branch(env, ctx)?));
+                }
+                *cache = Some(branches);
+            }
+        }
+
+        let branches = cache.as_ref().expect("pcc cache is none");
+        Ok(top.combine(branches.values().map(|(ctx, result)| (ctx, result))))
+    }
+}
+
+impl Checker for PccChecker {
+    fn check(
+        &mut self,
+        rules: &[Rule],
+        env: &CheckEnv,
+        changes: &[ContextChange],
+    ) -> Vec<RuleResult> {
+        let changed = changed_patterns(changes);
+        let mut results = Vec::new();
+        for (index, rule) in rules.iter().enumerate() {
+            if self.caches[index].is_some() && !self.infos[index].is_affected(&changed) {
+                continue;
+            }
+            match self.check_rule(index, rule, env, changes, &changed) {
+                Ok(result) => results.push((index, result)),
+                Err(e) => {
+                    // drop cache so that the rule is evaluated entirely next time
+                    self.caches[index] = None;
+                    error!("pcc check rule {} fail: {}", rule.id, e);
+                }
+            }
+        }
+        results
+    }
+}
+
+/// ConcChecker evaluates rules affected by changes,
+/// and contexts of the top quantifier are evaluated concurrently
+pub struct ConcChecker {
+    infos: Vec<RuleInfo>,
+    checked: Vec<bool>,
+    threads: usize,
+}
+
+impl ConcChecker {
+    pub fn new(rules: &[Rule]) -> Self {
+        let threads = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        Self {
+            infos: rules.iter().map(RuleInfo::new).collect(),
+            checked: vec![false; rules.len()],
+            threads,
+        }
+    }
+
+    fn check_rule(
+        &self,
+        index: usize,
+        rule: &Rule,
+        env: &CheckEnv,
+    ) -> Result<EvalResult, CtxError> {
+        let top = match &self.infos[index].top {
+            Some(top) => top,
+            None => return rule.eval(env),
+        };
+        let pool = top.pool(env);
+        if pool.is_empty() {
+            return Ok(top.combine(std::iter::empty()));
+        }
+
+        let chunk_size = pool.len().div_ceil(self.threads);
+        let results = thread::scope(|scope| {
+            let handles: Vec<_> = pool
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|ctx| top.eval_branch(env, ctx))
+                            .collect::<Result<Vec<_>, _>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("join conc check thread fail"))
+                .collect::<Result<Vec<_>, _>>()
+        })?;
+        Ok(top.combine(pool.iter().zip(results.iter().flatten())))
+    }
+}
+
+impl Checker for ConcChecker {
+    fn check(
+        &mut self,
+        rules: &[Rule],
+        env: &CheckEnv,
+        changes: &[ContextChange],
+    ) -> Vec<RuleResult> {
+        let changed = changed_patterns(changes);
+        let mut results = Vec::new();
+        for (index, rule) in rules.iter().enumerate() {
+            if self.checked[index] && !self.infos[index].is_affected(&changed) {
+                continue;
+            }
+            match self.check_rule(index, rule, env) {
+                Ok(result) => {
+                    self.checked[index] = true;
+                    results.push((index, result));
+                }
+                Err(e) => error!("conc check rule {} fail: {}", rule.id, e),
+            }
+        }
+        results
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+
+use common::structs::ctx_service_result::CtxLinkItem;
+use common::structs::sensor_data::SensorData;
+
+pub type CtxId = u64;
+pub type SyncContext = Arc<Context>;
+
+/// field name of context timestamp, can be used in bfunc and mfunc
+pub const TIMESTAMP_FIELD: &str = "_timestamp";
+/// field name of context sensor name, can be used in bfunc and mfunc
+pub const SENSOR_FIELD: &str = "_sensor";
+
+/// Context is built from one sensor data received by ctx service
+#[derive(Debug, Clone, PartialEq)]
+pub struct Context {
+    id: CtxId,
+    sensor_name: String,
+    fields: HashMap<String, Value>,
+    timestamp: u64,
+}
+
+impl Context {
+    /// new
+    /// timestamp is the current time in millis
+    pub fn new(id: CtxId, sensor_name: &str, sensor_data: &SensorData) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("get system time fail")
+            .as_millis() as u64;
+        Self::new_with_timestamp(id, sensor_name, sensor_data, timestamp)
+    }
+
+    pub fn new_with_timestamp(
+        id: CtxId,
+        sensor_name: &str,
+        sensor_data: &SensorData,
+        timestamp: u64,
+    ) -> Self {
+        Self {
+            id,
+            sensor_name: sensor_name.to_string(),
+            fields: sensor_data.get_all_data().clone(),
+            timestamp,
+        }
+    }
+
+    /// getter
+    pub fn get_id(&self) -> CtxId {
+        self.id
+    }
+
+    pub fn get_sensor_name(&self) -> &str {
+        &self.sensor_name
+    }
+
+    pub fn get_timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// get field
+    /// besides fields of sensor data, _timestamp and _sensor are supported
+    pub fn get_field(&self, name: &str) -> Option<Value> {
+        match name {
+            TIMESTAMP_FIELD => Some(json!(self.timestamp)),
+            SENSOR_FIELD => Some(json!(self.sensor_name)),
+            _ => self.fields.get(name).cloned(),
+        }
+    }
+
+    /// to link item
+    /// used to report inconsistency to app
+    pub fn to_link_item(&self, var: &str) -> CtxLinkItem {
+        CtxLinkItem {
+            var: var.to_string(),
+            ctx_id: self.id,
+            sensor_name: self.sensor_name.clone(),
+            fields: self.fields.clone(),
+            timestamp: self.timestamp,
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use std::fmt::Display;
+use std::sync::{Arc, Mutex};
+
+use dashmap::DashSet;
+use log::{info, trace};
+
+use common::socket::cmd_message_grp_ids::GroupId;
+use common::structs::enumeration::sensor_data_type::SensorDataType;
+use common::structs::sensor_data::SensorData;
+use common::SyncString;
+
+use crate::app::app_mgr::SyncAppName;
+use crate::pubsub::abstract_subscriber::{AbstractSubscriber, SubscriberId};
+use crate::pubsub::channel::{get_sensor, SENSOR_SUFFIX};
+use crate::pubsub::grp_prio_pair::GrpPrioPair;
+use crate::pubsub::subscriber::{Interception, Subscriber};
+use crate::pubsub::{abstract_subscriber, publisher};
+use crate::service::ctx::{CtxService, CTX_PRIO_ID};
+
+pub type SyncAppCtxServer = Arc<AppCtxServer>;
+
+/// AppCtxServer is the ctx server of one app.
+/// it intercepts sensor channels in the group of app at CTX_PRIO_ID,
+/// checks sensor data and passes it with inconsistencies to app
+pub struct AppCtxServer {
+    abstract_subscriber: AbstractSubscriber,
+    app_name: SyncAppName,
+    grp_id: GroupId,
+    sensors: DashSet<String>,
+    service: Mutex<CtxService>,
+}
+
+impl AppCtxServer {
+    fn new(id: SubscriberId, app_name: SyncAppName, grp_id: GroupId, service: CtxService) -> Self {
+        Self {
+            abstract_subscriber: AbstractSubscriber::new(id),
+            app_name,
+            grp_id,
+            sensors: DashSet::new(),
+            service: Mutex::new(service),
+        }
+    }
+
+    ///add to static set Abstract Subscriber objs
+    pub fn add_to_subscriber_objs(
+        app_name: SyncAppName,
+        grp_id: GroupId,
+        service: CtxService,
+    ) -> SyncAppCtxServer {
+        abstract_subscriber::register(|id| Self::new(id, app_name, grp_id, service))
+    }
+
+    /// get ctx service
+    pub fn get_service(&self) -> &Mutex<CtxService> {
+        &self.service
+    }
+
+    /// attach sensor
+    /// sensor data of this sensor will be checked before it reaches app
+    /// it is not attached if another interceptor is at CTX_PRIO_ID of the group
+    pub fn attach_sensor(&self, sensor_name: &str) {
+        if self.sensors.insert(sensor_name.to_string()) {
+            if !self.subscribe_as_interceptor(
+                &get_sensor(sensor_name),
+                Some(self.grp_id),
+                Some(CTX_PRIO_ID),
+            ) {
+                self.sensors.remove(sensor_name);
+                return;
+            }
+            trace!(
+                "app {} ctx server attach sensor {}",
+                self.app_name,
+                sensor_name
+            );
+        }
+    }
+
+    /// detach sensor
+    pub fn detach_sensor(&self, sensor_name: &str) {
+        if self.sensors.remove(sensor_name).is_some() {
+            self.unsubscribe(&get_sensor(sensor_name));
+            trace!(
+                "app {} ctx server detach sensor {}",
+                self.app_name,
+                sensor_name
+            );
+        }
+    }
+
+    /// close
+    /// detach all sensors and leave subscriber objs, used when ctx service is stopped
+    pub fn close(&self) {
+        self.detach_all();
+        abstract_subscriber::deregister(self.id());
+    }
+
+    /// detach all sensors
+    pub fn detach_all(&self) {
+        let sensors: Vec<String> = self.sensors.iter().map(|s| s.key().clone()).collect();
+        for sensor_name in sensors {
+            self.detach_sensor(&sensor_name);
+        }
+    }
+}
+
+impl Display for AppCtxServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "AppCtxServer({})", self.app_name)
+    }
+}
+
+impl Subscriber for AppCtxServer {
+    fn super_reference(&self) -> &AbstractSubscriber {
+        &self.abstract_subscriber
+    }
+
+    /// check sensor data, then pass it and inconsistencies on to lower priorities of the group
+    /// inconsistencies are passed on before sensor data
+    fn intercept(&self, channel: SyncString, msg: SyncString) -> Interception {
+        let sensor_name = channel.strip_suffix(SENSOR_SUFFIX).unwrap_or(&channel);
+
+        // failed request such as a timeout is not a context, just pass it
+        let incs = match serde_json::from_str::<SensorData>(&msg) {
+            Ok(sensor_data) if sensor_data.get_sensor_data_type() == SensorDataType::Msg => self
+                .service
+                .lock()
+                .expect("lock ctx service fail")
+                .on_sensor_data(sensor_name, &sensor_data),
+            _ => Vec::new(),
+        };
+
+        let grp_prio_pair = GrpPrioPair::new(self.grp_id, CTX_PRIO_ID);
+        for inc in incs {
+            let inc_msg = serde_json::to_string(&inc.to_sensor_data()).expect("serialize inc fail");
+            info!("[CtxServer -> {}]: {}", self.app_name, inc_msg);
+            publisher::pass_on(&channel, grp_prio_pair, Arc::new(inc_msg));
+        }
+        Interception::Pass
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::{channel, Sender};
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use common::structs::ctx_service_result::CtxServiceResult;
+    use common::structs::enumeration::ctx_validator::CtxValidator;
+
+    use crate::service::ctx::CtxResource;
+
+    use super::*;
+
+    struct Catcher {
+        abstract_subscriber: AbstractSubscriber,
+        sender: Mutex<Sender<String>>,
+    }
+
+    impl Display for Catcher {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "Catcher")
+        }
+    }
+
+    impl Subscriber for Catcher {
+        fn super_reference(&self) -> &AbstractSubscriber {
+            &self.abstract_subscriber
+        }
+
+        fn on_message(&self, _channel: SyncString, msg: SyncString) {
+            self.sender.lock().unwrap().send(msg.to_string()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_ctx_server_intercept() {
+        let dir = "../resources/config/ctx";
+        let resource = CtxResource::load(
+            &format!("{}/rules.json", dir),
+            &format!("{}/bfunc.json", dir),
+            &format!("{}/patterns.json", dir),
+            &format!("{}/mfunc.json", dir),
+        )
+        .unwrap();
+        let service = CtxService::new(Arc::new(resource), CtxValidator::EccImd).unwrap();
+        let grp_id = 1000;
+        let ctx_server =
+            AppCtxServer::add_to_subscriber_objs(Arc::new("app".to_string()), grp_id, service);
+        ctx_server.attach_sensor("YellowCar");
+        assert!(
+            crate::pubsub::channel::get_channel(&get_sensor("YellowCar"))
+                .is_interceptor(ctx_server.id())
+        );
+
+        let (sender, receiver) = channel();
+        let catcher = abstract_subscriber::register(|id| Catcher {
+            abstract_subscriber: AbstractSubscriber::new(id),
+            sender: Mutex::new(sender),
+        });
+        catcher.subscribe(&get_sensor("YellowCar"), Some(grp_id), None);
+
+        let msg = json!({"speed": 150, "longitude": 0, "latitude": 0}).to_string();
+        publisher::publish(
+            &get_sensor("YellowCar"),
+            Some(grp_id),
+            None,
+            Arc::new(msg.clone()),
+        );
+
+        let mut received: Vec<String> = (0..2)
+            .map(|_| receiver.recv_timeout(Duration::from_secs(3)).unwrap())
+            .collect();
+        received.sort_by_key(|m| m != &msg);
+        println!("{:?}", received);
+        assert_eq!(received[0], msg);
+        let inc = CtxServiceResult::from_sensor_data(&serde_json::from_str(&received[1]).unwrap())
+            .unwrap();
+        assert_eq!(inc.rule_id, "rule_yellow_speed");
+
+        ctx_server.close();
+        assert!(abstract_subscriber::deregister(catcher.id()));
+    }
+}
@@ -0,0 +1,321 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use common::structs::enumeration::compare_type::CompareType;
+use common::util::util::distance;
+
+use crate::service::ctx::context::SyncContext;
+use crate::service::ctx::CtxError;
+
+/// FieldRef refers to a field of a context bound to a param
+/// param is omitted in mfunc because mfunc only sees one context
+#[derive(Debug, Clone, Deserialize)]
+pub struct FieldRef {
+    #[serde(default)]
+    pub param: String,
+    pub name: String,
+}
+
+/// Expr is the expression language of bfunc and mfunc files
+/// e.g. {"cmp": {"op": ">", "left": {"field": {"param": "a", "name": "speed"}}, "right": {"const": 100}}}
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Expr {
+    Const(Value),
+    Field(FieldRef),
+    Arg(String),
+    Cmp {
+        op: CompareType,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    Add(Vec<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Vec<Expr>),
+    Div(Box<Expr>, Box<Expr>),
+    Abs(Box<Expr>),
+    Distance(Vec<Expr>, Vec<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+}
+
+/// Scope is what an expression can see while evaluating
+pub struct Scope<'a> {
+    pub contexts: &'a HashMap<String, SyncContext>,
+    pub args: &'a HashMap<String, Value>,
+}
+
+fn to_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(string) => string.parse::<f64>().ok(),
+        _ => None,
+    }
+}
+
+fn to_bool(value: &Value) -> Result<bool, CtxError> {
+    value
+        .as_bool()
+        .ok_or_else(|| CtxError::EvalError(format!("{} is not a bool", value)))
+}
+
+fn number(value: &Value) -> Result<f64, CtxError> {
+    to_f64(value).ok_or_else(|| CtxError::EvalError(format!("{} is not a number", value)))
+}
+
+fn value_eq(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Number(_), _) | (_, Value::Number(_)) => match (to_f64(left), to_f64(right)) {
+            (Some(l), Some(r)) => l == r,
+            _ => false,
+        },
+        _ => left == right,
+    }
+}
+
+fn value_cmp(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+        _ => to_f64(left)?.partial_cmp(&to_f64(right)?),
+    }
+}
+
+/// compare two values
+/// numbers are compared as f64, strings are compared lexicographically
+/// values that cannot be ordered never satisfy GT, GE, LT or LE
+pub fn compare(op: CompareType, left: &Value, right: &Value) -> bool {
+    match op {
+        CompareType::EQ => value_eq(left, right),
+        CompareType::NE => !value_eq(left, right),
+        CompareType::GT => value_cmp(left, right) == Some(Ordering::Greater),
+        CompareType::GE => matches!(
+            value_cmp(left, right),
+            Some(Ordering::Greater | Ordering::Equal)
+        ),
+        CompareType::LT => value_cmp(left, right) == Some(Ordering::Less),
+        CompareType::LE => matches!(
+            value_cmp(left, right),
+            Some(Ordering::Less | Ordering::Equal)
+        ),
+        CompareType::IN => match right {
+            Value::Array(values) => values.iter().any(|value| value_eq(left, value)),
+            _ => false,
+        },
+    }
+}
+
+impl Expr {
+    /// eval
+    /// a missing field is evaluated to null
+    pub fn eval(&self, scope: &Scope) -> Result<Value, CtxError> {
+        match self {
+            Expr::Const(value) => Ok(value.clone()),
+            Expr::Field(field_ref) => {
+                let ctx = scope.contexts.get(&field_ref.param).ok_or_else(|| {
+                    CtxError::EvalError(format!("param {} is not bound", field_ref.param))
+                })?;
+                Ok(ctx.get_field(&field_ref.name).unwrap_or(Value::Null))
+            }
+            Expr::Arg(arg) => scope
+                .args
+                .get(arg)
+                .cloned()
+                .ok_or_else(|| CtxError::EvalError(format!("arg {} is not given", arg))),
+            Expr::Cmp { op, left, right } => {
+                Ok(json!(compare(*op, &left.eval(scope)?, &right.eval(scope)?)))
+            }
+            Expr::Add(exprs) => {
+                let mut sum = 0.0;
+                for expr in exprs {
+                    sum += number(&expr.eval(scope)?)?;
+                }
+                Ok(json!(sum))
+            }
+            Expr::Sub(left, right) => Ok(json!(
+                number(&left.eval(scope)?)? - number(&right.eval(scope)?)?
+            )),
+            Expr::Mul(exprs) => {
+                let mut product = 1.0;
+                for expr in exprs {
+                    product *= number(&expr.eval(scope)?)?;
+                }
+                Ok(json!(product))
+            }
+            Expr::Div(left, right) => {
+                let divisor = number(&right.eval(scope)?)?;
+                if divisor == 0.0 {
+                    return Err(CtxError::EvalError("divide by zero".to_string()));
+                }
+                Ok(json!(number(&left.eval(scope)?)? / divisor))
+            }
+            Expr::Abs(expr) => Ok(json!(number(&expr.eval(scope)?)?.abs())),
+            Expr::Distance(left, right) => {
+                if left.len() != right.len() {
+                    return Err(CtxError::EvalError(
+                        "distance of vectors with different length".to_string(),
+                    ));
+                }
+                let mut v1 = Vec::with_capacity(left.len());
+                let mut v2 = Vec::with_capacity(right.len());
+                for (l, r) in left.iter().zip(right.iter()) {
+                    v1.push(number(&l.eval(scope)?)?);
+                    v2.push(number(&r.eval(scope)?)?);
+                }
+                Ok(json!(distance(&v1, &v2)))
+            }
+            Expr::And(exprs) => {
+                for expr in exprs {
+                    if !to_bool(&expr.eval(scope)?)? {
+                        return Ok(json!(false));
+                    }
+                }
+                Ok(json!(true))
+            }
+            Expr::Or(exprs) => {
+                for expr in exprs {
+                    if to_bool(&expr.eval(scope)?)? {
+                        return Ok(json!(true));
+                    }
+                }
+                Ok(json!(false))
+            }
+            Expr::Not(expr) => Ok(json!(!to_bool(&expr.eval(scope)?)?)),
+        }
+    }
+
+    /// check refs
+    /// make sure every field and arg of the expression is declared
+    pub fn check_refs(&self, params: &[String], args: &[String]) -> Result<(), String> {
+        match self {
+            Expr::Const(_) => Ok(()),
+            Expr::Field(field_ref) => {
+                if params.contains(&field_ref.param) {
+                    Ok(())
+                } else {
+                    Err(format!("param '{}' is not declared", field_ref.param))
+                }
+            }
+            Expr::Arg(arg) => {
+                if args.contains(arg) {
+                    Ok(())
+                } else {
+                    Err(format!("arg '{}' is not declared", arg))
+                }
+            }
+            Expr::Cmp { left, right, .. } | Expr::Sub(left, right) | Expr::Div(left, right) => {
+                left.check_refs(params, args)?;
+                right.check_refs(params, args)
+            }
+            Expr::Add(exprs) | Expr::Mul(exprs) | Expr::And(exprs) | Expr::Or(exprs) => exprs
+                .iter()
+                .try_for_each(|expr| expr.check_refs(params, args)),
+            Expr::Distance(left, right) => left
+                .iter()
+                .chain(right.iter())
+                .try_for_each(|expr| expr.check_refs(params, args)),
+            Expr::Abs(expr) | Expr::Not(expr) => expr.check_refs(params, args),
+        }
+    }
+}
+
+/// FuncDef is one function of bfunc file or mfunc file
+/// bfunc declares params bound to contexts by rules
+/// mfunc sees one context and declares args given by patterns
+#[derive(Debug, Clone, Deserialize)]
+pub struct FuncDef {
+    #[serde(default)]
+    pub params: Vec<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    pub body: Expr,
+}
+
+impl FuncDef {
+    /// call as bfunc
+    /// contexts maps param to context
+    pub fn call_bfunc(&self, contexts: &HashMap<String, SyncContext>) -> Result<bool, CtxError> {
+        let args = HashMap::new();
+        to_bool(&self.body.eval(&Scope {
+            contexts,
+            args: &args,
+        })?)
+    }
+
+    /// call as mfunc
+    /// return whether ctx matches
+    pub fn call_mfunc(
+        &self,
+        ctx: &SyncContext,
+        args: &HashMap<String, Value>,
+    ) -> Result<bool, CtxError> {
+        let contexts = HashMap::from([(String::new(), ctx.clone())]);
+        to_bool(&self.body.eval(&Scope {
+            contexts: &contexts,
+            args,
+        })?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use common::structs::sensor_data::SensorData;
+
+    use crate::service::ctx::context::Context;
+
+    use super::*;
+
+    #[test]
+    fn test_bfunc() {
+        let bfunc: FuncDef = serde_json::from_value(json!({
+            "params": ["a", "b"],
+            "body": {"cmp": {
+                "op": "<",
+                "left": {"abs": {"sub": [
+                    {"field": {"param": "a", "name": "speed"}},
+                    {"field": {"param": "b", "name": "speed"}}
+                ]}},
+                "right": {"const": 10}
+            }}
+        }))
+        .unwrap();
+        assert!(bfunc.body.check_refs(&bfunc.params, &bfunc.args).is_ok());
+
+        let ctx = |id, speed: Value| {
+            Arc::new(Context::new(
+                id,
+                "car",
+                &SensorData::new_with_one_field_with_default_type("speed".to_string(), speed),
+            ))
+        };
+        let contexts = HashMap::from([
+            ("a".to_string(), ctx(0, json!(50))),
+            ("b".to_string(), ctx(1, json!("55"))),
+        ]);
+        assert!(bfunc.call_bfunc(&contexts).unwrap());
+        let contexts = HashMap::from([
+            ("a".to_string(), ctx(0, json!(50))),
+            ("b".to_string(), ctx(1, json!(70))),
+        ]);
+        assert!(!bfunc.call_bfunc(&contexts).unwrap());
+    }
+
+    #[test]
+    fn test_mfunc_check_refs() {
+        let mfunc: FuncDef = serde_json::from_value(json!({
+            "args": ["colors"],
+            "body": {"cmp": {"op": "in", "left": {"field": {"name": "color"}}, "right": {"arg": "color"}}}
+        }))
+        .unwrap();
+        println!("{:?}", mfunc.body.check_refs(&[String::new()], &mfunc.args));
+        assert!(mfunc
+            .body
+            .check_refs(&[String::new()], &mfunc.args)
+            .is_err());
+    }
+}
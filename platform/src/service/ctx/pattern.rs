@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use log::warn;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::service::ctx::context::SyncContext;
+use crate::service::ctx::expr::FuncDef;
+
+pub type PatternId = String;
+/// ContextPools stores contexts of every pattern in arrival order
+pub type ContextPools = HashMap<PatternId, Vec<SyncContext>>;
+
+/// Freshness decides how long a context stays in a pattern
+/// number: keep the latest n contexts
+/// time: keep contexts received in the latest n millis
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type", content = "value")]
+pub enum Freshness {
+    Number(usize),
+    Time(u64),
+}
+
+/// Matcher calls a mfunc with extra args to filter contexts of a pattern
+#[derive(Debug, Clone, Deserialize)]
+pub struct Matcher {
+    pub mfunc: String,
+    #[serde(default)]
+    pub args: HashMap<String, Value>,
+}
+
+/// Pattern is a set of fresh contexts from one sensor
+/// without matcher every context of the sensor matches
+#[derive(Debug, Clone, Deserialize)]
+pub struct Pattern {
+    pub id: PatternId,
+    pub sensor: String,
+    pub freshness: Freshness,
+    #[serde(default)]
+    pub matcher: Option<Matcher>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeType {
+    Add,
+    Del,
+}
+
+/// ContextChange is an addition or deletion of a context in a pattern
+#[derive(Debug, Clone)]
+pub struct ContextChange {
+    pub change_type: ChangeType,
+    pub pattern_id: PatternId,
+    pub ctx: SyncContext,
+}
+
+impl ContextChange {
+    /// apply change to pools
+    pub fn apply(&self, pools: &mut ContextPools) {
+        let pool = pools.entry(self.pattern_id.clone()).or_default();
+        match self.change_type {
+            ChangeType::Add => pool.push(self.ctx.clone()),
+            ChangeType::Del => pool.retain(|ctx| ctx.get_id() != self.ctx.get_id()),
+        }
+    }
+}
+
+impl Pattern {
+    /// whether ctx matches this pattern
+    /// a mfunc that fails to evaluate is treated as not matched
+    fn is_matched(&self, mfuncs: &HashMap<String, FuncDef>, ctx: &SyncContext) -> bool {
+        if self.sensor != ctx.get_sensor_name() {
+            return false;
+        }
+        match &self.matcher {
+            None => true,
+            Some(matcher) => match mfuncs.get(&matcher.mfunc) {
+                Some(mfunc) => mfunc.call_mfunc(ctx, &matcher.args).unwrap_or_else(|e| {
+                    warn!("pattern {}: mfunc {} fail: {}", self.id, matcher.mfunc, e);
+                    false
+                }),
+                None => false,
+            },
+        }
+    }
+}
+
+/// gen changes
+/// contexts out of time freshness are deleted first,
+/// then ctx is added to every matched pattern and the oldest contexts
+/// of a full number freshness pattern are deleted before the addition
+pub fn gen_changes(
+    patterns: &[Pattern],
+    mfuncs: &HashMap<String, FuncDef>,
+    pools: &ContextPools,
+    ctx: &SyncContext,
+) -> Vec<ContextChange> {
+    let mut changes = Vec::new();
+    for pattern in patterns {
+        let pool = pools.get(&pattern.id).map(Vec::as_slice).unwrap_or(&[]);
+        let del = |old: &SyncContext| ContextChange {
+            change_type: ChangeType::Del,
+            pattern_id: pattern.id.clone(),
+            ctx: old.clone(),
+        };
+        let is_matched = pattern.is_matched(mfuncs, ctx);
+        match pattern.freshness {
+            Freshness::Time(millis) => changes.extend(
+                pool.iter()
+                    .filter(|old| ctx.get_timestamp().saturating_sub(old.get_timestamp()) > millis)
+                    .map(del),
+            ),
+            Freshness::Number(number) if is_matched && pool.len() >= number => {
+                changes.extend(pool[..pool.len() + 1 - number].iter().map(del))
+            }
+            Freshness::Number(_) => {}
+        }
+        if is_matched {
+            changes.push(ContextChange {
+                change_type: ChangeType::Add,
+                pattern_id: pattern.id.clone(),
+                ctx: ctx.clone(),
+            });
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use common::structs::sensor_data::SensorData;
+
+    use crate::service::ctx::context::Context;
+
+    use super::*;
+
+    #[test]
+    fn test_gen_changes() {
+        let patterns: Vec<Pattern> = serde_json::from_value(json!([
+            {"id": "pat_num", "sensor": "car", "freshness": {"type": "number", "value": 2}},
+            {"id": "pat_time", "sensor": "car", "freshness": {"type": "time", "value": 100}},
+        ]))
+        .unwrap();
+        let mfuncs = HashMap::new();
+        let mut pools = ContextPools::new();
+        let data = SensorData::new_without_data_with_default_type();
+
+        for (id, timestamp) in [(0, 0), (1, 50), (2, 120)] {
+            let ctx = Arc::new(Context::new_with_timestamp(id, "car", &data, timestamp));
+            let changes = gen_changes(&patterns, &mfuncs, &pools, &ctx);
+            println!("{:?}", changes);
+            changes.iter().for_each(|change| change.apply(&mut pools));
+        }
+        let ids = |pattern_id: &str| {
+            pools[pattern_id]
+                .iter()
+                .map(|ctx| ctx.get_id())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids("pat_num"), vec![1, 2]);
+        assert_eq!(ids("pat_time"), vec![1, 2]);
+    }
+}
@@ -0,0 +1,416 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::Deserialize;
+
+use crate::service::ctx::context::{CtxId, SyncContext};
+use crate::service::ctx::expr::FuncDef;
+use crate::service::ctx::pattern::{ContextPools, PatternId};
+use crate::service::ctx::CtxError;
+
+/// Link binds variables to contexts, it explains why a formula is true or false
+pub type Link = BTreeMap<String, SyncContext>;
+pub type LinkKey = Vec<(String, CtxId)>;
+
+/// max links kept by one formula, links over it are dropped
+pub const MAX_LINKS: usize = 256;
+
+/// Formula of rule file
+/// e.g. {"forall": {"var": "v1", "in": "pat_1", "formula": {"not": {"bfunc": {"name": "f", "params": {"a": "v1"}}}}}}
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Formula {
+    Forall {
+        var: String,
+        #[serde(rename = "in")]
+        pattern: PatternId,
+        formula: Box<Formula>,
+    },
+    Exists {
+        var: String,
+        #[serde(rename = "in")]
+        pattern: PatternId,
+        formula: Box<Formula>,
+    },
+    And(Box<Formula>, Box<Formula>),
+    Or(Box<Formula>, Box<Formula>),
+    Implies(Box<Formula>, Box<Formula>),
+    Not(Box<Formula>),
+    /// params maps param of bfunc to var of rule
+    Bfunc {
+        name: String,
+        params: HashMap<String, String>,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Rule {
+    pub id: String,
+    pub formula: Formula,
+}
+
+/// CheckEnv is what a formula can see while evaluating
+pub struct CheckEnv<'a> {
+    pub pools: &'a ContextPools,
+    pub bfuncs: &'a HashMap<String, FuncDef>,
+}
+
+/// EvalResult is the truth value of a formula
+/// links are only kept when value equals the wanted value of evaluation
+#[derive(Debug, Clone)]
+pub struct EvalResult {
+    pub value: bool,
+    pub links: Vec<Link>,
+}
+
+/// link key
+/// used to compare links by ctx ids
+pub fn link_key(link: &Link) -> LinkKey {
+    link.iter()
+        .map(|(var, ctx)| (var.clone(), ctx.get_id()))
+        .collect()
+}
+
+fn bind(link: &Link, var: &str, ctx: &SyncContext) -> Link {
+    let mut link = link.clone();
+    link.insert(var.to_string(), ctx.clone());
+    link
+}
+
+fn cartesian(left: &[Link], right: &[Link]) -> Vec<Link> {
+    let mut links = Vec::new();
+    for l in left {
+        for r in right {
+            if links.len() >= MAX_LINKS {
+                return links;
+            }
+            let mut link = l.clone();
+            link.extend(r.iter().map(|(var, ctx)| (var.clone(), ctx.clone())));
+            links.push(link);
+        }
+    }
+    links
+}
+
+fn union(mut left: Vec<Link>, right: Vec<Link>) -> Vec<Link> {
+    left.extend(right);
+    left.truncate(MAX_LINKS);
+    left
+}
+
+/// combine results of every context of a forall formula
+pub fn combine_forall<'a>(
+    var: &str,
+    branches: impl IntoIterator<Item = (&'a SyncContext, &'a EvalResult)>,
+    want: bool,
+) -> EvalResult {
+    let branches: Vec<_> = branches.into_iter().collect();
+    let value = branches.iter().all(|(_, result)| result.value);
+    let links = if value != want {
+        Vec::new()
+    } else if value {
+        branches
+            .iter()
+            .fold(vec![Link::new()], |links, (ctx, result)| {
+                let bound: Vec<Link> = result.links.iter().map(|l| bind(l, var, ctx)).collect();
+                cartesian(&links, &bound)
+            })
+    } else {
+        branches
+            .iter()
+            .filter(|(_, result)| !result.value)
+            .flat_map(|(ctx, result)| result.links.iter().map(|l| bind(l, var, ctx)))
+            .take(MAX_LINKS)
+            .collect()
+    };
+    EvalResult { value, links }
+}
+
+/// combine results of every context of an exists formula
+pub fn combine_exists<'a>(
+    var: &str,
+    branches: impl IntoIterator<Item = (&'a SyncContext, &'a EvalResult)>,
+    want: bool,
+) -> EvalResult {
+    let branches: Vec<_> = branches.into_iter().collect();
+    let value = branches.iter().any(|(_, result)| result.value);
+    let links = if value != want {
+        Vec::new()
+    } else if value {
+        branches
+            .iter()
+            .filter(|(_, result)| result.value)
+            .flat_map(|(ctx, result)| result.links.iter().map(|l| bind(l, var, ctx)))
+            .take(MAX_LINKS)
+            .collect()
+    } else {
+        branches
+            .iter()
+            .fold(vec![Link::new()], |links, (ctx, result)| {
+                let bound: Vec<Link> = result.links.iter().map(|l| bind(l, var, ctx)).collect();
+                cartesian(&links, &bound)
+            })
+    };
+    EvalResult { value, links }
+}
+
+impl Formula {
+    /// eval
+    /// want is the truth value whose links are needed, a rule wants false
+    pub fn eval(
+        &self,
+        env: &CheckEnv,
+        bindings: &Link,
+        want: bool,
+    ) -> Result<EvalResult, CtxError> {
+        match self {
+            Formula::Forall {
+                var,
+                pattern,
+                formula,
+            }
+            | Formula::Exists {
+                var,
+                pattern,
+                formula,
+            } => {
+                let pool = env.pools.get(pattern).map(Vec::as_slice).unwrap_or(&[]);
+                let mut results = Vec::with_capacity(pool.len());
+                for ctx in pool {
+                    results.push(formula.eval(env, &bind(bindings, var, ctx), want)?);
+                }
+                let branches = pool.iter().zip(results.iter());
+                if matches!(self, Formula::Forall { .. }) {
+                    Ok(combine_forall(var, branches, want))
+                } else {
+                    Ok(combine_exists(var, branches, want))
+                }
+            }
+            Formula::And(left, right) => {
+                let l = left.eval(env, bindings, want)?;
+                let r = right.eval(env, bindings, want)?;
+                let value = l.value && r.value;
+                let links = match (value == want, l.value, r.value) {
+                    (false, _, _) => Vec::new(),
+                    (true, true, true) => cartesian(&l.links, &r.links),
+                    (true, false, true) => l.links,
+                    (true, true, false) => r.links,
+                    (true, false, false) => union(l.links, r.links),
+                };
+                Ok(EvalResult { value, links })
+            }
+            Formula::Or(left, right) => {
+                let l = left.eval(env, bindings, want)?;
+                let r = right.eval(env, bindings, want)?;
+                Ok(Self::combine_or(l, r, want))
+            }
+            Formula::Implies(left, right) => {
+                let l = left.eval(env, bindings, !want)?;
+                let r = right.eval(env, bindings, want)?;
+                let not_l = EvalResult {
+                    value: !l.value,
+                    links: l.links,
+                };
+                Ok(Self::combine_or(not_l, r, want))
+            }
+            Formula::Not(formula) => {
+                let result = formula.eval(env, bindings, !want)?;
+                Ok(EvalResult {
+                    value: !result.value,
+                    links: result.links,
+                })
+            }
+            Formula::Bfunc { name, params } => {
+                let bfunc = env
+                    .bfuncs
+                    .get(name)
+                    .ok_or_else(|| CtxError::EvalError(format!("bfunc {} not found", name)))?;
+                let mut contexts = HashMap::new();
+                for (param, var) in params {
+                    let ctx = bindings
+                        .get(var)
+                        .ok_or_else(|| CtxError::EvalError(format!("var {} is not bound", var)))?;
+                    contexts.insert(param.clone(), ctx.clone());
+                }
+                let value = bfunc.call_bfunc(&contexts)?;
+                let links = if value == want {
+                    vec![Link::new()]
+                } else {
+                    Vec::new()
+                };
+                Ok(EvalResult { value, links })
+            }
+        }
+    }
+
+    fn combine_or(l: EvalResult, r: EvalResult, want: bool) -> EvalResult {
+        let value = l.value || r.value;
+        let links = match (value == want, l.value, r.value) {
+            (false, _, _) => Vec::new(),
+            (true, true, true) => union(l.links, r.links),
+            (true, true, false) => l.links,
+            (true, false, true) => r.links,
+            (true, false, false) => cartesian(&l.links, &r.links),
+        };
+        EvalResult { value, links }
+    }
+
+    /// patterns used by this formula
+    pub fn patterns(&self, patterns: &mut HashSet<PatternId>) {
+        match self {
+            Formula::Forall {
+                pattern, formula, ..
+            }
+            | Formula::Exists {
+                pattern, formula, ..
+            } => {
+                patterns.insert(pattern.clone());
+                formula.patterns(patterns);
+            }
+            Formula::And(left, right)
+            | Formula::Or(left, right)
+            | Formula::Implies(left, right) => {
+                left.patterns(patterns);
+                right.patterns(patterns);
+            }
+            Formula::Not(formula) => formula.patterns(patterns),
+            Formula::Bfunc { .. } => {}
+        }
+    }
+
+    /// validate
+    /// check patterns, bfuncs and vars used by this formula
+    fn validate(
+        &self,
+        vars: &mut Vec<String>,
+        patterns: &HashSet<PatternId>,
+        bfuncs: &HashMap<String, FuncDef>,
+    ) -> Result<(), String> {
+        match self {
+            Formula::Forall {
+                var,
+                pattern,
+                formula,
+            }
+            | Formula::Exists {
+                var,
+                pattern,
+                formula,
+            } => {
+                if !patterns.contains(pattern) {
+                    return Err(format!("pattern '{}' not found", pattern));
+                }
+                vars.push(var.clone());
+                let ret = formula.validate(vars, patterns, bfuncs);
+                vars.pop();
+                ret
+            }
+            Formula::And(left, right)
+            | Formula::Or(left, right)
+            | Formula::Implies(left, right) => {
+                left.validate(vars, patterns, bfuncs)?;
+                right.validate(vars, patterns, bfuncs)
+            }
+            Formula::Not(formula) => formula.validate(vars, patterns, bfuncs),
+            Formula::Bfunc { name, params } => {
+                let bfunc = bfuncs
+                    .get(name)
+                    .ok_or_else(|| format!("bfunc '{}' not found", name))?;
+                if let Some(param) = bfunc.params.iter().find(|p| !params.contains_key(*p)) {
+                    return Err(format!(
+                        "param '{}' of bfunc '{}' is not given",
+                        param, name
+                    ));
+                }
+                if let Some(var) = params.values().find(|v| !vars.contains(v)) {
+                    return Err(format!("var '{}' is not bound", var));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Rule {
+    /// all patterns used by this rule
+    pub fn patterns(&self) -> HashSet<PatternId> {
+        let mut patterns = HashSet::new();
+        self.formula.patterns(&mut patterns);
+        patterns
+    }
+
+    /// validate rule against loaded patterns and bfuncs
+    pub fn validate(
+        &self,
+        patterns: &HashSet<PatternId>,
+        bfuncs: &HashMap<String, FuncDef>,
+    ) -> Result<(), String> {
+        self.formula.validate(&mut Vec::new(), patterns, bfuncs)
+    }
+
+    /// eval
+    /// return the truth value of rule with violated links
+    pub fn eval(&self, env: &CheckEnv) -> Result<EvalResult, CtxError> {
+        self.formula.eval(env, &Link::new(), false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use common::structs::sensor_data::SensorData;
+
+    use crate::service::ctx::context::Context;
+
+    use super::*;
+
+    #[test]
+    fn test_rule_eval() {
+        let bfuncs: HashMap<String, FuncDef> = serde_json::from_value(json!({
+            "speed_ok": {"params": ["a"], "body": {"cmp": {"op": "<=", "left": {"field": {"param": "a", "name": "speed"}}, "right": {"const": 100}}}}
+        }))
+        .unwrap();
+        let rule: Rule = serde_json::from_value(json!({
+            "id": "rule_speed",
+            "formula": {"forall": {"var": "v1", "in": "pat_car", "formula": {"bfunc": {"name": "speed_ok", "params": {"a": "v1"}}}}}
+        }))
+        .unwrap();
+        assert!(rule
+            .validate(&HashSet::from(["pat_car".to_string()]), &bfuncs)
+            .is_ok());
+        assert!(rule.validate(&HashSet::new(), &bfuncs).is_err());
+
+        let pools = ContextPools::from([(
+            "pat_car".to_string(),
+            [50, 130, 160]
+                .into_iter()
+                .enumerate()
+                .map(|(id, speed)| {
+                    Arc::new(Context::new(
+                        id as CtxId,
+                        "car",
+                        &SensorData::new_with_one_field_with_default_type(
+                            "speed".to_string(),
+                            json!(speed),
+                        ),
+                    ))
+                })
+                .collect(),
+        )]);
+        let result = rule
+            .eval(&CheckEnv {
+                pools: &pools,
+                bfuncs: &bfuncs,
+            })
+            .unwrap();
+        let keys: Vec<LinkKey> = result.links.iter().map(link_key).collect();
+        println!("{:?}", keys);
+        assert!(!result.value);
+        assert_eq!(
+            keys,
+            vec![vec![("v1".to_string(), 1)], vec![("v1".to_string(), 2)]]
+        );
+    }
+}
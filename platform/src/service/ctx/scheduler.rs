@@ -0,0 +1,81 @@
+use crate::service::ctx::pattern::{ChangeType, ContextChange};
+
+/// default number of changes a GEAS scheduler buffers before checking
+pub const DEFAULT_GEAS_WINDOW: usize = 16;
+
+/// Scheduler decides when pending context changes are checked
+pub trait Scheduler: Send {
+    /// whether pending changes should be checked before this change is applied
+    fn check_before(&self, change: &ContextChange) -> bool;
+
+    /// push an applied change
+    /// return whether pending changes should be checked now
+    fn push(&mut self, change: ContextChange) -> bool;
+
+    /// take pending changes
+    fn take(&mut self) -> Vec<ContextChange>;
+}
+
+/// ImdScheduler checks every change immediately
+#[derive(Default)]
+pub struct ImdScheduler {
+    pending: Vec<ContextChange>,
+}
+
+impl Scheduler for ImdScheduler {
+    fn check_before(&self, _change: &ContextChange) -> bool {
+        false
+    }
+
+    fn push(&mut self, change: ContextChange) -> bool {
+        self.pending.push(change);
+        true
+    }
+
+    fn take(&mut self) -> Vec<ContextChange> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+/// GeasScheduler checks changes in batches.
+/// a batch is checked when it is full, or before a context added in it is deleted,
+/// so that no context leaves a pattern without being checked.
+pub struct GeasScheduler {
+    window: usize,
+    pending: Vec<ContextChange>,
+}
+
+impl GeasScheduler {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(1),
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl Default for GeasScheduler {
+    fn default() -> Self {
+        Self::new(DEFAULT_GEAS_WINDOW)
+    }
+}
+
+impl Scheduler for GeasScheduler {
+    fn check_before(&self, change: &ContextChange) -> bool {
+        change.change_type == ChangeType::Del
+            && self.pending.iter().any(|pending| {
+                pending.change_type == ChangeType::Add
+                    && pending.pattern_id == change.pattern_id
+                    && pending.ctx.get_id() == change.ctx.get_id()
+            })
+    }
+
+    fn push(&mut self, change: ContextChange) -> bool {
+        self.pending.push(change);
+        self.pending.len() >= self.window
+    }
+
+    fn take(&mut self) -> Vec<ContextChange> {
+        std::mem::take(&mut self.pending)
+    }
+}
@@ -1 +1,395 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
 
+use log::{error, info};
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use common::structs::check_info::{CheckInfo, TRACE_FILE_EXTENSION};
+use common::structs::enumeration::check_result::CheckResult;
+use common::structs::inv_service_config::InvServiceConfig;
+use common::structs::inv_service_result::InvServiceResult;
+
+use crate::config::configuration::INV_SERVER_CONFIG;
+use crate::service::inv::invariant::{infer, Invariant};
+
+pub mod invariant;
+
+#[derive(Error, Debug)]
+pub enum InvError {
+    #[error("inv server is off")]
+    ServerOffError,
+    #[error("parse invariants line {0} err: {1}")]
+    ParseError(usize, String),
+}
+
+/// Location is a check location of app: a line number and a group of variables.
+/// traces are kept until invariants are generated
+struct Location {
+    vars: Vec<String>,
+    traces: Vec<Vec<Value>>,
+    invariants: Option<Vec<Invariant>>,
+}
+
+/// InvService records traces of monitored variables of one app,
+/// generates invariants for every check location and checks new values against them.
+///
+/// variables checked together at a line form a group,
+/// so a check location is named <app>-line<N>-grp<M> like its trace file
+pub struct InvService {
+    app_name: String,
+    config: InvServiceConfig,
+    trace_dir: Option<PathBuf>,
+    monitored: BTreeSet<String>,
+    /// line number -> variables of every group, index is group
+    groups: HashMap<i32, Vec<Vec<String>>>,
+    locations: BTreeMap<(i32, i32), Location>,
+}
+
+impl InvService {
+    /// new
+    /// traces are not recorded to files if trace_dir is none
+    pub fn new(app_name: String, config: InvServiceConfig, trace_dir: Option<PathBuf>) -> Self {
+        Self {
+            app_name,
+            config,
+            trace_dir,
+            monitored: BTreeSet::new(),
+            groups: HashMap::new(),
+            locations: BTreeMap::new(),
+        }
+    }
+
+    /// get config
+    pub fn get_config(&self) -> &InvServiceConfig {
+        &self.config
+    }
+
+    /// monitor variables
+    /// return false if some name is empty or contains whitespace, nothing is monitored then
+    pub fn monitor(&mut self, names: &[String]) -> bool {
+        if names
+            .iter()
+            .any(|name| name.is_empty() || name.contains(char::is_whitespace))
+        {
+            return false;
+        }
+        self.monitored.extend(names.iter().cloned());
+        true
+    }
+
+    /// is monitored
+    /// return whether every variable is monitored
+    pub fn is_monitored(&self, names: &[String]) -> BTreeMap<String, bool> {
+        names
+            .iter()
+            .map(|name| (name.clone(), self.monitored.contains(name)))
+            .collect()
+    }
+
+    /// check values of variables at line_number
+    /// variables not monitored are ignored
+    /// return none if no variable of objs is monitored
+    pub fn check(
+        &mut self,
+        line_number: i32,
+        objs: &Map<String, Value>,
+    ) -> Option<InvServiceResult> {
+        let vars: Vec<String> = objs
+            .keys()
+            .filter(|name| self.monitored.contains(*name))
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        if vars.is_empty() {
+            return None;
+        }
+
+        let groups = self.groups.entry(line_number).or_default();
+        let group = match groups.iter().position(|group_vars| *group_vars == vars) {
+            Some(group) => group,
+            None => {
+                groups.push(vars.clone());
+                groups.len() - 1
+            }
+        } as i32;
+        let trace: Vec<Value> = vars.iter().map(|var| objs[var].clone()).collect();
+        let check_info = CheckInfo::new(self.app_name.clone(), line_number, group);
+        self.record_trace(&check_info, &vars, &trace);
+
+        let init_thro = self.config.get_init_thro();
+        let location = self
+            .locations
+            .entry((line_number, group))
+            .or_insert_with(|| Location {
+                vars,
+                traces: Vec::new(),
+                invariants: None,
+            });
+        if location.invariants.is_none() {
+            location.traces.push(trace);
+            if location.traces.len() >= init_thro {
+                let invariants = infer(&location.vars, &location.traces);
+                info!(
+                    "{} generate {} invariants from {} traces",
+                    check_info,
+                    invariants.len(),
+                    location.traces.len()
+                );
+                location.invariants = Some(invariants);
+                location.traces.clear();
+            }
+        }
+
+        let results = match &location.invariants {
+            None => location
+                .vars
+                .iter()
+                .map(|var| (var.clone(), CheckResult::InvGenerating))
+                .collect(),
+            Some(invariants) => {
+                let values: HashMap<&str, &Value> = location
+                    .vars
+                    .iter()
+                    .map(|var| (var.as_str(), &objs[var]))
+                    .collect();
+                let violated: BTreeSet<&str> = invariants
+                    .iter()
+                    .filter(|invariant| !invariant.holds(&values))
+                    .flat_map(|invariant| invariant.vars())
+                    .collect();
+                location
+                    .vars
+                    .iter()
+                    .map(|var| {
+                        let result = if violated.contains(var.as_str()) {
+                            CheckResult::InvViolated
+                        } else {
+                            CheckResult::InvPassed
+                        };
+                        (var.clone(), result)
+                    })
+                    .collect()
+            }
+        };
+        Some(InvServiceResult::new(line_number, group, results))
+    }
+
+    /// check generated
+    /// return whether invariants of every check location are generated
+    pub fn is_generated(&self) -> bool {
+        !self.locations.is_empty()
+            && self
+                .locations
+                .values()
+                .all(|location| location.invariants.is_some())
+    }
+
+    /// save invariants as text
+    /// every generated location starts with a header "# <app>-line<N>-grp<M>: x, y",
+    /// followed by one invariant per line
+    pub fn save(&self) -> String {
+        let mut content = String::new();
+        for ((line_number, group), location) in &self.locations {
+            let invariants = match &location.invariants {
+                Some(invariants) => invariants,
+                None => continue,
+            };
+            let check_info = CheckInfo::new(self.app_name.clone(), *line_number, *group);
+            content.push_str(&format!("# {}: {}\n", check_info, location.vars.join(", ")));
+            for invariant in invariants {
+                content.push_str(&format!("{}\n", invariant));
+            }
+            content.push('\n');
+        }
+        content
+    }
+
+    /// load invariants saved by save
+    /// loaded locations replace all locations of the service
+    /// return the number of loaded locations
+    pub fn load(&mut self, content: &str) -> Result<usize, InvError> {
+        let mut locations: BTreeMap<(i32, i32), Location> = BTreeMap::new();
+        let mut current = None;
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            let parse_error = |msg: String| InvError::ParseError(index + 1, msg);
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix("# ") {
+                let (name, vars) = header
+                    .split_once(':')
+                    .ok_or_else(|| parse_error(format!("{} is not a header", line)))?;
+                let check_info = CheckInfo::from_trace_file_name(&format!(
+                    "{}.{}",
+                    name.trim(),
+                    TRACE_FILE_EXTENSION
+                ))
+                .ok_or_else(|| parse_error(format!("{} is not a check location", name)))?;
+                let mut vars: Vec<String> = vars
+                    .split(',')
+                    .map(|var| var.trim().to_string())
+                    .filter(|var| !var.is_empty())
+                    .collect();
+                vars.sort();
+                let key = (check_info.line_number, check_info.group);
+                if key.1 < 0 || vars.is_empty() || locations.contains_key(&key) {
+                    return Err(parse_error(format!("invalid check location {}", name)));
+                }
+                locations.insert(
+                    key,
+                    Location {
+                        vars,
+                        traces: Vec::new(),
+                        invariants: Some(Vec::new()),
+                    },
+                );
+                current = Some(key);
+                continue;
+            }
+
+            let location = current
+                .and_then(|key| locations.get_mut(&key))
+                .ok_or_else(|| parse_error("invariant before any header".to_string()))?;
+            let invariant = line.parse::<Invariant>().map_err(parse_error)?;
+            if let Some(var) = invariant
+                .vars()
+                .into_iter()
+                .find(|var| !location.vars.iter().any(|v| v == var))
+            {
+                return Err(parse_error(format!(
+                    "{} is not a variable of location",
+                    var
+                )));
+            }
+            location
+                .invariants
+                .as_mut()
+                .expect("loaded location has no invariants")
+                .push(invariant);
+        }
+
+        let mut groups: HashMap<i32, Vec<Vec<String>>> = HashMap::new();
+        for ((line_number, group), location) in &locations {
+            let line_groups = groups.entry(*line_number).or_default();
+            if line_groups.len() <= *group as usize {
+                line_groups.resize(*group as usize + 1, Vec::new());
+            }
+            line_groups[*group as usize] = location.vars.clone();
+            self.monitored.extend(location.vars.iter().cloned());
+        }
+        let count = locations.len();
+        self.groups = groups;
+        self.locations = locations;
+        Ok(count)
+    }
+
+    /// append a trace to the trace file of check location
+    /// the first line of a trace file is the JSON array of variables,
+    /// every other line is the JSON array of their values
+    fn record_trace(&self, check_info: &CheckInfo, vars: &[String], trace: &[Value]) {
+        let trace_dir = match &self.trace_dir {
+            Some(trace_dir) => trace_dir,
+            None => return,
+        };
+        let path = trace_dir.join(check_info.get_trace_file_name());
+        let result = fs::create_dir_all(trace_dir).and_then(|_| {
+            let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+            if file.metadata()?.len() == 0 {
+                writeln!(file, "{}", Value::from(vars.to_vec()))?;
+            }
+            writeln!(file, "{}", Value::from(trace.to_vec()))
+        });
+        if let Err(e) = result {
+            error!("record trace to {:?} fail: {}", path, e);
+        }
+    }
+}
+
+/// new inv service
+/// traces are recorded in trace dir of inv server config
+pub fn new_inv_service(
+    app_name: String,
+    inv_service_config: InvServiceConfig,
+) -> Result<InvService, InvError> {
+    let config = INV_SERVER_CONFIG
+        .lock()
+        .expect("get inv server config fail");
+    if !config.is_server_on() {
+        return Err(InvError::ServerOffError);
+    }
+    let trace_dir = Some(config.get_trace_dir())
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from);
+    Ok(InvService::new(app_name, inv_service_config, trace_dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn objs(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_check_save_and_load() {
+        let trace_dir = std::env::temp_dir().join(format!("inv_test_{}", std::process::id()));
+        let mut service = InvService::new(
+            "app".to_string(),
+            InvServiceConfig::new(5),
+            Some(trace_dir.clone()),
+        );
+        assert!(service.monitor(&["x".to_string(), "y".to_string()]));
+        assert!(!service.monitor(&["a b".to_string()]));
+        assert_eq!(
+            service.is_monitored(&["x".to_string(), "z".to_string()]),
+            BTreeMap::from([("x".to_string(), true), ("z".to_string(), false)])
+        );
+        assert!(service.check(10, &objs(json!({"z": 1}))).is_none());
+
+        for i in 0..4 {
+            let result = service
+                .check(10, &objs(json!({"x": i, "y": 2 * i + 1, "z": 0})))
+                .unwrap();
+            assert_eq!(result.group, 0);
+            assert_eq!(result.results["x"], CheckResult::InvGenerating);
+            assert!(!result.results.contains_key("z"));
+        }
+        assert!(!service.is_generated());
+        let result = service.check(10, &objs(json!({"x": 4, "y": 9}))).unwrap();
+        assert_eq!(result.results["y"], CheckResult::InvPassed);
+        assert!(service.is_generated());
+
+        let result = service.check(10, &objs(json!({"x": 2, "y": 4}))).unwrap();
+        println!("{:?}", result);
+        assert!(result.is_violated());
+        let result = service.check(10, &objs(json!({"x": 3, "y": 7}))).unwrap();
+        assert!(!result.is_violated());
+        let result = service.check(10, &objs(json!({"x": 3}))).unwrap();
+        assert_eq!(result.group, 1);
+
+        let content = service.save();
+        println!("{}", content);
+        let trace = fs::read_to_string(trace_dir.join("app-line10-grp0.trace")).unwrap();
+        assert_eq!(trace.lines().count(), 8);
+
+        let mut loaded = InvService::new("app".to_string(), InvServiceConfig::default(), None);
+        assert_eq!(loaded.load(&content).unwrap(), 1);
+        assert_eq!(loaded.save(), content);
+        assert!(loaded.is_generated());
+        let result = loaded.check(10, &objs(json!({"x": 2, "y": 4}))).unwrap();
+        assert!(result.is_violated());
+        assert!(loaded.load("x in [0, 1]").is_err());
+        assert!(loaded.load("# app-line1-grp0: x\ny in [0, 1]").is_err());
+
+        fs::remove_dir_all(trace_dir).unwrap();
+    }
+}
@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use serde_json::Value;
+
+/// a non-numeric variable with at most MAX_ONE_OF distinct values gets a one-of invariant
+pub const MAX_ONE_OF: usize = 3;
+
+/// relative tolerance of equality and linear invariants
+const EPSILON: f64 = 1e-6;
+
+/// Invariant is a property that holds on every trace of a check location
+/// text forms (see Display):
+/// x == 3, x in [0, 10], x == y, y == 2 * x + 1, x one of ["a","b"]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Invariant {
+    /// min <= var <= max, a constant if min == max
+    Range { var: String, min: f64, max: f64 },
+    /// var is one of values
+    OneOf { var: String, values: Vec<Value> },
+    /// left == right
+    Equal { left: String, right: String },
+    /// left == a * right + b
+    Linear {
+        left: String,
+        right: String,
+        a: f64,
+        b: f64,
+    },
+}
+
+fn to_f64(value: &Value) -> Option<f64> {
+    value.as_f64()
+}
+
+fn approx_eq(x: f64, y: f64) -> bool {
+    (x - y).abs() <= EPSILON * x.abs().max(y.abs()).max(1.0)
+}
+
+/// fit ys = a * xs + b
+/// return none if xs is constant or some point is off the line
+fn fit_linear(xs: &[f64], ys: &[f64]) -> Option<(f64, f64)> {
+    let k = xs.iter().position(|x| *x != xs[0])?;
+    let a = (ys[k] - ys[0]) / (xs[k] - xs[0]);
+    let b = ys[0] - a * xs[0];
+    xs.iter()
+        .zip(ys.iter())
+        .all(|(x, y)| approx_eq(*y, a * x + b))
+        .then_some((a, b))
+}
+
+/// infer invariants of a check location
+/// every trace holds the values of vars in the same order
+pub fn infer(vars: &[String], traces: &[Vec<Value>]) -> Vec<Invariant> {
+    let mut invariants = Vec::new();
+    if traces.is_empty() {
+        return invariants;
+    }
+    let columns: Vec<Option<Vec<f64>>> = (0..vars.len())
+        .map(|i| traces.iter().map(|trace| to_f64(&trace[i])).collect())
+        .collect();
+
+    for (i, var) in vars.iter().enumerate() {
+        match &columns[i] {
+            Some(xs) => invariants.push(Invariant::Range {
+                var: var.clone(),
+                min: xs.iter().cloned().fold(f64::INFINITY, f64::min),
+                max: xs.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+            }),
+            None => {
+                let mut values: Vec<Value> = Vec::new();
+                for trace in traces {
+                    if !values.contains(&trace[i]) {
+                        values.push(trace[i].clone());
+                    }
+                }
+                if values.len() <= MAX_ONE_OF {
+                    invariants.push(Invariant::OneOf {
+                        var: var.clone(),
+                        values,
+                    });
+                }
+            }
+        }
+    }
+
+    // relations between numeric variables, constants are already described by ranges
+    // a linear invariant describes the later variable by the former one
+    let is_constant = |xs: &[f64]| xs.iter().all(|x| *x == xs[0]);
+    for i in 0..vars.len() {
+        for j in i + 1..vars.len() {
+            let (xs, ys) = match (&columns[i], &columns[j]) {
+                (Some(xs), Some(ys)) if !is_constant(xs) && !is_constant(ys) => (xs, ys),
+                _ => continue,
+            };
+            if xs.iter().zip(ys.iter()).all(|(x, y)| approx_eq(*x, *y)) {
+                invariants.push(Invariant::Equal {
+                    left: vars[i].clone(),
+                    right: vars[j].clone(),
+                });
+            } else if let Some((a, b)) = fit_linear(xs, ys) {
+                invariants.push(Invariant::Linear {
+                    left: vars[j].clone(),
+                    right: vars[i].clone(),
+                    a,
+                    b,
+                });
+            }
+        }
+    }
+    invariants
+}
+
+impl Invariant {
+    /// variables of the invariant
+    pub fn vars(&self) -> Vec<&str> {
+        match self {
+            Invariant::Range { var, .. } | Invariant::OneOf { var, .. } => vec![var],
+            Invariant::Equal { left, right } | Invariant::Linear { left, right, .. } => {
+                vec![left, right]
+            }
+        }
+    }
+
+    /// whether values satisfy the invariant
+    /// a missing or non-numeric value of a numeric invariant violates it
+    pub fn holds(&self, values: &HashMap<&str, &Value>) -> bool {
+        let number = |var: &str| values.get(var).and_then(|value| to_f64(value));
+        match self {
+            Invariant::Range { var, min, max } => {
+                number(var).is_some_and(|x| *min <= x && x <= *max)
+            }
+            Invariant::OneOf {
+                var,
+                values: one_of,
+            } => values
+                .get(var.as_str())
+                .is_some_and(|value| one_of.contains(value)),
+            Invariant::Equal { left, right } => match (number(left), number(right)) {
+                (Some(x), Some(y)) => approx_eq(x, y),
+                _ => false,
+            },
+            Invariant::Linear { left, right, a, b } => match (number(left), number(right)) {
+                (Some(y), Some(x)) => approx_eq(y, a * x + b),
+                _ => false,
+            },
+        }
+    }
+}
+
+impl Display for Invariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Invariant::Range { var, min, max } if min == max => write!(f, "{} == {}", var, min),
+            Invariant::Range { var, min, max } => write!(f, "{} in [{}, {}]", var, min, max),
+            Invariant::OneOf { var, values } => write!(
+                f,
+                "{} one of {}",
+                var,
+                serde_json::to_string(values).expect("serialize one of values fail")
+            ),
+            Invariant::Equal { left, right } => write!(f, "{} == {}", left, right),
+            Invariant::Linear { left, right, a, b } => {
+                write!(f, "{} == {} * {} + {}", left, a, right, b)
+            }
+        }
+    }
+}
+
+/// parse the text form written by Display
+impl FromStr for Invariant {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_f64 = |token: &str| {
+            token
+                .parse::<f64>()
+                .map_err(|_| format!("{} is not a number", token))
+        };
+        let s = s.trim();
+        let (var, rest) = s
+            .split_once(' ')
+            .ok_or_else(|| format!("{} is not an invariant", s))?;
+        let var = var.to_string();
+
+        if let Some(values) = rest.strip_prefix("one of ") {
+            let values = serde_json::from_str(values).map_err(|e| e.to_string())?;
+            return Ok(Invariant::OneOf { var, values });
+        }
+        if let Some(range) = rest.strip_prefix("in [").and_then(|r| r.strip_suffix(']')) {
+            let (min, max) = range
+                .split_once(',')
+                .ok_or_else(|| format!("{} is not a range", rest))?;
+            return Ok(Invariant::Range {
+                var,
+                min: parse_f64(min.trim())?,
+                max: parse_f64(max.trim())?,
+            });
+        }
+        let tokens: Vec<&str> = rest
+            .strip_prefix("== ")
+            .ok_or_else(|| format!("{} is not an invariant", s))?
+            .split_whitespace()
+            .collect();
+        match tokens.as_slice() {
+            [value] => match value.parse::<f64>() {
+                Ok(value) => Ok(Invariant::Range {
+                    var,
+                    min: value,
+                    max: value,
+                }),
+                Err(_) => Ok(Invariant::Equal {
+                    left: var,
+                    right: value.to_string(),
+                }),
+            },
+            [a, "*", right, "+", b] => Ok(Invariant::Linear {
+                left: var,
+                right: right.to_string(),
+                a: parse_f64(a)?,
+                b: parse_f64(b)?,
+            }),
+            _ => Err(format!("{} is not an invariant", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_infer_and_parse() {
+        let vars: Vec<String> = ["c", "mode", "x", "y", "z"]
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+        let traces: Vec<Vec<Value>> = (0..10)
+            .map(|i| {
+                vec![
+                    json!(7),
+                    json!(if i % 2 == 0 { "on" } else { "off" }),
+                    json!(i),
+                    json!(2.5 * i as f64 - 1.0),
+                    json!(i),
+                ]
+            })
+            .collect();
+        let invariants = infer(&vars, &traces);
+        for invariant in &invariants {
+            println!("{}", invariant);
+            assert_eq!(
+                invariant.to_string().parse::<Invariant>().unwrap(),
+                *invariant
+            );
+        }
+        assert!(invariants.contains(&Invariant::Range {
+            var: "c".to_string(),
+            min: 7.0,
+            max: 7.0
+        }));
+        assert!(invariants.contains(&Invariant::Equal {
+            left: "x".to_string(),
+            right: "z".to_string()
+        }));
+        assert!(invariants.contains(&Invariant::Linear {
+            left: "y".to_string(),
+            right: "x".to_string(),
+            a: 2.5,
+            b: -1.0
+        }));
+
+        let (x, y) = (json!(3), json!(6.5));
+        let values = HashMap::from([("x", &x), ("y", &y)]);
+        let linear = "y == 2.5 * x + -1".parse::<Invariant>().unwrap();
+        assert!(linear.holds(&values));
+        let range = "x in [0, 2]".parse::<Invariant>().unwrap();
+        assert!(!range.holds(&values));
+        assert!("x >= 0".parse::<Invariant>().is_err());
+    }
+}
@@ -1 +1,70 @@
+use std::sync::{Condvar, Mutex};
 
+use log::{error, info};
+use once_cell::sync::Lazy;
+
+use crate::config::configuration::CTX_SERVER_CONFIG;
+use crate::platform_ctrl;
+use crate::service::ctx;
+
+/// ser_mgr_thread is the manager of platform services.
+/// it runs until platform is shutdown.
+pub struct SerMgrThread {
+    should_stop: Mutex<bool>,
+    cvar: Condvar,
+}
+
+impl SerMgrThread {
+    fn new() -> Self {
+        Self {
+            should_stop: Mutex::new(false),
+            cvar: Condvar::new(),
+        }
+    }
+
+    pub fn run(&self) {
+        let ctx_server_on = CTX_SERVER_CONFIG
+            .lock()
+            .expect("get ctx server config fail")
+            .is_server_on();
+        info!("service manager start, ctx server on: {}", ctx_server_on);
+        // apps that give their own ctx files can still start ctx service if base files fail
+        if ctx_server_on {
+            if let Err(e) = ctx::load_base_resource() {
+                error!("ctx base resource load fail: {}", e);
+            }
+        }
+        platform_ctrl::incr_mgr_start_flag();
+
+        let mut should_stop = self.should_stop.lock().expect("lock should stop fail");
+        while !*should_stop {
+            should_stop = self.cvar.wait(should_stop).expect("wait should stop fail");
+        }
+        info!("service manager stop");
+    }
+
+    /// stop
+    /// wake up run and let it return
+    pub fn stop(&self) {
+        *self.should_stop.lock().expect("lock should stop fail") = true;
+        self.cvar.notify_all();
+    }
+}
+
+pub static SER_MGR_THREAD: Lazy<SerMgrThread> = Lazy::new(SerMgrThread::new);
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_ser_mgr_thread_stop() {
+        let ser_mgr_thread = std::sync::Arc::new(SerMgrThread::new());
+        let ser_mgr_thread_in = ser_mgr_thread.clone();
+        let handle = thread::spawn(move || ser_mgr_thread_in.run());
+        ser_mgr_thread.stop();
+        handle.join().unwrap();
+    }
+}
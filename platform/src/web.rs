@@ -0,0 +1,165 @@
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::thread::JoinHandle;
+
+use log::error;
+use once_cell::sync::Lazy;
+use serde_json::{json, Map, Value};
+
+use common::structs::enumeration::resource_type::ResourceType;
+use webserver::http::{Request, Response};
+use webserver::server::{SyncWebServer, WebServer};
+use webserver::WebError;
+
+use crate::app::app_mgr_thread::APP_MGR_THREAD;
+use crate::config::configuration::WEB_SERVER_CONFIG;
+use crate::resource::res_mgr_thread::RES_MGR_THREAD;
+
+pub mod dashboard;
+pub mod sensor_stream;
+
+/// WEB_SERVER is the running web server, none if it is off
+static WEB_SERVER: Lazy<RwLock<Option<SyncWebServer>>> = Lazy::new(|| RwLock::new(None));
+
+fn json_response(value: Value) -> Response {
+    Response::json(value.to_string())
+}
+
+/// services of all apps, or of the app given by query app_name
+fn services(request: &Request) -> Response {
+    let services: Map<String, Value> = APP_MGR_THREAD
+        .get_app_mgrs()
+        .iter()
+        .filter(|app_mgr| {
+            request
+                .get_query("app_name")
+                .is_none_or(|app_name| app_mgr.key().as_str() == app_name)
+        })
+        .map(|app_mgr| {
+            (
+                app_mgr.key().to_string(),
+                json!(app_mgr.create_all_service_info()),
+            )
+        })
+        .collect();
+    json_response(json!({ "services": services }))
+}
+
+/// overview of platform
+/// wrappers are connected resources with their liveness,
+/// apps are registered apps with frequencies of their sensors
+pub fn create_overview() -> Value {
+    let mut wrappers: Vec<Value> = RES_MGR_THREAD
+        .get_sensor_mgrs()
+        .iter()
+        .map(|sensor_mgr| {
+            json!({
+                "resource_name": sensor_mgr.key().as_str(),
+                "resource_type": ResourceType::Sensor,
+                "alive": sensor_mgr.is_alive(),
+                "apps": sensor_mgr.get_app_names_vec(),
+            })
+        })
+        .chain(RES_MGR_THREAD.get_actor_mgrs().iter().map(|actor_mgr| {
+            json!({
+                "resource_name": actor_mgr.key().as_str(),
+                "resource_type": ResourceType::Actor,
+                "alive": actor_mgr.is_alive(),
+                "apps": actor_mgr.get_app_names_vec(),
+            })
+        }))
+        .collect();
+    wrappers.sort_by(|l, r| {
+        l["resource_name"]
+            .as_str()
+            .cmp(&r["resource_name"].as_str())
+    });
+
+    let mut apps: Vec<Value> = APP_MGR_THREAD
+        .get_app_mgrs()
+        .iter()
+        .map(|app_mgr| {
+            let sensors: Vec<Value> = app_mgr
+                .get_sensor_names_vec()
+                .iter()
+                .map(|sensor_name| {
+                    let sensor_mgr = RES_MGR_THREAD.get_sensor_mgrs().get(sensor_name);
+                    json!({
+                        "sensor_name": sensor_name,
+                        "freq": sensor_mgr
+                            .as_ref()
+                            .and_then(|sensor_mgr| sensor_mgr.get_app_freq(app_mgr.key())),
+                        "alive": sensor_mgr.is_some_and(|sensor_mgr| sensor_mgr.is_alive()),
+                    })
+                })
+                .collect();
+            json!({
+                "app_name": app_mgr.key().as_str(),
+                "grp_id": app_mgr.get_grp_id_clone(),
+                "sensors": sensors,
+                "actors": app_mgr.get_actor_names_vec(),
+                "services": app_mgr.create_all_service_info(),
+            })
+        })
+        .collect();
+    apps.sort_by(|l, r| l["app_name"].as_str().cmp(&r["app_name"].as_str()));
+
+    json!({"wrappers": wrappers, "apps": apps})
+}
+
+/// create web server with routes of platform
+pub fn create_web_server(port: u16) -> Result<WebServer, WebError> {
+    Ok(WebServer::bind(port)?
+        .route("/", |_| {
+            Response::html(dashboard::render(&create_overview()))
+        })
+        .route("/api/overview", |_| json_response(create_overview()))
+        .route("/api/apps", |_| {
+            json_response(json!({"apps": APP_MGR_THREAD.create_all_app_info()}))
+        })
+        .route("/api/sensors", |_| {
+            json_response(json!({"sensors": RES_MGR_THREAD.create_all_sensor_info_and_data()}))
+        })
+        .route("/api/actors", |_| {
+            json_response(json!({"actors": RES_MGR_THREAD.create_all_actor_info()}))
+        })
+        .route("/api/services", services)
+        .route("/api/stream", sensor_stream::stream))
+}
+
+/// start
+/// start web server if it is on in config
+/// return join handle of web server thread, none if it is off or bind fails
+pub fn start() -> Option<JoinHandle<()>> {
+    let port = {
+        let config = WEB_SERVER_CONFIG
+            .lock()
+            .expect("get web server config fail");
+        if !config.is_server_on() {
+            return None;
+        }
+        config.get_port()
+    };
+    let web_server = match create_web_server(port) {
+        Ok(web_server) => Arc::new(web_server),
+        Err(e) => {
+            error!("start web server on port {} fail: {}", port, e);
+            return None;
+        }
+    };
+    *WEB_SERVER.write().expect("write web server fail") = Some(web_server.clone());
+    Some(
+        thread::Builder::new()
+            .name("web_server".to_string())
+            .spawn(move || web_server.run())
+            .expect("spawn web server thread fail"),
+    )
+}
+
+/// stop
+/// stop web server if it is running
+pub fn stop() {
+    if let Some(web_server) = WEB_SERVER.write().expect("write web server fail").take() {
+        web_server.stop();
+    }
+}
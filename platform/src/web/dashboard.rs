@@ -0,0 +1,148 @@
+use std::fmt::Write;
+
+use serde_json::Value;
+
+/// seconds between two refreshes of dashboard
+const REFRESH_SECS: u32 = 2;
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em}\
+table{border-collapse:collapse;margin-bottom:2em}\
+th,td{border:1px solid #ccc;padding:4px 10px;text-align:left}\
+th{background:#eee}.on{color:green}.off{color:red}";
+
+/// escape text put in html
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// text of a json value, string without quotes and null as "-"
+fn text(value: &Value) -> String {
+    match value {
+        Value::Null => "-".to_string(),
+        Value::String(s) => escape_html(s),
+        Value::Array(values) => values.iter().map(text).collect::<Vec<_>>().join(", "),
+        value => escape_html(&value.to_string()),
+    }
+}
+
+fn liveness(alive: &Value) -> &'static str {
+    if alive.as_bool().unwrap_or(false) {
+        r#"<span class="on">alive</span>"#
+    } else {
+        r#"<span class="off">dead</span>"#
+    }
+}
+
+/// render dashboard
+/// overview is created by web::create_overview
+pub fn render(overview: &Value) -> String {
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\">\
+<meta http-equiv=\"refresh\" content=\"{}\"><title>platform dashboard</title>\
+<style>{}</style></head><body><h1>platform dashboard</h1>",
+        REFRESH_SECS, STYLE
+    );
+
+    html.push_str("<h2>wrappers</h2><table><tr><th>resource</th><th>type</th><th>state</th><th>apps</th></tr>");
+    for wrapper in overview["wrappers"].as_array().into_iter().flatten() {
+        let _ = write!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            text(&wrapper["resource_name"]),
+            text(&wrapper["resource_type"]),
+            liveness(&wrapper["alive"]),
+            text(&wrapper["apps"]),
+        );
+    }
+    html.push_str("</table>");
+
+    html.push_str("<h2>apps</h2><table><tr><th>app</th><th>group</th><th>sensor</th><th>freq</th><th>state</th><th>actors</th></tr>");
+    for app in overview["apps"].as_array().into_iter().flatten() {
+        let sensors = app["sensors"].as_array().cloned().unwrap_or_default();
+        let rows = sensors.len().max(1);
+        let _ = write!(
+            html,
+            "<tr><td rowspan=\"{rows}\">{}</td><td rowspan=\"{rows}\">{}</td>",
+            text(&app["app_name"]),
+            text(&app["grp_id"]),
+        );
+        match sensors.first() {
+            Some(sensor) => {
+                let _ = write!(
+                    html,
+                    "<td>{}</td><td>{}</td><td>{}</td>",
+                    text(&sensor["sensor_name"]),
+                    text(&sensor["freq"]),
+                    liveness(&sensor["alive"]),
+                );
+            }
+            None => html.push_str("<td>-</td><td>-</td><td>-</td>"),
+        }
+        let _ = write!(
+            html,
+            "<td rowspan=\"{rows}\">{}</td></tr>",
+            text(&app["actors"])
+        );
+        for sensor in sensors.iter().skip(1) {
+            let _ = write!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                text(&sensor["sensor_name"]),
+                text(&sensor["freq"]),
+                liveness(&sensor["alive"]),
+            );
+        }
+    }
+    html.push_str("</table>");
+
+    html.push_str(
+        "<p>json: <a href=\"/api/overview\">overview</a> <a href=\"/api/apps\">apps</a> \
+<a href=\"/api/sensors\">sensors</a> <a href=\"/api/actors\">actors</a> \
+<a href=\"/api/services\">services</a> \
+<a href=\"/api/stream\">stream</a></p></body></html>",
+    );
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let overview = json!({
+            "wrappers": [
+                {"resource_name": "YellowCar", "resource_type": "Sensor", "alive": true, "apps": ["app<1>"]},
+                {"resource_name": "Light", "resource_type": "Actor", "alive": false, "apps": []},
+            ],
+            "apps": [
+                {"app_name": "app<1>", "grp_id": 1, "actors": [],
+                 "sensors": [{"sensor_name": "YellowCar", "freq": 5, "alive": true},
+                             {"sensor_name": "Taxi", "freq": null, "alive": false}]},
+            ],
+        });
+        let html = render(&overview);
+        println!("{}", html);
+        assert!(html.contains("app&lt;1&gt;"));
+        assert!(!html.contains("app<1>"));
+        assert!(
+            html.contains("<td>YellowCar</td><td>5</td><td><span class=\"on\">alive</span></td>")
+        );
+        assert!(html.contains("<td>Taxi</td><td>-</td><td><span class=\"off\">dead</span></td>"));
+    }
+}
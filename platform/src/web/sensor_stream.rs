@@ -0,0 +1,277 @@
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::trace;
+use serde_json::{json, Map, Value};
+
+use common::socket::cmd_message_grp_ids::CmdMessageGrpIds;
+use common::SyncString;
+use webserver::http::{Request, Response};
+
+use crate::pubsub::abstract_subscriber::{AbstractSubscriber, SubscriberId};
+use crate::pubsub::channel::{
+    get_sensor, get_sensor_request, is_pattern, matches_pattern, DEFAULT_PRIO_ID, MONITOR_GRP_ID,
+    SENSOR_SUFFIX, WILDCARD,
+};
+use crate::pubsub::subscriber::Subscriber;
+use crate::pubsub::{abstract_subscriber, publisher};
+use crate::resource::res_mgr_thread::RES_MGR_THREAD;
+
+pub type SyncSensorStream = Arc<SensorStream>;
+
+/// seconds between two keepalive comments
+const KEEPALIVE_SECS: u64 = 15;
+
+/// SensorStream is a monitor of sensor channels.
+/// it subscribes them in MONITOR_GRP_ID, so groups and priorities of apps are not touched,
+/// and passes every sensor data, filtered by fields, to a web client
+pub struct SensorStream {
+    abstract_subscriber: AbstractSubscriber,
+    /// fields to keep, none keeps all fields
+    fields: Option<HashSet<String>>,
+    sender: Sender<String>,
+}
+
+impl SensorStream {
+    fn new(id: SubscriberId, fields: Option<HashSet<String>>, sender: Sender<String>) -> Self {
+        Self {
+            abstract_subscriber: AbstractSubscriber::new(id),
+            fields,
+            sender,
+        }
+    }
+
+    ///add to static set Abstract Subscriber objs
+    pub fn add_to_subscriber_objs(
+        fields: Option<HashSet<String>>,
+        sender: Sender<String>,
+    ) -> SyncSensorStream {
+        abstract_subscriber::register(|id| Self::new(id, fields, sender))
+    }
+
+    /// attach sensors, a pattern attaches sensors matching it, including sensors added later
+    /// client receives the last data of sensors at once, if they have one
+    pub fn attach(&self, sensor_names: &[String]) {
+        for sensor_name in sensor_names {
+            self.subscribe_with_retained(
+                &get_sensor(sensor_name),
+                Some(MONITOR_GRP_ID),
+                Some(DEFAULT_PRIO_ID),
+            );
+        }
+    }
+
+    /// detach sensors
+    /// used when client is gone
+    pub fn detach(&self, sensor_names: &[String]) {
+        for sensor_name in sensor_names {
+            self.unsubscribe(&get_sensor(sensor_name));
+        }
+    }
+
+    /// filter sensor data by fields
+    /// data which is not an object is the default field
+    /// return none if no field is left
+    pub fn filter(&self, sensor_data: Value) -> Option<Value> {
+        let data = match sensor_data {
+            Value::Object(data) => data,
+            value => Map::from_iter([("default".to_string(), value)]),
+        };
+        let data: Map<String, Value> = match &self.fields {
+            Some(fields) => data
+                .into_iter()
+                .filter(|(field, _)| fields.contains(field))
+                .collect(),
+            None => data,
+        };
+        if data.is_empty() {
+            None
+        } else {
+            Some(Value::Object(data))
+        }
+    }
+}
+
+impl Display for SensorStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SensorStream({})", self.id())
+    }
+}
+
+impl Subscriber for SensorStream {
+    fn super_reference(&self) -> &AbstractSubscriber {
+        &self.abstract_subscriber
+    }
+
+    fn on_message(&self, channel: SyncString, msg: SyncString) {
+        let sensor_name = channel.strip_suffix(SENSOR_SUFFIX).unwrap_or(&channel);
+        let sensor_data =
+            serde_json::from_str(&msg).unwrap_or_else(|_| Value::String(msg.to_string()));
+        if let Some(sensor_data) = self.filter(sensor_data) {
+            // client may be gone, it is detached by stream then
+            let _ = self
+                .sender
+                .send(json!({"sensor_name": sensor_name, "sensor_data": sensor_data}).to_string());
+        }
+    }
+}
+
+/// comma separated values of query
+fn query_list(request: &Request, key: &str) -> Option<Vec<String>> {
+    request.get_query(key).map(|values| {
+        values
+            .split(',')
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+            .collect()
+    })
+}
+
+/// publish sensory_request of sensors for monitors
+fn request_sensors(sensor_names: &[String]) {
+    let msg = CmdMessageGrpIds::new(
+        Some("sensory_request".to_string()),
+        None,
+        Some(vec![MONITOR_GRP_ID]),
+    );
+    let msg: SyncString = Arc::new(serde_json::to_string(&msg).expect("serialize msg fail"));
+    for sensor_name in sensor_names {
+        publisher::publish(&get_sensor_request(sensor_name), None, None, msg.clone());
+    }
+}
+
+/// pass sensor data to client until it is gone
+fn run(
+    mut sse: webserver::sse::SseWriter,
+    receiver: Receiver<String>,
+    sensor_names: &[String],
+    period: Option<Duration>,
+) -> std::io::Result<()> {
+    let mut next_request = Instant::now();
+    let mut next_keepalive = Instant::now() + Duration::from_secs(KEEPALIVE_SECS);
+    loop {
+        let now = Instant::now();
+        if let Some(period) = period {
+            if now >= next_request {
+                request_sensors(sensor_names);
+                next_request += period;
+            }
+        }
+        if now >= next_keepalive {
+            sse.comment("keepalive")?;
+            next_keepalive = now + Duration::from_secs(KEEPALIVE_SECS);
+        }
+        let deadline = match period {
+            Some(_) => next_request.min(next_keepalive),
+            None => next_keepalive,
+        };
+        match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(data) => sse.send(Some("sensor_data"), &data)?,
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+    }
+}
+
+/// stream sensor data as server-sent events of type sensor_data
+/// query sensor_name: comma separated sensors or patterns such as Yellow*, all sensors if it is absent.
+/// sensors added later are streamed too if they match a pattern
+/// query field: comma separated fields to keep, all fields if it is absent
+/// query freq: request sensors at this frequency for the stream,
+/// without it only data requested by apps is streamed
+pub fn stream(request: &Request) -> Response {
+    let attached = query_list(request, "sensor_name").unwrap_or_else(|| vec![WILDCARD.to_string()]);
+    for sensor_name in &attached {
+        if !is_pattern(sensor_name) && !RES_MGR_THREAD.get_sensor_mgrs().contains_key(sensor_name) {
+            return Response::not_found(&format!("sensor {}", sensor_name));
+        }
+    }
+    // sensors requested at freq, patterns are resolved to sensors connected now
+    let mut sensor_names: Vec<String> = RES_MGR_THREAD
+        .get_sensor_mgrs()
+        .iter()
+        .map(|sensor_mgr| sensor_mgr.key().to_string())
+        .filter(|sensor_name| {
+            attached
+                .iter()
+                .any(|attached| matches_pattern(attached, sensor_name))
+        })
+        .collect();
+    sensor_names.sort();
+    let fields = query_list(request, "field").map(|fields| fields.into_iter().collect());
+
+    let period = match request.get_query("freq") {
+        Some(freq) => {
+            let freq = match freq.parse::<u64>() {
+                Ok(freq) => freq,
+                Err(_) => return Response::error(400, &format!("invalid freq {}", freq)),
+            };
+            for sensor_name in &sensor_names {
+                let valid = RES_MGR_THREAD
+                    .get_sensor_mgrs()
+                    .get(sensor_name)
+                    .is_some_and(|sensor_mgr| sensor_mgr.check_value_freq(freq));
+                if !valid {
+                    return Response::error(
+                        400,
+                        &format!("freq {} is out of range of sensor {}", freq, sensor_name),
+                    );
+                }
+            }
+            Some(Duration::from_millis(1000 / freq.max(1)))
+        }
+        None => None,
+    };
+
+    Response::event_stream(move |sse| {
+        let (sender, receiver) = channel();
+        let sensor_stream = SensorStream::add_to_subscriber_objs(fields, sender);
+        sensor_stream.attach(&attached);
+        trace!("{} attach sensors {:?}", sensor_stream, attached);
+        let result = run(sse, receiver, &sensor_names, period);
+        sensor_stream.detach(&attached);
+        abstract_subscriber::deregister(sensor_stream.id());
+        trace!("{} detach sensors {:?}", sensor_stream, attached);
+        result
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sensor_stream_filter() {
+        let (sender, receiver) = channel();
+        let sensor_stream = SensorStream::add_to_subscriber_objs(
+            Some(HashSet::from(["speed".to_string()])),
+            sender,
+        );
+        sensor_stream.on_message(
+            Arc::new(get_sensor("stream_car")),
+            Arc::new(r#"{"speed":1,"angle":2}"#.to_string()),
+        );
+        sensor_stream.on_message(
+            Arc::new(get_sensor("stream_car")),
+            Arc::new(r#"{"angle":3}"#.to_string()),
+        );
+        let data: Value = serde_json::from_str(&receiver.try_recv().unwrap()).unwrap();
+        println!("{}", data);
+        assert_eq!(
+            data,
+            json!({"sensor_name": "stream_car", "sensor_data": {"speed": 1}})
+        );
+        assert!(receiver.try_recv().is_err());
+
+        let (sender, _receiver) = channel();
+        let sensor_stream = SensorStream::add_to_subscriber_objs(None, sender);
+        assert_eq!(
+            sensor_stream.filter(json!("open")),
+            Some(json!({"default": "open"}))
+        );
+    }
+}
@@ -1,12 +1,12 @@
 use std::any::type_name;
 use std::fmt::Display;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::thread;
 
 use common::socket::cmd_message_grp_ids::GroupId;
 use common::SyncString;
 use platform::pubsub::grp_prio_pair::{GrpPrioPair, PrioId};
-use platform::pubsub::subscriber::Subscriber;
+use platform::pubsub::subscriber::{Interception, Subscriber};
 use platform::pubsub::*;
 
 struct TestSubscriber1 {
@@ -15,15 +15,9 @@ struct TestSubscriber1 {
 
 impl TestSubscriber1 {
     pub fn new() -> Arc<Self> {
-        let subscriber_objs = abstract_subscriber::get_objs();
-        let mut subscriber_objs = subscriber_objs.write().expect("get subscriber objs failed");
-        let subscriber = Arc::new(Self {
-            abstract_subscriber: abstract_subscriber::AbstractSubscriber::new(
-                subscriber_objs.len() as i32,
-            ),
-        });
-        subscriber_objs.push(subscriber.clone());
-        subscriber
+        abstract_subscriber::register(|id| Self {
+            abstract_subscriber: abstract_subscriber::AbstractSubscriber::new(id),
+        })
     }
 }
 
@@ -34,8 +28,8 @@ impl Display for TestSubscriber1 {
 }
 
 impl Subscriber for TestSubscriber1 {
-    fn id(&self) -> i32 {
-        self.abstract_subscriber.id()
+    fn super_reference(&self) -> &abstract_subscriber::AbstractSubscriber {
+        &self.abstract_subscriber
     }
 
     fn get_grp_prio_pair(&self, channel: &str) -> Option<GrpPrioPair> {
@@ -67,15 +61,9 @@ struct TestSubscriber2 {
 
 impl TestSubscriber2 {
     pub fn new() -> Arc<Self> {
-        let subscriber_objs = abstract_subscriber::get_objs();
-        let mut subscriber_objs = subscriber_objs.write().expect("get subscriber objs failed");
-        let subscriber = Arc::new(Self {
-            abstract_subscriber: abstract_subscriber::AbstractSubscriber::new(
-                subscriber_objs.len() as i32,
-            ),
-        });
-        subscriber_objs.push(subscriber.clone());
-        subscriber
+        abstract_subscriber::register(|id| Self {
+            abstract_subscriber: abstract_subscriber::AbstractSubscriber::new(id),
+        })
     }
 }
 
@@ -86,8 +74,8 @@ impl Display for TestSubscriber2 {
 }
 
 impl Subscriber for TestSubscriber2 {
-    fn id(&self) -> i32 {
-        self.abstract_subscriber.id()
+    fn super_reference(&self) -> &abstract_subscriber::AbstractSubscriber {
+        &self.abstract_subscriber
     }
 
     fn get_grp_prio_pair(&self, channel: &str) -> Option<GrpPrioPair> {
@@ -119,15 +107,9 @@ struct TestSubscriber3 {
 
 impl TestSubscriber3 {
     pub fn new() -> Arc<Self> {
-        let subscriber_objs = abstract_subscriber::get_objs();
-        let mut subscriber_objs = subscriber_objs.write().expect("get subscriber objs failed");
-        let subscriber = Arc::new(Self {
-            abstract_subscriber: abstract_subscriber::AbstractSubscriber::new(
-                subscriber_objs.len() as i32,
-            ),
-        });
-        subscriber_objs.push(subscriber.clone());
-        subscriber
+        abstract_subscriber::register(|id| Self {
+            abstract_subscriber: abstract_subscriber::AbstractSubscriber::new(id),
+        })
     }
 }
 
@@ -138,8 +120,8 @@ impl Display for TestSubscriber3 {
 }
 
 impl Subscriber for TestSubscriber3 {
-    fn id(&self) -> i32 {
-        self.abstract_subscriber.id()
+    fn super_reference(&self) -> &abstract_subscriber::AbstractSubscriber {
+        &self.abstract_subscriber
     }
 
     fn get_grp_prio_pair(&self, channel: &str) -> Option<GrpPrioPair> {
@@ -171,15 +153,9 @@ struct TestSubscriber4 {
 
 impl TestSubscriber4 {
     pub fn new() -> Arc<Self> {
-        let subscriber_objs = abstract_subscriber::get_objs();
-        let mut subscriber_objs = subscriber_objs.write().expect("get subscriber objs failed");
-        let subscriber = Arc::new(Self {
-            abstract_subscriber: abstract_subscriber::AbstractSubscriber::new(
-                subscriber_objs.len() as i32,
-            ),
-        });
-        subscriber_objs.push(subscriber.clone());
-        subscriber
+        abstract_subscriber::register(|id| Self {
+            abstract_subscriber: abstract_subscriber::AbstractSubscriber::new(id),
+        })
     }
 }
 
@@ -190,8 +166,8 @@ impl Display for TestSubscriber4 {
 }
 
 impl Subscriber for TestSubscriber4 {
-    fn id(&self) -> i32 {
-        self.abstract_subscriber.id()
+    fn super_reference(&self) -> &abstract_subscriber::AbstractSubscriber {
+        &self.abstract_subscriber
     }
 
     fn get_grp_prio_pair(&self, channel: &str) -> Option<GrpPrioPair> {
@@ -229,16 +205,83 @@ impl Subscriber for TestSubscriber4 {
     }
 }
 
+struct TestInterceptor {
+    abstract_subscriber: abstract_subscriber::AbstractSubscriber,
+}
+
+impl TestInterceptor {
+    pub fn new() -> Arc<Self> {
+        abstract_subscriber::register(|id| Self {
+            abstract_subscriber: abstract_subscriber::AbstractSubscriber::new(id),
+        })
+    }
+}
+
+impl Display for TestInterceptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", type_name::<Self>())
+    }
+}
+
+impl Subscriber for TestInterceptor {
+    fn super_reference(&self) -> &abstract_subscriber::AbstractSubscriber {
+        &self.abstract_subscriber
+    }
+
+    /// drop message "drop", rewrite message "rewrite", pass on others
+    fn intercept(&self, channel: SyncString, msg: SyncString) -> Interception {
+        println!(
+            "{} intercepted message: {} from channel: {}",
+            self.get_name(),
+            msg,
+            channel
+        );
+        match msg.as_str() {
+            "drop" => Interception::Drop,
+            "rewrite" => Interception::Rewrite(Arc::new(String::from("rewritten"))),
+            _ => Interception::Pass,
+        }
+    }
+}
+
+struct TestRecorder {
+    abstract_subscriber: abstract_subscriber::AbstractSubscriber,
+    received: Mutex<Vec<String>>,
+}
+
+impl TestRecorder {
+    pub fn new() -> Arc<Self> {
+        abstract_subscriber::register(|id| Self {
+            abstract_subscriber: abstract_subscriber::AbstractSubscriber::new(id),
+            received: Mutex::new(Vec::new()),
+        })
+    }
+}
+
+impl Display for TestRecorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", type_name::<Self>())
+    }
+}
+
+impl Subscriber for TestRecorder {
+    fn super_reference(&self) -> &abstract_subscriber::AbstractSubscriber {
+        &self.abstract_subscriber
+    }
+
+    fn on_message(&self, _channel: SyncString, msg: SyncString) {
+        self.received.lock().unwrap().push(msg.to_string());
+    }
+}
+
 #[test]
 fn test_pubsub_simple() {
-    let channel_1 = channel::get_channel("channel_1");
     let subscriber_1 = TestSubscriber1::new();
     subscriber_1.subscribe("channel_1", None, None);
     let subscriber_2 = TestSubscriber2::new();
     subscriber_2.subscribe("channel_1", None, None);
     let subscriber_3 = TestSubscriber3::new();
-    let pair = channel_1
-        .get_grp_prio_pair(subscriber_2.id())
+    let pair = channel::get_grp_prio_pair("channel_1", subscriber_2.id())
         .expect("get grp prio pair failed");
     subscriber_3.subscribe("channel_1", Some(pair.grp_id), Some(pair.priority_id + 1));
 
@@ -249,19 +292,16 @@ fn test_pubsub_simple() {
 
 #[test]
 fn test_pubsub_hard() {
-    let channel_1 = channel::get_channel("channel_1");
     let subscriber_1 = TestSubscriber1::new();
     subscriber_1.subscribe("channel_1", None, None);
     let subscriber_2 = TestSubscriber2::new();
     subscriber_2.subscribe("channel_1", None, None);
     let subscriber_3 = TestSubscriber3::new();
-    let pair = channel_1
-        .get_grp_prio_pair(subscriber_2.id())
+    let pair = channel::get_grp_prio_pair("channel_1", subscriber_2.id())
         .expect("get grp prio pair failed");
     subscriber_3.subscribe("channel_1", Some(pair.grp_id), Some(pair.priority_id + 1));
     let subscriber_4 = TestSubscriber4::new();
-    let pair = channel_1
-        .get_grp_prio_pair(subscriber_3.id())
+    let pair = channel::get_grp_prio_pair("channel_1", subscriber_3.id())
         .expect("get grp prio pair failed");
     subscriber_4.subscribe("channel_1", Some(pair.grp_id), Some(pair.priority_id));
 
@@ -280,23 +320,19 @@ fn test_pubsub_hard() {
 
 #[test]
 fn test_pub_with_mutil_channel() {
-    let channel_1 = channel::get_channel("channel_1");
     let subscriber_1 = TestSubscriber1::new();
     subscriber_1.subscribe("channel_1", None, None);
     let subscriber_2 = TestSubscriber2::new();
     subscriber_2.subscribe("channel_1", None, None);
     let subscriber_3 = TestSubscriber3::new();
-    let pair = channel_1
-        .get_grp_prio_pair(subscriber_2.id())
+    let pair = channel::get_grp_prio_pair("channel_1", subscriber_2.id())
         .expect("get grp prio pair failed");
     subscriber_3.subscribe("channel_1", Some(pair.grp_id), Some(pair.priority_id + 1));
     let subscriber_4 = TestSubscriber4::new();
-    let pair = channel_1
-        .get_grp_prio_pair(subscriber_3.id())
+    let pair = channel::get_grp_prio_pair("channel_1", subscriber_3.id())
         .expect("get grp prio pair failed");
     subscriber_4.subscribe("channel_1", Some(pair.grp_id), Some(pair.priority_id));
 
-    let _channel_2 = channel::get_channel("channel_2");
     subscriber_1.subscribe("channel_2", None, None);
     subscriber_2.subscribe("channel_2", None, None);
     subscriber_3.subscribe("channel_2", None, None);
@@ -315,3 +351,157 @@ fn test_pub_with_mutil_channel() {
 
     thread::sleep(std::time::Duration::from_secs(1));
 }
+
+#[test]
+fn test_deregister() {
+    let subscriber_1 = TestSubscriber1::new();
+    subscriber_1.subscribe("channel_3", None, None);
+    subscriber_1.subscribe("channel_4", None, None);
+    let id = subscriber_1.id();
+    assert!(abstract_subscriber::deregister(id));
+    assert!(channel::get_grp_prio_pair("channel_3", id).is_none());
+    assert!(channel::get_grp_prio_pair("channel_4", id).is_none());
+    assert!(abstract_subscriber::get_subscriber(id).is_none());
+
+    // index is reused with a new generation, stale id is rejected
+    let subscriber_2 = TestSubscriber2::new();
+    println!("{} -> {}", id, subscriber_2.id());
+    assert!(!abstract_subscriber::deregister(id));
+    assert!(abstract_subscriber::get_subscriber(subscriber_2.id()).is_some());
+    publisher::publish("channel_3", None, None, Arc::new(String::from("hello")));
+    assert!(abstract_subscriber::deregister(subscriber_2.id()));
+}
+
+#[test]
+fn test_pattern_subscribe() {
+    let recorder_1 = TestRecorder::new();
+    recorder_1.subscribe("pattern_*", None, None);
+    // subscriber of both pattern and channel receives message once
+    let recorder_2 = TestRecorder::new();
+    recorder_2.subscribe("pattern_*", None, None);
+    recorder_2.subscribe("pattern_1", None, None);
+    assert!(channel::get_grp_prio_pair("pattern_*", recorder_1.id()).is_some());
+
+    // channel created after subscribing is matched too
+    // message is the name of its channel
+    for channel in ["pattern_1", "pattern_2", "channel_5"] {
+        publisher::publish(channel, None, None, Arc::new(String::from(channel)));
+    }
+
+    thread::sleep(std::time::Duration::from_secs(1));
+    for recorder in [&recorder_1, &recorder_2] {
+        let received = recorder.received.lock().unwrap().clone();
+        println!("{} received: {:?}", recorder.id(), received);
+        assert_eq!(received, vec!["pattern_1", "pattern_2"]);
+    }
+}
+
+#[test]
+fn test_subscribe_with_retained() {
+    publisher::retain("channel_6", Arc::new(String::from("retained")));
+    publisher::publish("channel_6", None, None, Arc::new(String::from("hello")));
+    // retained message is received at once, without waiting for next publish
+    let recorder_1 = TestRecorder::new();
+    recorder_1.subscribe_with_retained("channel_6", None, None);
+    // nothing is retained in an empty channel
+    let recorder_2 = TestRecorder::new();
+    recorder_2.subscribe_with_retained("channel_8", None, None);
+    let subscriber_3 = TestSubscriber2::new();
+    subscriber_3.subscribe_with_retained("channel_*", None, None);
+    let retained = channel::get_channel("channel_6")
+        .get_retained(None)
+        .expect("get retained failed");
+    assert_eq!(retained.msg.as_str(), "retained");
+
+    thread::sleep(std::time::Duration::from_secs(1));
+    assert_eq!(*recorder_1.received.lock().unwrap(), vec!["retained"]);
+    assert!(recorder_2.received.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_interceptor() {
+    let recorder = TestRecorder::new();
+    recorder.subscribe("channel_7", None, None);
+    let pair =
+        channel::get_grp_prio_pair("channel_7", recorder.id()).expect("get grp prio pair failed");
+    // logger and validator are in front of recorder, validator first
+    let logger = TestSubscriber1::new();
+    logger.subscribe_as_interceptor("channel_7", Some(pair.grp_id), Some(pair.priority_id + 1));
+    let validator = TestInterceptor::new();
+    validator.subscribe_as_interceptor("channel_7", Some(pair.grp_id), Some(pair.priority_id + 2));
+
+    for msg in ["hello", "drop", "rewrite"] {
+        publisher::publish("channel_7", None, None, Arc::new(String::from(msg)));
+    }
+
+    thread::sleep(std::time::Duration::from_secs(1));
+    let received = recorder.received.lock().unwrap().clone();
+    println!("recorder received: {:?}", received);
+    assert_eq!(received, vec!["hello", "rewritten"]);
+
+    // subscribing normally leaves interception mode, recorder is cut off again
+    validator.subscribe("channel_7", Some(pair.grp_id), Some(pair.priority_id + 2));
+    publisher::publish("channel_7", None, None, Arc::new(String::from("hello")));
+    thread::sleep(std::time::Duration::from_secs(1));
+    assert_eq!(recorder.received.lock().unwrap().len(), 2);
+}
+
+#[test]
+fn test_interceptors_at_same_priority() {
+    let recorder = TestRecorder::new();
+    recorder.subscribe("channel_9", None, None);
+    let pair =
+        channel::get_grp_prio_pair("channel_9", recorder.id()).expect("get grp prio pair failed");
+    // a priority holds one interceptor, so recorder receives a message once
+    let validator = TestInterceptor::new();
+    assert!(validator.subscribe_as_interceptor(
+        "channel_9",
+        Some(pair.grp_id),
+        Some(pair.priority_id + 1)
+    ));
+    let logger = TestSubscriber1::new();
+    assert!(!logger.subscribe_as_interceptor(
+        "channel_9",
+        Some(pair.grp_id),
+        Some(pair.priority_id + 1)
+    ));
+    assert!(logger.get_grp_prio_pair("channel_9").is_none());
+
+    publisher::publish("channel_9", None, None, Arc::new(String::from("rewrite")));
+    // nothing is below the lowest priority
+    publisher::pass_on(
+        "channel_9",
+        GrpPrioPair::new(pair.grp_id, PrioId::MIN),
+        Arc::new(String::from("hello")),
+    );
+
+    thread::sleep(std::time::Duration::from_secs(1));
+    assert_eq!(*recorder.received.lock().unwrap(), vec!["rewritten"]);
+}
+
+#[test]
+fn test_interceptor_at_lowest_priority() {
+    // validator is the lowest of its group, message stops at it
+    let validator = TestInterceptor::new();
+    assert!(validator.subscribe_as_interceptor("channel_10", None, Some(PrioId::MIN + 1)));
+    let pair =
+        channel::get_grp_prio_pair("channel_10", validator.id()).expect("get grp prio pair failed");
+    // recorder at the lowest priority of another group is reached through its interceptor
+    let recorder = TestRecorder::new();
+    recorder.subscribe("channel_10", None, Some(PrioId::MIN));
+    let recorder_pair =
+        channel::get_grp_prio_pair("channel_10", recorder.id()).expect("get grp prio pair failed");
+    assert_ne!(recorder_pair.grp_id, pair.grp_id);
+    let logger = TestInterceptor::new();
+    assert!(logger.subscribe_as_interceptor(
+        "channel_10",
+        Some(recorder_pair.grp_id),
+        Some(PrioId::MIN + 1)
+    ));
+
+    publisher::publish("channel_10", None, None, Arc::new(String::from("rewrite")));
+    publisher::pass_on("channel_10", pair, Arc::new(String::from("hello")));
+
+    thread::sleep(std::time::Duration::from_secs(1));
+    assert_eq!(*recorder.received.lock().unwrap(), vec!["rewritten"]);
+}
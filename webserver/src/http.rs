@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::io::{BufRead, Read, Write};
+
+use strum_macros::{Display, EnumString};
+
+use crate::sse::SseWriter;
+use crate::WebError;
+
+/// max length of request line and each header line
+const MAX_LINE_LEN: usize = 8192;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display, EnumString)]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum Method {
+    Get,
+    Head,
+    Post,
+    Put,
+    Delete,
+    Options,
+}
+
+/// Request is a parsed http request
+/// body is ignored, every endpoint is a query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    method: Method,
+    path: String,
+    query: HashMap<String, String>,
+    headers: HashMap<String, String>,
+}
+
+/// decode %XX and '+' of a query component
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => match s
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                Some(b) => {
+                    decoded.push(b);
+                    i += 2;
+                }
+                None => decoded.push(b'%'),
+            },
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+fn read_line<R: BufRead>(reader: &mut R) -> Result<String, WebError> {
+    let mut line = String::new();
+    let len = Read::take(&mut *reader, MAX_LINE_LEN as u64).read_line(&mut line)?;
+    if len == 0 {
+        return Err(WebError::ParseRequestError("connection closed".to_string()));
+    }
+    if !line.ends_with('\n') {
+        return Err(WebError::ParseRequestError("line too long".to_string()));
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+impl Request {
+    /// parse request line and headers
+    pub fn parse(reader: &mut impl BufRead) -> Result<Self, WebError> {
+        let line = read_line(reader)?;
+        let mut parts = line.split_whitespace();
+        let (method, target) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/") => {
+                (method, target)
+            }
+            _ => {
+                return Err(WebError::ParseRequestError(format!(
+                    "invalid request line: {}",
+                    line
+                )))
+            }
+        };
+        let method = method
+            .parse()
+            .map_err(|_| WebError::ParseRequestError(format!("unknown method: {}", method)))?;
+
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let query = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (percent_decode(key), percent_decode(value))
+            })
+            .collect();
+
+        let mut headers = HashMap::new();
+        loop {
+            let line = read_line(reader)?;
+            if line.is_empty() {
+                break;
+            }
+            if let Some((key, value)) = line.split_once(':') {
+                headers.insert(key.trim().to_ascii_lowercase(), value.trim().to_string());
+            }
+        }
+
+        Ok(Self {
+            method,
+            path: percent_decode(path),
+            query,
+            headers,
+        })
+    }
+
+    //getter
+    pub fn get_method(&self) -> Method {
+        self.method
+    }
+
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn get_query(&self, key: &str) -> Option<&str> {
+        self.query.get(key).map(String::as_str)
+    }
+
+    /// get header, name is case insensitive
+    pub fn get_header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+}
+
+/// StreamFn writes body of a streaming response until it returns
+pub type StreamFn = Box<dyn FnOnce(&mut dyn Write) -> std::io::Result<()> + Send>;
+
+/// Body is either complete or written by a stream fn
+pub enum Body {
+    Full(String),
+    Stream(StreamFn),
+}
+
+impl Debug for Body {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Body::Full(body) => write!(f, "Full({:?})", body),
+            Body::Stream(_) => write!(f, "Stream"),
+        }
+    }
+}
+
+/// Response is an http response, connection is closed after it is sent
+#[derive(Debug)]
+pub struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: Body,
+}
+
+/// reason phrase of status code
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+impl Response {
+    pub fn new(status: u16, content_type: &'static str, body: String) -> Self {
+        Self {
+            status,
+            content_type,
+            body: Body::Full(body),
+        }
+    }
+
+    /// server-sent events response
+    /// events are written by f until it returns or fails, e.g. when client is gone
+    pub fn event_stream(f: impl FnOnce(SseWriter) -> std::io::Result<()> + Send + 'static) -> Self {
+        Self {
+            status: 200,
+            content_type: "text/event-stream; charset=utf-8",
+            body: Body::Stream(Box::new(move |writer| f(SseWriter::new(writer)))),
+        }
+    }
+
+    pub fn json(body: String) -> Self {
+        Self::new(200, "application/json; charset=utf-8", body)
+    }
+
+    pub fn html(body: String) -> Self {
+        Self::new(200, "text/html; charset=utf-8", body)
+    }
+
+    /// error response with a json body {"error": msg}
+    pub fn error(status: u16, msg: &str) -> Self {
+        let mut body = String::from(r#"{"error":"#);
+        body.push_str(&serde_json::Value::String(msg.to_string()).to_string());
+        body.push('}');
+        Self::new(status, "application/json; charset=utf-8", body)
+    }
+
+    pub fn not_found(path: &str) -> Self {
+        Self::error(404, &format!("{} not found", path))
+    }
+
+    //getter
+    pub fn get_status(&self) -> u16 {
+        self.status
+    }
+
+    /// get body, none for a streaming response
+    pub fn get_body(&self) -> Option<&str> {
+        match &self.body {
+            Body::Full(body) => Some(body),
+            Body::Stream(_) => None,
+        }
+    }
+
+    /// write status line, headers and body
+    /// body is not written for HEAD,
+    /// a streaming body has no content length and ends when connection is closed
+    pub fn write_to(self, writer: &mut impl Write, with_body: bool) -> std::io::Result<()> {
+        write!(
+            writer,
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\n",
+            self.status,
+            reason(self.status),
+            self.content_type
+        )?;
+        if let Body::Full(body) = &self.body {
+            write!(writer, "Content-Length: {}\r\n", body.len())?;
+        }
+        write!(
+            writer,
+            "Cache-Control: no-cache\r\nConnection: close\r\n\r\n"
+        )?;
+        match self.body {
+            Body::Full(body) if with_body => writer.write_all(body.as_bytes())?,
+            Body::Stream(f) if with_body => {
+                writer.flush()?;
+                f(writer)?;
+            }
+            _ => {}
+        }
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_request() {
+        let raw = "GET /api/sensors?sensor_name=Yellow%20Car&field=speed HTTP/1.1\r\nHost: localhost\r\nAccept: */*\r\n\r\n";
+        let request = Request::parse(&mut BufReader::new(raw.as_bytes())).unwrap();
+        println!("{:?}", request);
+        assert_eq!(request.get_method(), Method::Get);
+        assert_eq!(request.get_path(), "/api/sensors");
+        assert_eq!(request.get_query("sensor_name"), Some("Yellow Car"));
+        assert_eq!(request.get_query("field"), Some("speed"));
+        assert_eq!(request.get_header("HOST"), Some("localhost"));
+
+        assert!(Request::parse(&mut BufReader::new("GET /\r\n\r\n".as_bytes())).is_err());
+        assert!(
+            Request::parse(&mut BufReader::new("FETCH / HTTP/1.1\r\n\r\n".as_bytes())).is_err()
+        );
+    }
+
+    #[test]
+    fn test_write_response() {
+        let mut buf = Vec::new();
+        Response::error(404, "a \"b\"")
+            .write_to(&mut buf, true)
+            .unwrap();
+        let raw = String::from_utf8(buf).unwrap();
+        println!("{}", raw);
+        assert!(raw.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(raw.ends_with(r#"{"error":"a \"b\""}"#));
+
+        let mut buf = Vec::new();
+        Response::event_stream(|mut sse| sse.send(None, "1"))
+            .write_to(&mut buf, true)
+            .unwrap();
+        let raw = String::from_utf8(buf).unwrap();
+        println!("{}", raw);
+        assert!(!raw.contains("Content-Length"));
+        assert!(raw.ends_with("\r\n\r\ndata: 1\n\n"));
+    }
+}
@@ -1,14 +1,17 @@
-pub fn add(left: usize, right: usize) -> usize {
-    left + right
-}
+//! webserver is a tiny http server without async runtime.
+//! platform starts it to serve its dashboard and json api,
+//! routes are registered by platform so that this crate does not depend on it
+
+use thiserror::Error;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+pub mod http;
+pub mod server;
+pub mod sse;
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
+#[derive(Error, Debug)]
+pub enum WebError {
+    #[error("io err: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("parse request err: {0}")]
+    ParseRequestError(String),
 }
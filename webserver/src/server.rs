@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::io::BufReader;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use log::{info, trace, warn};
+
+use crate::http::{Method, Request, Response};
+use crate::WebError;
+
+pub type Handler = Arc<dyn Fn(&Request) -> Response + Send + Sync>;
+pub type SyncWebServer = Arc<WebServer>;
+
+/// a connection sending nothing for this long is closed
+const READ_TIMEOUT_MS: u64 = 5000;
+/// a write blocked for this long fails
+const WRITE_TIMEOUT_MS: u64 = 5000;
+
+/// WebServer is a tiny http/1.1 server.
+/// handlers are routed by exact path and only serve GET and HEAD,
+/// every connection is handled in its own thread and closed after one response,
+/// so a streaming response holds only its own thread
+pub struct WebServer {
+    listener: TcpListener,
+    running: AtomicBool,
+    routes: HashMap<String, Handler>,
+}
+
+impl WebServer {
+    /// bind
+    /// port 0 binds a random port, see local_addr
+    pub fn bind(port: u16) -> Result<Self, WebError> {
+        Ok(Self {
+            listener: TcpListener::bind(("0.0.0.0", port))?,
+            running: AtomicBool::new(true),
+            routes: HashMap::new(),
+        })
+    }
+
+    /// add a handler of path
+    pub fn route(
+        mut self,
+        path: &str,
+        handler: impl Fn(&Request) -> Response + Send + Sync + 'static,
+    ) -> Self {
+        self.routes.insert(path.to_string(), Arc::new(handler));
+        self
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.listener.local_addr().expect("get local addr fail")
+    }
+
+    /// run
+    /// accept connections until stop is called
+    pub fn run(self: &Arc<Self>) {
+        info!("web server listen on {}", self.local_addr());
+        for stream in self.listener.incoming() {
+            if !self.running.load(Ordering::SeqCst) {
+                break;
+            }
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    warn!("web server accept fail: {}", e);
+                    continue;
+                }
+            };
+            let server = self.clone();
+            thread::spawn(move || server.handle_connection(stream));
+        }
+        info!("web server stop");
+    }
+
+    /// stop
+    /// stop accepting new connections and let run return
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+        // wake up the blocking accept in run
+        let _ = TcpStream::connect(("127.0.0.1", self.local_addr().port()));
+    }
+
+    /// dispatch request to handler of its path
+    pub fn dispatch(&self, request: &Request) -> Response {
+        if !matches!(request.get_method(), Method::Get | Method::Head) {
+            return Response::error(405, &format!("{} is not allowed", request.get_method()));
+        }
+        match self.routes.get(request.get_path()) {
+            Some(handler) => handler(request),
+            None => Response::not_found(request.get_path()),
+        }
+    }
+
+    fn handle_connection(&self, stream: TcpStream) {
+        let _ = stream.set_read_timeout(Some(Duration::from_millis(READ_TIMEOUT_MS)));
+        // a client not reading a stream is dropped instead of blocking its writer forever
+        let _ = stream.set_write_timeout(Some(Duration::from_millis(WRITE_TIMEOUT_MS)));
+        let peer = stream.peer_addr().ok();
+        let mut reader = BufReader::new(&stream);
+        let (response, with_body) = match Request::parse(&mut reader) {
+            Ok(request) => {
+                trace!(
+                    "web request from {:?}: {} {}",
+                    peer,
+                    request.get_method(),
+                    request.get_path()
+                );
+                (
+                    self.dispatch(&request),
+                    request.get_method() != Method::Head,
+                )
+            }
+            Err(e) => {
+                trace!("web request from {:?} is invalid: {}", peer, e);
+                (Response::error(400, &e.to_string()), true)
+            }
+        };
+        if let Err(e) = response.write_to(&mut &stream, with_body) {
+            trace!("web response to {:?} fail: {}", peer, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+
+    use super::*;
+
+    fn get(port: u16, target: &str) -> String {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", target).unwrap();
+        let mut raw = String::new();
+        stream.read_to_string(&mut raw).unwrap();
+        raw
+    }
+
+    #[test]
+    fn test_web_server() {
+        let server = Arc::new(WebServer::bind(0).unwrap().route("/hello", |request| {
+            Response::json(format!(
+                r#"{{"hello":"{}"}}"#,
+                request.get_query("name").unwrap_or("world")
+            ))
+        }));
+        let port = server.local_addr().port();
+        let handle = {
+            let server = server.clone();
+            thread::spawn(move || server.run())
+        };
+
+        let raw = get(port, "/hello?name=rust");
+        println!("{}", raw);
+        assert!(raw.starts_with("HTTP/1.1 200 OK"));
+        assert!(raw.ends_with(r#"{"hello":"rust"}"#));
+        assert!(get(port, "/bye").starts_with("HTTP/1.1 404"));
+
+        server.stop();
+        handle.join().unwrap();
+    }
+}
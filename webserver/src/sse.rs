@@ -0,0 +1,52 @@
+use std::io::Write;
+
+/// SseWriter writes server-sent events to a streaming response
+pub struct SseWriter<'a> {
+    writer: &'a mut dyn Write,
+}
+
+impl<'a> SseWriter<'a> {
+    pub fn new(writer: &'a mut dyn Write) -> Self {
+        Self { writer }
+    }
+
+    /// send an event
+    /// data of several lines is sent as several data fields
+    pub fn send(&mut self, event: Option<&str>, data: &str) -> std::io::Result<()> {
+        if let Some(event) = event {
+            writeln!(self.writer, "event: {}", event)?;
+        }
+        for line in data.lines() {
+            writeln!(self.writer, "data: {}", line)?;
+        }
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()
+    }
+
+    /// send a comment
+    /// it is ignored by client, used as keepalive so that a closed connection is found
+    pub fn comment(&mut self, text: &str) -> std::io::Result<()> {
+        write!(self.writer, ": {}\n\n", text)?;
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_writer() {
+        let mut buf = Vec::new();
+        let mut sse = SseWriter::new(&mut buf);
+        sse.send(Some("sensor_data"), "{\"a\":1}\n{\"b\":2}")
+            .unwrap();
+        sse.comment("keepalive").unwrap();
+        let raw = String::from_utf8(buf).unwrap();
+        println!("{}", raw);
+        assert_eq!(
+            raw,
+            "event: sensor_data\ndata: {\"a\":1}\ndata: {\"b\":2}\n\n: keepalive\n\n"
+        );
+    }
+}
@@ -1,62 +1,49 @@
-use std::fs::File;
+use std::sync::atomic::{AtomicU32, Ordering};
 
 use env_logger::Builder;
+use serde_json::json;
 
-use common::socket::cmd_message::CmdMessage;
 use common::structs::resource_config::ResourceConfig;
-use wrapper::wrapper_remote_connector::WRAPPER_REMOTE_CONNECTOR;
+use common::structs::sensor_data::SensorData;
+use wrapper::wrapper_runner::{run, Wrapper};
 
-fn main() {
-    Builder::new().parse_filters("trace").init();
-
-    let config_file = "resources/config/wrapper/green_car.json".to_string();
-    let config: ResourceConfig =
-        serde_json::from_reader(File::open(config_file).expect("open file fail"))
-            .expect("parse json fail");
-
-    if WRAPPER_REMOTE_CONNECTOR.register("127.0.0.1", 9091, config) {
-        let mut cnt = 0;
-        let mut flag = true;
-
-        loop {
-            flag = ((cnt % 20) == 0) != flag;
-            cnt = cnt + 1;
+/// GreenCar switches between two positions every 20 requests
+struct GreenCar {
+    cnt: AtomicU32,
+}
 
-            match WRAPPER_REMOTE_CONNECTOR.recv() {
-                Some(recv) => {
-                    if recv.cmd.expect("cmd is none").eq("sensory_request") {
-                        let data = match flag {
-                            true => serde_json::json!(
-                                {
-                                    "speed": 10.0,
-                                    "longitude": 20.0,
-                                    "latitude": 30.0,
-                                }
-                            ),
-                            false => serde_json::json!(
-                                {
-                                    "speed": 20.0,
-                                    "longitude": 30.0,
-                                    "latitude": 10.0,
-                                }
-                            ),
-                        };
+impl Wrapper for GreenCar {
+    fn sense(&self) -> SensorData {
+        let cnt = self.cnt.fetch_add(1, Ordering::SeqCst);
+        let values = if (cnt / 20).is_multiple_of(2) {
+            vec![json!(10.0), json!(20.0), json!(30.0)]
+        } else {
+            vec![json!(20.0), json!(30.0), json!(10.0)]
+        };
+        SensorData::new_with_default_type(
+            vec![
+                "speed".to_string(),
+                "longitude".to_string(),
+                "latitude".to_string(),
+            ],
+            values,
+        )
+    }
+}
 
-                        let ret: CmdMessage =
-                            CmdMessage::new(Some("sensory_back".to_string()), Some(data));
+fn main() {
+    Builder::new().parse_filters("trace").init();
 
-                        WRAPPER_REMOTE_CONNECTOR
-                            .send(&serde_json::to_string(&ret).expect("to string fail"));
-                        println!(
-                            "[Wrapper]: {}",
-                            serde_json::to_string(&ret).expect("to string fail")
-                        );
-                    }
-                }
-                None => {
-                    panic!("recv error")
-                }
-            }
+    let config = match ResourceConfig::from_file("resources/config/wrapper/green_car.json") {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
         }
-    }
+    };
+
+    let green_car = GreenCar {
+        cnt: AtomicU32::new(0),
+    };
+    run(&green_car, "127.0.0.1", 9091, config);
 }
@@ -0,0 +1,46 @@
+use std::sync::RwLock;
+
+use env_logger::Builder;
+use serde_json::json;
+
+use common::structs::enumeration::resource_type::ResourceType;
+use common::structs::resource_config::ResourceConfig;
+use common::structs::sensor_data::SensorData;
+use wrapper::wrapper_runner::{run, Wrapper};
+
+/// BlueCar is a hybrid resource, apps read its speed as a sensor and set it as an actor
+struct BlueCar {
+    speed: RwLock<f64>,
+}
+
+impl Wrapper for BlueCar {
+    fn sense(&self) -> SensorData {
+        SensorData::new_with_one_field_with_default_type(
+            "speed".to_string(),
+            json!(*self.speed.read().expect("read speed fail")),
+        )
+    }
+
+    fn act(&self, action: &str) -> Result<(), String> {
+        let speed = action
+            .parse::<f64>()
+            .map_err(|e| format!("action {} is not a speed: {}", action, e))?;
+        *self.speed.write().expect("write speed fail") = speed;
+        Ok(())
+    }
+}
+
+fn main() {
+    Builder::new().parse_filters("trace").init();
+
+    let config = ResourceConfig::new(
+        Some("BlueCar".to_string()),
+        ResourceType::Hybrid,
+        Some(vec!["speed".to_string()]),
+    );
+
+    let blue_car = BlueCar {
+        speed: RwLock::new(0.0),
+    };
+    run(&blue_car, "127.0.0.1", 9091, config);
+}
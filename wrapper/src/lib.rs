@@ -1,3 +1,5 @@
 /// wrapper provides a connection interface for tcp connections.
 /// it's used for sensor and actor to connect to platform.
 pub mod wrapper_remote_connector;
+/// wrapper_runner registers a device integration and replies requests of platform for it.
+pub mod wrapper_runner;
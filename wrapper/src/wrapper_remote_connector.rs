@@ -1,15 +1,23 @@
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock, Weak};
+use std::thread;
 
-use log::info;
+use log::{error, info, warn};
 use once_cell::sync::Lazy;
 use serde_json::value::Index;
 
 use common::socket::cmd_message::CmdMessage;
+use common::socket::framing::{Framing, FRAME_VERSION};
 use common::socket::tcp::TCP;
 use common::structs::resource_config::ResourceConfig;
 
-use crate::wrapper_remote_connector::wrapper_remote_connector_tcp::RwLockOptionWrapperRemoteConnectorTCP;
+use crate::wrapper_remote_connector::reconnect::{ConnectionState, ReconnectPolicy, StateCallback};
+use crate::wrapper_remote_connector::wrapper_remote_connector_tcp::{
+    RwLockOptionWrapperRemoteConnectorTCP, WrapperRemoteConnectorTCP,
+};
 
+pub mod reconnect;
 pub mod wrapper_remote_connector_tcp;
 
 pub type WeakWrapperRemoteConnector = Weak<WrapperRemoteConnector>;
@@ -21,6 +29,13 @@ pub type RwLockOptionSyncWrapperString = RwLock<Option<Arc<WrapperString>>>;
 pub struct WrapperRemoteConnector {
     wrapper_name: RwLockOptionSyncWrapperString,
     tcp: RwLockOptionWrapperRemoteConnectorTCP,
+    /// platform address and config, kept to register again after reconnect
+    addr: RwLock<Option<(String, u16)>>,
+    resource_config: RwLock<Option<ResourceConfig>>,
+    reconnect_policy: RwLock<ReconnectPolicy>,
+    state_callback: RwLock<Option<StateCallback>>,
+    /// set by shutdown, so that a closed connection is not reconnected
+    closed: AtomicBool,
 }
 
 impl WrapperRemoteConnector {
@@ -30,55 +45,109 @@ impl WrapperRemoteConnector {
         Self {
             wrapper_name: RwLock::new(None),
             tcp: RwLock::new(None),
+            addr: RwLock::new(None),
+            resource_config: RwLock::new(None),
+            reconnect_policy: RwLock::new(ReconnectPolicy::default()),
+            state_callback: RwLock::new(None),
+            closed: AtomicBool::new(false),
         }
     }
 
-    /// recv    ///  from tcp
-    /// transfer it to cmd_message
-    /// return cmd_message
-    pub fn recv(&self) -> Option<CmdMessage> {
-        if let Some(recv) = self
-            .tcp
+    /// set reconnect policy
+    /// it is used by the next reconnection
+    pub fn set_reconnect_policy(&self, reconnect_policy: ReconnectPolicy) {
+        *self
+            .reconnect_policy
+            .write()
+            .expect("write reconnect policy fail") = reconnect_policy;
+    }
+
+    /// set state callback
+    /// it is called in the thread of recv when connection state changes
+    pub fn set_state_callback(
+        &self,
+        state_callback: impl Fn(ConnectionState) + Send + Sync + 'static,
+    ) {
+        self.state_callback
+            .write()
+            .expect("write state callback fail")
+            .replace(Box::new(state_callback));
+    }
+
+    fn notify(&self, state: ConnectionState) {
+        info!(
+            "[{}]: connection state -> {:?}",
+            self.get_wrapper_name(),
+            state
+        );
+        if let Some(state_callback) = self
+            .state_callback
             .read()
-            .expect("read tcp fail")
+            .expect("read state callback fail")
             .as_ref()
-            .expect("tcp is none")
-            .recv()
         {
-            let cmd_message: CmdMessage =
-                serde_json::from_str(&recv).expect("parse cmd message fail");
+            state_callback(state);
+        }
+    }
+
+    fn get_wrapper_name(&self) -> SyncWrapperString {
+        self.wrapper_name
+            .read()
+            .expect("read wrapper name fail")
+            .as_ref()
+            .expect("wrapper name is none")
+            .clone()
+    }
 
-            if cmd_message
-                .cmd
+    /// recv    ///  from tcp
+    /// transfer it to cmd_message
+    /// return cmd_message
+    /// if connection is broken, reconnect and register again per reconnect policy,
+    /// return none only if connector is shut down or reconnection is given up
+    pub fn recv(&self) -> Option<CmdMessage> {
+        loop {
+            //guard is released before reconnect replaces tcp
+            let recv = self
+                .tcp
+                .read()
+                .expect("read tcp fail")
                 .as_ref()
-                .expect("cmd is none")
-                .eq_ignore_ascii_case("alive_request")
-                == false
-            {
-                info!(
-                    "[{}]: recv() -> {}",
-                    self.wrapper_name
-                        .read()
-                        .expect("read wrapper name fail")
-                        .as_ref()
-                        .expect("wrapper name is none"),
-                    recv
-                );
+                .expect("tcp is none")
+                .recv();
+            if let Some(recv) = recv {
+                //a malformed line, e.g. from a platform restarting, is skipped instead of panic
+                let cmd_message: CmdMessage = match serde_json::from_str(&recv) {
+                    Ok(cmd_message) => cmd_message,
+                    Err(e) => {
+                        warn!(
+                            "[{}]: skip invalid message {}: {}",
+                            self.get_wrapper_name(),
+                            recv,
+                            e
+                        );
+                        continue;
+                    }
+                };
+
+                if !cmd_message
+                    .cmd
+                    .as_deref()
+                    .is_some_and(|cmd| cmd.eq_ignore_ascii_case("alive_request"))
+                {
+                    info!("[{}]: recv() -> {}", self.get_wrapper_name(), recv);
+                }
+                return Some(cmd_message);
+            }
+            info!("[{}]: recv() -> None", self.get_wrapper_name());
+            if self.closed.load(Ordering::SeqCst) || !self.reconnect() {
+                return None;
             }
-            return Some(cmd_message);
         }
-        info!(
-            "[{}]: recv() -> None",
-            self.wrapper_name
-                .read()
-                .expect("read wrapper name fail")
-                .as_ref()
-                .expect("wrapper name is none")
-        );
-        None
     }
 
     /// send    ///  string to platform
+    /// a reply should echo id of its request, see CmdMessage::new_with_id,
+    /// so that platform can have several requests in flight
     pub fn send(&self, send: &str) {
         self.tcp
             .read()
@@ -103,7 +172,9 @@ impl WrapperRemoteConnector {
     }
 
     /// shutdown tcp
+    /// connection is not reconnected after it
     pub fn shutdown(&self) -> bool {
+        self.closed.store(true, Ordering::SeqCst);
         self.tcp
             .read()
             .expect("read tcp fail")
@@ -118,11 +189,13 @@ impl WrapperRemoteConnector {
                 .as_ref()
                 .expect("wrapper name is none")
         );
+        self.notify(ConnectionState::Closed);
         true
     }
 
     /// register to platform
     /// will set wrapper_name and tcp
+    /// address and resource_config are kept to register again after reconnect
     pub fn register(&self, ip_addr: &str, port: u16, resource_config: ResourceConfig) -> bool {
         //set wrapper_name
         let wrapper_name = resource_config
@@ -137,81 +210,147 @@ impl WrapperRemoteConnector {
 
         //todo: log set
 
+        self.addr
+            .write()
+            .expect("write addr fail")
+            .replace((ip_addr.to_string(), port));
+        self.resource_config
+            .write()
+            .expect("write resource config fail")
+            .replace(resource_config.clone());
+        self.closed.store(false, Ordering::SeqCst);
+
+        let state = self.connect_and_register();
+        info!(
+            "[{}]: register({}, {}, {}) -> {}",
+            self.get_wrapper_name(),
+            ip_addr,
+            port,
+            serde_json::to_string(&resource_config).expect("to string fail"),
+            state
+        );
+        if state {
+            self.notify(ConnectionState::Connected);
+        }
+        state
+    }
+
+    /// reconnect
+    /// connect and register again with backoff until it succeeds,
+    /// connector is shut down, or reconnect policy gives up
+    fn reconnect(&self) -> bool {
+        self.notify(ConnectionState::Disconnected);
+        let reconnect_policy = *self
+            .reconnect_policy
+            .read()
+            .expect("read reconnect policy fail");
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            if !reconnect_policy.allow(attempt) {
+                warn!(
+                    "[{}]: give up reconnection after {} attempts",
+                    self.get_wrapper_name(),
+                    attempt - 1
+                );
+                self.notify(ConnectionState::Closed);
+                return false;
+            }
+            self.notify(ConnectionState::Reconnecting(attempt));
+            thread::sleep(reconnect_policy.get_backoff(attempt));
+            if self.closed.load(Ordering::SeqCst) {
+                return false;
+            }
+            if self.connect_and_register() {
+                self.notify(ConnectionState::Connected);
+                return true;
+            }
+        }
+    }
+
+    /// connect to platform and send register with kept address and resource_config
+    /// tcp is replaced only if platform accepts it
+    fn connect_and_register(&self) -> bool {
+        let (ip_addr, port) = self
+            .addr
+            .read()
+            .expect("read addr fail")
+            .clone()
+            .expect("addr is none");
+        let resource_config = self
+            .resource_config
+            .read()
+            .expect("read resource config fail")
+            .clone()
+            .expect("resource config is none");
+
         //generate cmd_message
-        let cmd_message = CmdMessage::new(
+        //request length prefixed framing, platform replies without it if it does not support
+        let cmd_message = CmdMessage::new_with_framing(
             Some("register".to_string()),
             Some(serde_json::to_value(&resource_config).expect("to value fail")),
+            Some(FRAME_VERSION),
         );
-        let mut state: bool = false;
 
-        //由于err已经在内部处理，所以这里不需要处理err
         //get socket by ip_addr and port
-        //notion: 在java中，使用了try catch，但实际上没有处理err，所以这里直接使用expect
-        let socket = std::net::TcpStream::connect((ip_addr, port)).expect("connect fail");
-        //set tcp
-        let tcp = wrapper_remote_connector_tcp::WrapperRemoteConnectorTCP::new(
-            socket,
-            false,
-            self.wrapper_name
-                .read()
-                .expect("read wrapper name fail")
-                .as_ref()
-                .expect("wrapper name is none")
-                .clone(),
-        );
-        self.tcp.write().expect("write tcp fail").replace(tcp);
+        let socket = match TcpStream::connect((ip_addr.as_str(), port)) {
+            Ok(socket) => socket,
+            Err(e) => {
+                error!(
+                    "[{}]: connect {}:{} fail: {}",
+                    self.get_wrapper_name(),
+                    ip_addr,
+                    port,
+                    e
+                );
+                return false;
+            }
+        };
+        let tcp = WrapperRemoteConnectorTCP::new(socket, false, self.get_wrapper_name());
 
         //register
-        self.tcp
-            .read()
-            .expect("read tcp fail")
-            .as_ref()
-            .expect("tcp is none")
-            .send(&serde_json::to_string(&cmd_message).expect("to string fail"));
+        if !tcp.send(&serde_json::to_string(&cmd_message).expect("to string fail")) {
+            return false;
+        }
 
         //recv
-        if let Some(recv) = self
-            .tcp
-            .read()
-            .expect("read tcp fail")
-            .as_ref()
-            .expect("tcp is none")
-            .recv()
-        {
-            let recv_cmd_message: CmdMessage =
-                serde_json::from_str(&recv).expect("parse cmd message fail");
-            if recv_cmd_message
-                .cmd
-                .as_ref()
-                .expect("cmd is none")
-                .eq("register_back")
-            {
+        let mut state = false;
+        //an invalid register back fails the register, so that it is retried per reconnect policy
+        let recv_cmd_message = tcp.recv().and_then(|recv| {
+            serde_json::from_str::<CmdMessage>(&recv)
+                .map_err(|e| {
+                    error!(
+                        "[{}]: invalid register back {}: {}",
+                        self.get_wrapper_name(),
+                        recv,
+                        e
+                    )
+                })
+                .ok()
+        });
+        if let Some(recv_cmd_message) = recv_cmd_message {
+            if recv_cmd_message.cmd.as_deref() == Some("register_back") {
                 //only message is "true" ignore case
                 //todo: check it whether one is true
-                match recv_cmd_message.message.as_ref() {
-                    Some(message) => {
-                        state = message
-                            .as_str()
-                            .expect("message as str fail")
-                            .eq_ignore_ascii_case("true");
-                    }
-                    None => state = false,
+                state = recv_cmd_message
+                    .message
+                    .as_ref()
+                    .and_then(|message| message.as_str())
+                    .is_some_and(|message| message.eq_ignore_ascii_case("true"));
+                //platform tells why it refuses, e.g. problems of resource config
+                if let Some(e) = recv_cmd_message.error.as_ref() {
+                    error!("[{}]: register is refused: {}", self.get_wrapper_name(), e);
                 }
+                //register back is the last line, frames start after it
+                tcp.set_framing(Framing::from_version(recv_cmd_message.framing));
             }
         }
 
-        info!(
-            "[{}]: register({}, {}, {}) -> {}",
-            self.wrapper_name
-                .read()
-                .expect("read wrapper name fail")
-                .as_ref()
-                .expect("wrapper name is none"),
-            ip_addr,
-            port,
-            serde_json::to_string(&resource_config).expect("to string fail"),
-            state
-        );
+        if state {
+            self.tcp.write().expect("write tcp fail").replace(tcp);
+        } else {
+            tcp.close();
+        }
         state
     }
 }
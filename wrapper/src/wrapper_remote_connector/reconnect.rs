@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+/// ConnectionState is passed to state callback of wrapper when connection changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// registered to platform
+    Connected,
+    /// connection to platform is broken
+    Disconnected,
+    /// the nth attempt to connect and register again, starting from 1
+    Reconnecting(u32),
+    /// reconnection is given up, or connector is shut down
+    Closed,
+}
+
+pub type StateCallback = Box<dyn Fn(ConnectionState) + Send + Sync>;
+
+/// ReconnectPolicy describes how wrapper connects to platform again after connection is broken.
+/// backoff before each attempt doubles from initial_backoff_ms up to max_backoff_ms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    /// none retries forever
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff_ms: 500,
+            max_backoff_ms: 30_000,
+            max_attempts: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    pub fn new(initial_backoff_ms: u64, max_backoff_ms: u64, max_attempts: Option<u32>) -> Self {
+        Self {
+            initial_backoff_ms,
+            max_backoff_ms,
+            max_attempts,
+        }
+    }
+
+    /// backoff before the nth attempt, starting from 1
+    pub fn get_backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u64 << attempt.saturating_sub(1).min(16);
+        Duration::from_millis(
+            self.initial_backoff_ms
+                .saturating_mul(factor)
+                .min(self.max_backoff_ms),
+        )
+    }
+
+    /// whether the nth attempt is allowed
+    pub fn allow(&self, attempt: u32) -> bool {
+        self.max_attempts
+            .is_none_or(|max_attempts| attempt <= max_attempts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconnect_policy() {
+        let policy = ReconnectPolicy::new(100, 500, Some(5));
+        assert_eq!(policy.get_backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.get_backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.get_backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.get_backoff(100), Duration::from_millis(500));
+        assert!(policy.allow(5));
+        assert!(!policy.allow(6));
+        assert!(ReconnectPolicy::default().allow(u32::MAX));
+    }
+}
@@ -0,0 +1,130 @@
+use log::{error, warn};
+use serde_json::json;
+
+use common::socket::cmd_message::CmdMessage;
+use common::structs::resource_config::ResourceConfig;
+use common::structs::sensor_data::SensorData;
+
+use crate::wrapper_remote_connector::WRAPPER_REMOTE_CONNECTOR;
+
+/// Wrapper is a device integration
+/// a sensor implements sense, an actor implements act, a hybrid resource implements both.
+/// run it by run, which registers it and replies requests of platform
+pub trait Wrapper: Send + Sync {
+    /// data for a sensory_request, its fields should be fields in resource config
+    /// default is an error, so that platform does not take it for real data
+    fn sense(&self) -> SensorData {
+        SensorData::new_error("sense not supported")
+    }
+
+    /// perform an action_request
+    /// return err with reason if action is not done
+    fn act(&self, action: &str) -> Result<(), String> {
+        Err(format!("action {} is not supported", action))
+    }
+}
+
+/// handle a request of platform
+/// return reply with id of request, none if request needs no reply
+pub fn handle(wrapper: &impl Wrapper, request: &CmdMessage) -> Option<CmdMessage> {
+    let cmd = request.cmd.as_deref().unwrap_or_default();
+    if cmd.eq_ignore_ascii_case("sensory_request") {
+        let data = serde_json::to_value(wrapper.sense()).expect("to value fail");
+        Some(CmdMessage::new_with_id(
+            Some("sensory_back".to_string()),
+            Some(data),
+            request.id,
+        ))
+    } else if cmd.eq_ignore_ascii_case("action_request") {
+        //action is sent as a json string, other values are passed as json text
+        let action = match request.message.as_ref() {
+            Some(serde_json::Value::String(action)) => action.clone(),
+            Some(action) => action.to_string(),
+            None => String::new(),
+        };
+        let done = match wrapper.act(&action) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("action {} fail: {}", action, e);
+                false
+            }
+        };
+        Some(CmdMessage::new_with_id(
+            Some("action_back".to_string()),
+            Some(json!(done.to_string())),
+            request.id,
+        ))
+    } else if cmd.eq_ignore_ascii_case("alive_request") {
+        //platform only checks that alive_request is sent, a reply would be taken as a request reply
+        None
+    } else {
+        warn!("unknown request: {}", request);
+        None
+    }
+}
+
+/// run
+/// register wrapper to platform and reply its requests,
+/// connection is resumed by WRAPPER_REMOTE_CONNECTOR if it is broken
+/// return false if register fails, true after connector is shut down or gives up reconnection
+pub fn run(
+    wrapper: &impl Wrapper,
+    ip_addr: &str,
+    port: u16,
+    resource_config: ResourceConfig,
+) -> bool {
+    if !WRAPPER_REMOTE_CONNECTOR.register(ip_addr, port, resource_config) {
+        error!("register to {}:{} fail", ip_addr, port);
+        return false;
+    }
+    while let Some(request) = WRAPPER_REMOTE_CONNECTOR.recv() {
+        if let Some(reply) = handle(wrapper, &request) {
+            WRAPPER_REMOTE_CONNECTOR.send(&serde_json::to_string(&reply).expect("to string fail"));
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    struct Lamp;
+
+    impl Wrapper for Lamp {
+        fn sense(&self) -> SensorData {
+            SensorData::new_with_one_field_with_default_type("light".to_string(), json!(1))
+        }
+
+        fn act(&self, action: &str) -> Result<(), String> {
+            match action {
+                "on" | "off" => Ok(()),
+                _ => Err(format!("unknown action {}", action)),
+            }
+        }
+    }
+
+    fn request(cmd: &str, message: Option<serde_json::Value>) -> CmdMessage {
+        CmdMessage::new_with_id(Some(cmd.to_string()), message, Some(7))
+    }
+
+    #[test]
+    fn test_handle() {
+        let reply = handle(&Lamp, &request("sensory_request", None)).unwrap();
+        println!("{}", reply);
+        assert_eq!(reply.cmd.as_deref(), Some("sensory_back"));
+        assert_eq!(reply.message, Some(json!({"light": 1})));
+        assert_eq!(reply.id, Some(7));
+
+        let reply = handle(&Lamp, &request("action_request", Some(json!("on")))).unwrap();
+        assert_eq!(reply.cmd.as_deref(), Some("action_back"));
+        assert_eq!(reply.message, Some(json!("true")));
+        let reply = handle(&Lamp, &request("action_request", Some(json!("blink")))).unwrap();
+        assert_eq!(reply.message, Some(json!("false")));
+
+        assert!(handle(&Lamp, &request("alive_request", None)).is_none());
+        assert!(handle(&Lamp, &request("unknown", None)).is_none());
+    }
+}